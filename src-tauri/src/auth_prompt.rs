@@ -0,0 +1,281 @@
+//! Keyboard-Interactive Authentication Module for Packet
+//!
+//! Routes the prompts of SSH keyboard-interactive authentication (RFC 4256)
+//! to the user. Devices behind AAA, RADIUS or TACACS+, OTP tokens and
+//! password-expiry dialogs all use it instead of plain password auth.
+//!
+//! # Flow
+//!
+//! Each round of prompts from the server is emitted as an `ssh-auth-prompt`
+//! event. The connection waits until the frontend answers with
+//! `respond_auth_prompt`, then sends the answers back; the server may follow
+//! up with more rounds (a token after the password, a new password after an
+//! expired one). Rounds without prompts only carry informational text; it
+//! is emitted the same way with an empty `prompts` list for the frontend to
+//! show, and the round is answered immediately.
+//!
+//! Cancelling a prompt, or leaving it unanswered, fails the authentication.
+//! The daemon relays the prompts of sessions it opens for a Packet window;
+//! ones it opens on its own have no window to ask and fail on the first
+//! prompt.
+
+use crate::daemon::{can_prompt, remote_answer, SessionCommand};
+use crate::session::EventSink;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ssh2::{KeyboardInteractivePrompt, Prompt};
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long a connection waits for the user to answer a round of prompts,
+/// long enough to fetch a token
+const AUTH_PROMPT_TIMEOUT_SECS: u64 = 300;
+
+/// Delivers the answers to one round of prompts; `None` cancels the login
+type AnswerSender = mpsc::SyncSender<Option<Vec<String>>>;
+
+/// Connections waiting for answers, by request ID
+static PENDING_ANSWERS: Lazy<Mutex<HashMap<String, AnswerSender>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A single question from the server
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuthPromptField {
+    pub text: String,
+    /// Whether the answer may be shown while typed (false for secrets)
+    pub echo: bool,
+}
+
+/// Event payload carrying one round of keyboard-interactive prompts
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuthPrompt {
+    #[serde(rename = "requestId")]
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Title sent by the server for this round, often empty
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPromptField>,
+    /// 1 for the first round, counting up on multi-round logins; notices
+    /// (no prompts) carry the number of the last round
+    pub round: u32,
+}
+
+/// Asks the user for the answers to each round of prompts
+struct UiPrompter<'a> {
+    sink: &'a EventSink,
+    session_id: &'a str,
+    host: &'a str,
+    port: u16,
+    username: &'a str,
+    round: u32,
+    /// Informational rounds shown so far
+    notices: u32,
+    /// Why the user could not answer; once set, later rounds get no answers
+    failure: Option<String>,
+}
+
+impl UiPrompter<'_> {
+    fn ask(&mut self, name: &str, instructions: &str, prompts: &[Prompt<'_>]) -> Vec<String> {
+        if self.failure.is_some() {
+            return Vec::new();
+        }
+
+        // Informational round, e.g. "Your password has expired"
+        if prompts.is_empty() {
+            if !instructions.is_empty() {
+                println!("[SSH] Server message: {}", instructions.trim_end());
+                if !matches!(self.sink, EventSink::Daemon) || can_prompt() {
+                    self.notices += 1;
+                    let request_id = format!("{}-notice{}", self.session_id, self.notices);
+                    self.sink.emit(
+                        "ssh-auth-prompt",
+                        self.session_id,
+                        self.event(request_id, name, instructions, &[]),
+                    );
+                }
+            }
+            return Vec::new();
+        }
+
+        if matches!(self.sink, EventSink::Daemon) && !can_prompt() {
+            self.failure = Some(format!(
+                "{}@{}:{} asked for keyboard-interactive input ({}), which needs the Packet window",
+                self.username,
+                self.host,
+                self.port,
+                prompts[0].text.trim_end()
+            ));
+            return Vec::new();
+        }
+
+        self.round += 1;
+        let request_id = format!("{}-{}", self.session_id, self.round);
+        let (answer, response) = mpsc::sync_channel(1);
+        PENDING_ANSWERS.lock().insert(request_id.clone(), answer);
+        self.sink.emit(
+            "ssh-auth-prompt",
+            self.session_id,
+            self.event(request_id.clone(), name, instructions, prompts),
+        );
+        println!(
+            "[SSH] Waiting for {} keyboard-interactive answer(s) for {}",
+            prompts.len(),
+            self.session_id
+        );
+
+        let answers = response.recv_timeout(Duration::from_secs(AUTH_PROMPT_TIMEOUT_SECS));
+        PENDING_ANSWERS.lock().remove(&request_id);
+
+        match answers {
+            Ok(Some(answers)) => answers,
+            Ok(None) => {
+                self.failure = Some("Authentication cancelled".to_string());
+                Vec::new()
+            }
+            Err(_) => {
+                self.failure = Some("Timed out waiting for authentication input".to_string());
+                Vec::new()
+            }
+        }
+    }
+
+    /// The `ssh-auth-prompt` payload for a round
+    fn event(
+        &self,
+        request_id: String,
+        name: &str,
+        instructions: &str,
+        prompts: &[Prompt<'_>],
+    ) -> AuthPrompt {
+        AuthPrompt {
+            request_id,
+            host: self.host.to_string(),
+            port: self.port,
+            username: self.username.to_string(),
+            name: name.to_string(),
+            instructions: instructions.to_string(),
+            prompts: prompts
+                .iter()
+                .map(|prompt| AuthPromptField {
+                    text: prompt.text.to_string(),
+                    echo: prompt.echo,
+                })
+                .collect(),
+            round: self.round,
+        }
+    }
+}
+
+impl KeyboardInteractivePrompt for UiPrompter<'_> {
+    fn prompt<'a>(
+        &mut self,
+        username: &str,
+        instructions: &str,
+        prompts: &[Prompt<'a>],
+    ) -> Vec<String> {
+        // libssh2 passes the round's name in the username slot
+        self.ask(username, instructions, prompts)
+    }
+}
+
+/// Runs keyboard-interactive authentication, asking the user through `sink`.
+///
+/// `session_id` is the ID of the session being opened; each round's request
+/// ID is derived from it.
+pub fn userauth_interactive(
+    session: &ssh2::Session,
+    sink: &EventSink,
+    session_id: &str,
+    host: &str,
+    port: u16,
+    username: &str,
+) -> Result<(), String> {
+    let mut prompter = UiPrompter {
+        sink,
+        session_id,
+        host,
+        port,
+        username,
+        round: 0,
+        notices: 0,
+        failure: None,
+    };
+
+    let result = session.userauth_keyboard_interactive(username, &mut prompter);
+    match (result, prompter.failure) {
+        (_, Some(failure)) => Err(failure),
+        (Ok(()), None) => Ok(()),
+        (Err(e), None) => Err(format!("Keyboard-interactive authentication failed: {}", e)),
+    }
+}
+
+/// Answers a pending `ssh-auth-prompt` (here or in the session daemon), one
+/// answer per prompt in order. `None` cancels the login.
+#[tauri::command]
+pub fn respond_auth_prompt(request_id: String, answers: Option<Vec<String>>) -> Result<(), String> {
+    let Some(answer) = PENDING_ANSWERS.lock().remove(&request_id) else {
+        return remote_answer(SessionCommand::RespondAuthPrompt {
+            request_id,
+            answers,
+        })
+        .unwrap_or_else(|| {
+            Err("No authentication prompt is pending for this request".to_string())
+        });
+    };
+    answer
+        .send(answers)
+        .map_err(|_| "Connection is no longer waiting for an answer".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    fn prompter(sink: &EventSink) -> UiPrompter<'_> {
+        UiPrompter {
+            sink,
+            session_id: "test",
+            host: "r1.lab",
+            port: 22,
+            username: "admin",
+            round: 0,
+            notices: 0,
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn test_info_round_needs_no_answers() {
+        let sink = EventSink::Daemon;
+        let mut prompter = prompter(&sink);
+        assert!(prompter
+            .ask("", "Your password has expired", &[])
+            .is_empty());
+        assert!(prompter.failure.is_none());
+        assert_eq!(prompter.round, 0);
+    }
+
+    #[test]
+    fn test_daemon_cannot_prompt() {
+        let sink = EventSink::Daemon;
+        let mut prompter = prompter(&sink);
+        let prompts = [Prompt {
+            text: Cow::Borrowed("Password: "),
+            echo: false,
+        }];
+        assert!(prompter.ask("", "", &prompts).is_empty());
+        let failure = prompter.failure.clone().unwrap();
+        assert!(failure.contains("admin@r1.lab:22"));
+        assert!(failure.contains("Password:"));
+
+        // Later rounds are refused without asking again
+        assert!(prompter.ask("", "", &prompts).is_empty());
+        assert_eq!(prompter.failure, Some(failure));
+    }
+}
@@ -0,0 +1,201 @@
+//! Broadcast Module for Packet
+//!
+//! Fans a single input out to many sessions in one IPC call, and gives the
+//! caller a per-session report of what succeeded. Writing to a session only
+//! queues the input for its I/O task, so targets are written one after the
+//! other without a slow or dead device delaying the rest. Telnet commands
+//! such as BREAK can be broadcast the same way.
+
+use crate::session::sessions;
+use crate::telnet::{send_command_to, TelnetCommand};
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a broadcast write for a single session
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BroadcastResult {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub success: bool,
+    /// Error message when the write failed
+    pub error: Option<String>,
+}
+
+/// Writes the same bytes to every listed session.
+///
+/// Results are returned in the same order as `session_ids`.
+pub fn broadcast_bytes(session_ids: &[String], data: &[u8]) -> Vec<BroadcastResult> {
+    broadcast_each(session_ids, |session_id| sessions().write(session_id, data))
+}
+
+/// Runs `send` for every listed session and collects the outcomes
+fn broadcast_each<F>(session_ids: &[String], send: F) -> Vec<BroadcastResult>
+where
+    F: Fn(&str) -> Result<(), String>,
+{
+    session_ids
+        .iter()
+        .map(|session_id| {
+            let outcome = send(session_id);
+            BroadcastResult {
+                session_id: session_id.clone(),
+                success: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect()
+}
+
+/// Writes input data to several sessions at once, regardless of connection type.
+///
+/// # Arguments
+/// * `session_ids` - Backend session IDs to write to
+/// * `data` - The input to send to each session
+///
+/// # Returns
+/// One result per session, in the order given
+#[tauri::command]
+pub fn broadcast_write(session_ids: Vec<String>, data: String) -> Vec<BroadcastResult> {
+    let results = broadcast_bytes(&session_ids, data.as_bytes());
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    if failed > 0 {
+        eprintln!("[Broadcast] {} of {} writes failed", failed, results.len());
+    }
+
+    results
+}
+
+/// Sends a telnet command (e.g. BRK) to several telnet sessions at once.
+///
+/// Sessions that are not telnet sessions are reported as failed. Runs on
+/// the blocking pool, since sessions hosted by the session daemon wait for
+/// its reply.
+///
+/// # Returns
+/// One result per session, in the order given
+#[tauri::command]
+pub async fn broadcast_telnet_command(
+    session_ids: Vec<String>,
+    command: TelnetCommand,
+) -> Result<Vec<BroadcastResult>, String> {
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        broadcast_each(&session_ids, |session_id| {
+            send_command_to(session_id, command)
+        })
+    })
+    .await
+    .map_err(|e| format!("Broadcast task failed: {}", e))?;
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    if failed > 0 {
+        eprintln!(
+            "[Broadcast] {} of {} telnet commands failed",
+            failed,
+            results.len()
+        );
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{Session, SessionInfo, SessionKind, SessionStatus};
+    use parking_lot::Mutex;
+    use std::any::Any;
+    use std::sync::Arc;
+
+    /// Records what it is sent, or fails like a session whose connection dropped
+    struct TestSession {
+        id: String,
+        dead: bool,
+        written: Mutex<Vec<u8>>,
+    }
+
+    impl Session for TestSession {
+        fn write(&self, data: &[u8]) -> Result<(), String> {
+            if self.dead {
+                return Err("Session is disconnected".to_string());
+            }
+            self.written.lock().extend_from_slice(data);
+            Ok(())
+        }
+
+        fn resize(&self, _cols: u16, _rows: u16) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn close(&self) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn info(&self) -> SessionInfo {
+            SessionInfo {
+                session_id: self.id.clone(),
+                kind: SessionKind::Raw,
+                label: "test".to_string(),
+                status: self.status(),
+            }
+        }
+
+        fn status(&self) -> SessionStatus {
+            if self.dead {
+                SessionStatus::Disconnected
+            } else {
+                SessionStatus::Connected
+            }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn register(id: &str, dead: bool) -> Arc<TestSession> {
+        let session = Arc::new(TestSession {
+            id: id.to_string(),
+            dead,
+            written: Mutex::new(Vec::new()),
+        });
+        sessions().insert(id, session.clone());
+        session
+    }
+
+    #[test]
+    fn test_broadcast_results() {
+        let r1 = register("broadcast-test-r1", false);
+        register("broadcast-test-dead", true);
+        let r2 = register("broadcast-test-r2", false);
+
+        let ids: Vec<String> = [
+            "broadcast-test-r2",
+            "broadcast-test-unknown",
+            "broadcast-test-dead",
+            "broadcast-test-r1",
+        ]
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+        let results = broadcast_bytes(&ids, b"show ip int brief\r");
+        for id in &ids {
+            sessions().unregister(id);
+        }
+
+        // One result per session, in the order given
+        let order: Vec<&str> = results.iter().map(|r| r.session_id.as_str()).collect();
+        assert_eq!(order, ids);
+
+        assert!(results[0].success && results[0].error.is_none());
+        assert!(results[3].success);
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("Session not found: broadcast-test-unknown")
+        );
+        assert!(!results[2].success);
+        assert_eq!(results[2].error.as_deref(), Some("Session is disconnected"));
+
+        assert_eq!(*r1.written.lock(), b"show ip int brief\r");
+        assert_eq!(*r2.written.lock(), b"show ip int brief\r");
+    }
+}
@@ -11,6 +11,8 @@
 //! ```
 //!
 //! This will launch Packet and automatically connect to the device.
+//!
+//! Add `--raw` for consoles that expose raw TCP instead of telnet.
 
 use clap::Parser;
 use once_cell::sync::OnceCell;
@@ -19,6 +21,9 @@ use serde::{Deserialize, Serialize};
 /// Global storage for CLI arguments (accessible from Tauri commands)
 pub static CLI_ARGS: OnceCell<Option<ConnectionArgs>> = OnceCell::new();
 
+/// Whether Packet was started as the headless session daemon
+static DAEMON_MODE: OnceCell<bool> = OnceCell::new();
+
 /// Command-line arguments for Packet
 #[derive(Parser, Debug, Clone)]
 #[command(name = "packet")]
@@ -39,9 +44,17 @@ pub struct Args {
     #[arg(short, long = "port")]
     pub port: Option<u16>,
 
+    /// Connect with raw TCP instead of telnet (no option negotiation)
+    #[arg(long = "raw")]
+    pub raw: bool,
+
     /// Legacy xfce4-terminal compatible: -e "telnet host port"
     #[arg(short = 'e', long = "execute")]
     pub execute: Option<String>,
+
+    /// Run as a headless session daemon (no window)
+    #[arg(long = "daemon")]
+    pub daemon: bool,
 }
 
 /// Parsed connection arguments to send to frontend
@@ -50,6 +63,9 @@ pub struct ConnectionArgs {
     pub name: String,
     pub host: String,
     pub port: u16,
+    /// Raw TCP instead of telnet
+    #[serde(default)]
+    pub raw: bool,
 }
 
 impl Args {
@@ -61,12 +77,14 @@ impl Args {
                 name: self.name.clone().unwrap_or_else(|| format!("{}:{}", host, port)),
                 host: host.clone(),
                 port,
+                raw: self.raw,
             });
         }
 
         // Try parsing -e "telnet host port" format (xfce4-terminal compatible)
         if let Some(execute) = &self.execute {
-            if let Some(conn) = Self::parse_execute_command(execute, self.name.clone()) {
+            if let Some(mut conn) = Self::parse_execute_command(execute, self.name.clone()) {
+                conn.raw |= self.raw;
                 return Some(conn);
             }
         }
@@ -75,17 +93,20 @@ impl Args {
     }
 
     /// Parse xfce4-terminal style execute command: "telnet host port"
+    /// ("nc host port" opens a raw TCP session)
     fn parse_execute_command(cmd: &str, name: Option<String>) -> Option<ConnectionArgs> {
         let parts: Vec<&str> = cmd.split_whitespace().collect();
         
-        // Expected format: "telnet host port" or just "host port"
-        if parts.len() >= 3 && parts[0].to_lowercase() == "telnet" {
+        // Expected format: "telnet host port", "nc host port" or just "host port"
+        let program = parts.first().map(|p| p.to_lowercase());
+        if parts.len() >= 3 && matches!(program.as_deref(), Some("telnet" | "nc" | "netcat")) {
             let host = parts[1].to_string();
             if let Ok(port) = parts[2].parse::<u16>() {
                 return Some(ConnectionArgs {
                     name: name.unwrap_or_else(|| format!("{}:{}", host, port)),
                     host,
                     port,
+                    raw: program.as_deref() != Some("telnet"),
                 });
             }
         } else if parts.len() >= 2 {
@@ -96,6 +117,7 @@ impl Args {
                     name: name.unwrap_or_else(|| format!("{}:{}", host, port)),
                     host,
                     port,
+                    raw: false,
                 });
             }
         }
@@ -110,15 +132,27 @@ pub fn init_cli() -> Option<ConnectionArgs> {
     let connection = args.parse_connection();
     
     if let Some(ref conn) = connection {
-        println!("[CLI] Connection request: {} -> {}:{}", conn.name, conn.host, conn.port);
+        println!(
+            "[CLI] Connection request: {} -> {}:{}{}",
+            conn.name,
+            conn.host,
+            conn.port,
+            if conn.raw { " (raw)" } else { "" }
+        );
     }
     
     // Store in global for later access
     let _ = CLI_ARGS.set(connection.clone());
+    let _ = DAEMON_MODE.set(args.daemon);
     
     connection
 }
 
+/// Returns true if `--daemon` was passed (valid after `init_cli`)
+pub fn daemon_mode() -> bool {
+    DAEMON_MODE.get().copied().unwrap_or(false)
+}
+
 /// Parse connection args from a vector of strings (for single-instance callback)
 /// This is used when a second instance tries to start and sends its args to the first instance
 pub fn parse_args_to_connection(args: &[String]) -> Option<ConnectionArgs> {
@@ -128,6 +162,7 @@ pub fn parse_args_to_connection(args: &[String]) -> Option<ConnectionArgs> {
     let mut host: Option<String> = None;
     let mut port: Option<u16> = None;
     let mut execute: Option<String> = None;
+    let mut raw = false;
     
     let mut iter = args.iter().skip(1).peekable(); // Skip program name
     
@@ -145,6 +180,9 @@ pub fn parse_args_to_connection(args: &[String]) -> Option<ConnectionArgs> {
             "-e" | "--execute" => {
                 execute = iter.next().cloned();
             }
+            "--raw" => {
+                raw = true;
+            }
             other => {
                 // Handle --name=value style
                 if let Some(val) = other.strip_prefix("--name=") {
@@ -164,12 +202,16 @@ pub fn parse_args_to_connection(args: &[String]) -> Option<ConnectionArgs> {
             name: name.unwrap_or_else(|| format!("{}:{}", h, p)),
             host: h.clone(),
             port: p,
+            raw,
         });
     }
     
     // Try parsing -e "telnet host port" format
     if let Some(exec) = execute {
-        return Args::parse_execute_command(&exec, name);
+        return Args::parse_execute_command(&exec, name).map(|mut conn| {
+            conn.raw |= raw;
+            conn
+        });
     }
     
     None
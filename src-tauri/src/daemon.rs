@@ -0,0 +1,1340 @@
+//! Session Daemon Module for Packet
+//!
+//! Runs the session backends in a headless process (`packet --daemon`) so
+//! telnet consoles, SSH sessions and shells survive closing the window,
+//! similar to tmux. The GUI talks to the daemon over a local Unix socket and
+//! can detach from sessions, list orphaned ones and reattach later.
+//!
+//! # Protocol
+//!
+//! Newline-delimited JSON over `$XDG_RUNTIME_DIR/packet/daemon.sock`:
+//! - Requests: `{"id": 1, "method": "resize", "params": {...}}`
+//! - Responses: `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}`
+//! - Events: `{"event": "telnet-output", "sessionId": "...", "payload": {...}}`
+//!
+//! Requests with ID 0 are notifications and get no response; keystrokes are
+//! sent that way (as base64, so any byte survives). `open` requests run on
+//! their own thread and may be answered after later requests, so responses
+//! are matched by ID, not by order.
+//!
+//! Events are only delivered to clients attached to that session. Sessions
+//! without an attached client keep running and are reported as orphaned.
+//! A client that cannot keep up (a write times out) is disconnected.
+//!
+//! While a session is being opened, the client that asked for it also gets
+//! its events, so host key and keyboard-interactive prompts reach the
+//! window; the answers come back as `command` requests, which also carry the
+//! transport-specific commands (telnet commands, serial settings, port
+//! forwards) for daemon sessions.
+//!
+//! # GUI Side
+//!
+//! Sessions reached through the daemon are registered in the local session
+//! manager as [`RemoteSession`]s, so the generic session commands, broadcast
+//! and logging work on them unchanged. Daemon events are re-emitted as the
+//! usual Tauri events (`pty-output`, `telnet-status`, ...).
+
+use crate::auth_prompt::respond_auth_prompt;
+use crate::forward::{
+    add_forward, add_ssh_forward, list_ssh_forwards, remove_ssh_forward, ForwardSpec,
+};
+use crate::jump::JumpHost;
+use crate::known_hosts::respond_host_key;
+use crate::output::{register_output_channel, send_to_channel};
+use crate::pty::{open_pty, PtyCommand};
+use crate::scrollback::get_scrollback;
+use crate::serial::{open_serial, serial_break, serial_configure, serial_status};
+use crate::session::{record_output, sessions, EventSink, Session, SessionInfo, SessionStatus};
+use crate::socket::{open_raw, open_unix_socket, RawOptions};
+use crate::ssh::{open_ssh, SshAuthMethod};
+use crate::telnet::{
+    open_telnet, open_telnet_unix, send_command_to, telnet_com_port_break,
+    telnet_com_port_configure, telnet_com_port_status, ComPortSettings, TelnetCommand,
+    TelnetOptions,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+
+/// How long the GUI waits for a daemon response (SSH handshakes can be slow)
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// How long to wait for a freshly started daemon to accept connections
+const STARTUP_TIMEOUT_MS: u64 = 3000;
+
+/// Write timeout for client sockets, so a stalled GUI cannot block the daemon
+const CLIENT_WRITE_TIMEOUT_SECS: u64 = 5;
+
+/// Request ID of notifications, which get no response
+const NOTIFICATION_ID: u64 = 0;
+
+/// A session to open inside the daemon
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OpenSession {
+    Pty {
+        cols: u16,
+        rows: u16,
+        #[serde(default)]
+        command: Option<PtyCommand>,
+    },
+    Telnet {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        cols: Option<u16>,
+        #[serde(default)]
+        rows: Option<u16>,
+        #[serde(default)]
+        options: TelnetOptions,
+        /// SSH bastions to tunnel through, first to last
+        #[serde(default, rename = "jumpHosts")]
+        jump_hosts: Vec<JumpHost>,
+    },
+    Ssh {
+        host: String,
+        /// Taken from ~/.ssh/config (or 22) when missing
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        username: Option<String>,
+        auth: SshAuthMethod,
+        cols: u32,
+        rows: u32,
+        /// Falls back to the ProxyJump from ~/.ssh/config when empty
+        #[serde(default, rename = "jumpHosts")]
+        jump_hosts: Vec<JumpHost>,
+        /// Port forwards to start once connected
+        #[serde(default)]
+        forwards: Vec<ForwardSpec>,
+    },
+    Serial {
+        path: String,
+        #[serde(default)]
+        settings: ComPortSettings,
+    },
+    Unix {
+        path: String,
+        /// Speak telnet on the socket instead of passing bytes through
+        #[serde(default)]
+        telnet: bool,
+        #[serde(default)]
+        cols: Option<u16>,
+        #[serde(default)]
+        rows: Option<u16>,
+        #[serde(default)]
+        options: TelnetOptions,
+    },
+    Raw {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        options: RawOptions,
+    },
+}
+
+/// Transport-specific requests for daemon sessions, and answers to the
+/// prompts of sessions being opened
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum SessionCommand {
+    Telnet {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        command: TelnetCommand,
+    },
+    ComPortConfigure {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        settings: ComPortSettings,
+    },
+    ComPortBreak {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "durationMs")]
+        duration_ms: Option<u64>,
+    },
+    ComPortStatus {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    SerialConfigure {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        settings: ComPortSettings,
+    },
+    SerialBreak {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "durationMs")]
+        duration_ms: Option<u64>,
+    },
+    SerialStatus {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    AddForward {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        forward: ForwardSpec,
+    },
+    RemoveForward {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "forwardId")]
+        forward_id: String,
+    },
+    ListForwards {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    RespondHostKey {
+        #[serde(rename = "requestId")]
+        request_id: String,
+        trust: bool,
+    },
+    RespondAuthPrompt {
+        #[serde(rename = "requestId")]
+        request_id: String,
+        answers: Option<Vec<String>>,
+    },
+}
+
+/// Requests the GUI sends to the daemon
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum DaemonRequest {
+    Open(OpenSession),
+    Command(SessionCommand),
+    Write {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        /// Input bytes, base64 encoded
+        data: String,
+    },
+    Resize {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Close {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    Attach {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    Detach {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    List,
+}
+
+/// A request tagged with the ID its response will carry
+#[derive(Serialize, Deserialize)]
+struct RequestEnvelope {
+    id: u64,
+    #[serde(flatten)]
+    request: DaemonRequest,
+}
+
+/// Messages the daemon sends to the GUI
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum DaemonMessage {
+    Response {
+        id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Event {
+        event: String,
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        payload: Value,
+    },
+}
+
+impl DaemonMessage {
+    fn response(id: u64, outcome: Result<Value, String>) -> Self {
+        match outcome {
+            Ok(result) => DaemonMessage::Response {
+                id,
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => DaemonMessage::Response {
+                id,
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Information about a session hosted by the daemon
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DaemonSessionInfo {
+    #[serde(flatten)]
+    pub session: SessionInfo,
+    /// Whether any GUI is currently attached; `false` means orphaned
+    pub attached: bool,
+}
+
+/// Reply to an attach request
+#[derive(Serialize, Deserialize)]
+struct AttachResult {
+    session: SessionInfo,
+    /// Output produced so far, for rehydrating the pane
+    scrollback: String,
+}
+
+/// Connection status event payload for the daemon link
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub connected: bool,
+}
+
+/// Returns the daemon socket path, creating its private directory
+fn socket_path() -> Result<PathBuf, String> {
+    let dir = match dirs::runtime_dir() {
+        Some(runtime) => runtime.join("packet"),
+        None => std::env::temp_dir().join(format!(
+            "packet-{}",
+            std::env::var("USER").unwrap_or_default()
+        )),
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create daemon directory: {}", e))?;
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("Failed to secure daemon directory: {}", e))?;
+    Ok(dir.join("daemon.sock"))
+}
+
+/// Serializes a message as one JSON line
+fn encode_line<T: Serialize>(message: &T) -> Result<Vec<u8>, String> {
+    let mut line =
+        serde_json::to_vec(message).map_err(|e| format!("Failed to encode message: {}", e))?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Writes one encoded line to a client socket
+fn write_line(writer: &Mutex<UnixStream>, line: &[u8]) -> Result<(), String> {
+    writer
+        .lock()
+        .write_all(line)
+        .map_err(|e| format!("Failed to send to daemon socket: {}", e))
+}
+
+/// Serializes a message and writes it to a client socket
+fn send_message<T: Serialize>(writer: &Mutex<UnixStream>, message: &T) -> Result<(), String> {
+    write_line(writer, &encode_line(message)?)
+}
+
+// ============================================================================
+// Daemon Side
+// ============================================================================
+
+/// Connected GUI clients and the sessions each one is attached to
+#[derive(Default)]
+struct DaemonState {
+    next_client: u64,
+    clients: HashMap<u64, Arc<Mutex<UnixStream>>>,
+    /// Maps session_id -> attached client IDs
+    attachments: HashMap<String, HashSet<u64>>,
+}
+
+static DAEMON_STATE: Lazy<Mutex<DaemonState>> = Lazy::new(|| Mutex::new(DaemonState::default()));
+
+thread_local! {
+    /// The client whose `open` request this thread is carrying out
+    static OPENING_CLIENT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Whether the current thread is opening a session for a GUI client, which
+/// can then answer the session's login prompts
+pub fn can_prompt() -> bool {
+    OPENING_CLIENT.get().is_some()
+}
+
+/// Runs the headless session daemon until the process is killed.
+pub fn run_daemon() -> Result<(), String> {
+    let path = socket_path()?;
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!(
+            "A session daemon is already listening on {}",
+            path.display()
+        ));
+    }
+    // Remove a stale socket left by a daemon that did not exit cleanly
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to bind '{}': {}", path.display(), e))?;
+    println!("[Daemon] Listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || serve_client(stream));
+            }
+            Err(e) => eprintln!("[Daemon] Accept failed: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Delivers a session event to every client attached to the session, and
+/// to the client opening it when called from the opening thread.
+///
+/// The writes happen after the state lock is released, so a slow client
+/// only delays its own events; a client whose write fails is dropped.
+pub fn forward_event<S: Serialize>(event: &str, session_id: &str, payload: S) {
+    let message = DaemonMessage::Event {
+        event: event.to_string(),
+        session_id: session_id.to_string(),
+        payload: serde_json::to_value(payload).unwrap_or_default(),
+    };
+
+    let writers: Vec<(u64, Arc<Mutex<UnixStream>>)> = {
+        let state = DAEMON_STATE.lock();
+        let mut client_ids: HashSet<u64> = state
+            .attachments
+            .get(session_id)
+            .cloned()
+            .unwrap_or_default();
+        client_ids.extend(OPENING_CLIENT.get());
+        client_ids
+            .into_iter()
+            .filter_map(|client_id| {
+                let writer = state.clients.get(&client_id)?;
+                Some((client_id, writer.clone()))
+            })
+            .collect()
+    };
+    if writers.is_empty() {
+        return;
+    }
+
+    let line = match encode_line(&message) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("[Daemon] {}", e);
+            return;
+        }
+    };
+    for (client_id, writer) in writers {
+        if let Err(e) = write_line(&writer, &line) {
+            eprintln!("[Daemon] Dropping client {}: {}", client_id, e);
+            drop_client(client_id);
+        }
+    }
+}
+
+/// Forgets a client and closes its socket; its sessions keep running
+fn drop_client(client_id: u64) {
+    let writer = {
+        let mut state = DAEMON_STATE.lock();
+        for client_ids in state.attachments.values_mut() {
+            client_ids.remove(&client_id);
+        }
+        state.clients.remove(&client_id)
+    };
+    // Also ends the client's request loop, which is reading the same socket
+    if let Some(writer) = writer {
+        let _ = writer.lock().shutdown(Shutdown::Both);
+    }
+}
+
+/// Sends the outcome of a request, unless it was a notification.
+///
+/// Returns false if the client can no longer be written to.
+fn reply(writer: &Mutex<UnixStream>, id: u64, outcome: Result<Value, String>) -> bool {
+    if id == NOTIFICATION_ID {
+        if let Err(e) = outcome {
+            eprintln!("[Daemon] Notification failed: {}", e);
+        }
+        return true;
+    }
+    send_message(writer, &DaemonMessage::response(id, outcome)).is_ok()
+}
+
+/// The `id` of a request line that did not parse as a request
+fn request_id(line: &str) -> Option<u64> {
+    serde_json::from_str::<Value>(line)
+        .ok()?
+        .get("id")?
+        .as_u64()
+}
+
+/// Handles one GUI connection until it disconnects
+fn serve_client(stream: UnixStream) {
+    let writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("[Daemon] Failed to clone client stream: {}", e);
+            return;
+        }
+    };
+    let _ = writer.set_write_timeout(Some(Duration::from_secs(CLIENT_WRITE_TIMEOUT_SECS)));
+    let writer = Arc::new(Mutex::new(writer));
+
+    let client_id = {
+        let mut state = DAEMON_STATE.lock();
+        state.next_client += 1;
+        let client_id = state.next_client;
+        state.clients.insert(client_id, writer.clone());
+        client_id
+    };
+    println!("[Daemon] Client {} connected", client_id);
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let RequestEnvelope { id, request } = match serde_json::from_str(&line) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                eprintln!("[Daemon] Invalid request from client {}: {}", client_id, e);
+                // Answer it if possible, so the client does not wait for a timeout
+                if let Some(id) = request_id(&line) {
+                    if !reply(&writer, id, Err(format!("Invalid request: {}", e))) {
+                        break;
+                    }
+                }
+                continue;
+            }
+        };
+
+        // Connecting can take a long time (handshakes, login prompts), and so
+        // can some commands; the client's other sessions must not wait for
+        // them. A prompt's answer arrives while its `open` is still running.
+        if let DaemonRequest::Open(_) | DaemonRequest::Command(_) = request {
+            let writer = writer.clone();
+            thread::spawn(move || {
+                if let DaemonRequest::Open(_) = request {
+                    OPENING_CLIENT.set(Some(client_id));
+                }
+                let outcome = handle_request(client_id, request);
+                reply(&writer, id, outcome);
+            });
+            continue;
+        }
+
+        let outcome = handle_request(client_id, request);
+        if !reply(&writer, id, outcome) {
+            break;
+        }
+    }
+
+    // Detach everything this client was attached to; the sessions keep running
+    drop_client(client_id);
+    println!("[Daemon] Client {} disconnected", client_id);
+}
+
+/// Starts the port forwards requested with an SSH session; there is no
+/// window to report failures to, so they are only logged
+fn start_forwards(session_id: &str, forwards: Vec<ForwardSpec>) {
+    for spec in forwards {
+        let label = spec.to_string();
+        match tauri::async_runtime::block_on(add_forward(session_id, spec)) {
+            Ok(info) => println!(
+                "[Daemon] Forwarding {} (port {}) for {}",
+                label, info.bound_port, session_id
+            ),
+            Err(e) => eprintln!(
+                "[Daemon] Forward {} failed for {}: {}",
+                label, session_id, e
+            ),
+        }
+    }
+}
+
+/// Marks a client as attached to a session, unless the client has
+/// disconnected in the meantime (the session is then left orphaned)
+fn attach(client_id: u64, session_id: &str) {
+    let mut state = DAEMON_STATE.lock();
+    if state.clients.contains_key(&client_id) {
+        state
+            .attachments
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(client_id);
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to encode result: {}", e))
+}
+
+/// Runs a transport command against the daemon's own sessions
+fn run_command(command: SessionCommand) -> Result<Value, String> {
+    match command {
+        SessionCommand::Telnet {
+            session_id,
+            command,
+        } => to_value(send_command_to(&session_id, command)?),
+        SessionCommand::ComPortConfigure {
+            session_id,
+            settings,
+        } => to_value(telnet_com_port_configure(session_id, settings)?),
+        SessionCommand::ComPortBreak {
+            session_id,
+            duration_ms,
+        } => to_value(telnet_com_port_break(session_id, duration_ms)?),
+        SessionCommand::ComPortStatus { session_id } => {
+            to_value(telnet_com_port_status(session_id)?)
+        }
+        SessionCommand::SerialConfigure {
+            session_id,
+            settings,
+        } => to_value(serial_configure(session_id, settings)?),
+        SessionCommand::SerialBreak {
+            session_id,
+            duration_ms,
+        } => to_value(serial_break(session_id, duration_ms)?),
+        SessionCommand::SerialStatus { session_id } => to_value(serial_status(session_id)?),
+        SessionCommand::AddForward {
+            session_id,
+            forward,
+        } => to_value(tauri::async_runtime::block_on(add_ssh_forward(
+            session_id, forward,
+        ))?),
+        SessionCommand::RemoveForward {
+            session_id,
+            forward_id,
+        } => to_value(tauri::async_runtime::block_on(remove_ssh_forward(
+            session_id, forward_id,
+        ))?),
+        SessionCommand::ListForwards { session_id } => to_value(list_ssh_forwards(session_id)?),
+        SessionCommand::RespondHostKey { request_id, trust } => {
+            to_value(respond_host_key(request_id, trust)?)
+        }
+        SessionCommand::RespondAuthPrompt {
+            request_id,
+            answers,
+        } => to_value(respond_auth_prompt(request_id, answers)?),
+    }
+}
+
+/// Executes a single client request
+fn handle_request(client_id: u64, request: DaemonRequest) -> Result<Value, String> {
+    match request {
+        DaemonRequest::Open(open) => {
+            let session_id = match open {
+                OpenSession::Pty {
+                    cols,
+                    rows,
+                    command,
+                } => open_pty(EventSink::Daemon, cols, rows, command, None),
+                OpenSession::Telnet {
+                    host,
+                    port,
+                    cols,
+                    rows,
+                    options,
+                    jump_hosts,
+                } => open_telnet(
+                    EventSink::Daemon,
+                    host,
+                    port,
+                    cols.zip(rows),
+                    options,
+                    jump_hosts,
+                    None,
+                ),
+                OpenSession::Ssh {
+                    host,
+                    port,
+                    username,
+                    auth,
+                    cols,
+                    rows,
+                    jump_hosts,
+                    forwards,
+                } => open_ssh(
+                    EventSink::Daemon,
+                    host,
+                    port,
+                    username,
+                    auth,
+                    jump_hosts,
+                    cols,
+                    rows,
+                    None,
+                )
+                .inspect(|session_id| start_forwards(session_id, forwards)),
+                OpenSession::Serial { path, settings } => {
+                    open_serial(EventSink::Daemon, path, settings, None)
+                }
+                OpenSession::Unix {
+                    path,
+                    telnet: true,
+                    cols,
+                    rows,
+                    options,
+                } => open_telnet_unix(EventSink::Daemon, path, cols.zip(rows), options, None),
+                OpenSession::Unix { path, .. } => open_unix_socket(EventSink::Daemon, path, None),
+                OpenSession::Raw {
+                    host,
+                    port,
+                    options,
+                } => open_raw(EventSink::Daemon, host, port, options, None),
+            }?;
+            attach(client_id, &session_id);
+            to_value(sessions().get(&session_id)?.info())
+        }
+        DaemonRequest::Command(command) => run_command(command),
+        DaemonRequest::Write { session_id, data } => {
+            let data = STANDARD
+                .decode(data)
+                .map_err(|e| format!("Invalid input encoding: {}", e))?;
+            sessions().write(&session_id, &data)?;
+            Ok(Value::Null)
+        }
+        DaemonRequest::Resize {
+            session_id,
+            cols,
+            rows,
+        } => {
+            sessions().resize(&session_id, cols, rows)?;
+            Ok(Value::Null)
+        }
+        DaemonRequest::Close { session_id } => {
+            DAEMON_STATE.lock().attachments.remove(&session_id);
+            sessions().close(&session_id)?;
+            Ok(Value::Null)
+        }
+        DaemonRequest::Attach { session_id } => {
+            let session = sessions().get(&session_id)?;
+            attach(client_id, &session_id);
+            to_value(AttachResult {
+                session: session.info(),
+                scrollback: get_scrollback(session_id, None),
+            })
+        }
+        DaemonRequest::Detach { session_id } => {
+            if let Some(client_ids) = DAEMON_STATE.lock().attachments.get_mut(&session_id) {
+                client_ids.remove(&client_id);
+            }
+            Ok(Value::Null)
+        }
+        DaemonRequest::List => {
+            let live = sessions().list();
+            let mut state = DAEMON_STATE.lock();
+            // Forget attachments of sessions that ended on their own
+            state
+                .attachments
+                .retain(|session_id, _| live.iter().any(|s| &s.session_id == session_id));
+
+            let list: Vec<DaemonSessionInfo> = live
+                .into_iter()
+                .map(|session| DaemonSessionInfo {
+                    attached: state
+                        .attachments
+                        .get(&session.session_id)
+                        .is_some_and(|client_ids| !client_ids.is_empty()),
+                    session,
+                })
+                .collect();
+            to_value(list)
+        }
+    }
+}
+
+// ============================================================================
+// GUI Side
+// ============================================================================
+
+/// The GUI's connection to the session daemon
+struct DaemonClient {
+    /// Encoded requests for the writer thread, so callers never block on the socket
+    outgoing: mpsc::Sender<Vec<u8>>,
+    next_id: AtomicU64,
+    /// Requests waiting for a response, keyed by request ID
+    pending: Mutex<HashMap<u64, mpsc::Sender<Result<Value, String>>>>,
+    /// Open and attach requests in flight, with the channel for the
+    /// session's output; the reader registers the session from the response
+    opening: Mutex<HashMap<u64, Option<Channel<String>>>>,
+}
+
+static DAEMON_CLIENT: Lazy<Mutex<Option<Arc<DaemonClient>>>> = Lazy::new(|| Mutex::new(None));
+
+impl DaemonClient {
+    /// Queues a request for the writer thread
+    fn send(&self, id: u64, request: DaemonRequest) -> Result<(), String> {
+        let line = encode_line(&RequestEnvelope { id, request })?;
+        self.outgoing
+            .send(line)
+            .map_err(|_| "Lost connection to session daemon".to_string())
+    }
+
+    /// Sends a request and waits for its response
+    fn request(&self, request: DaemonRequest) -> Result<Value, String> {
+        self.request_as(self.next_id.fetch_add(1, Ordering::Relaxed), request)
+    }
+
+    /// Sends an open or attach request; the session is registered locally
+    /// before any output that follows the response is handled
+    fn request_session(
+        &self,
+        request: DaemonRequest,
+        on_output: Option<Channel<String>>,
+    ) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.opening.lock().insert(id, on_output);
+        let result = self.request_as(id, request);
+        self.opening.lock().remove(&id);
+        result
+    }
+
+    fn request_as(&self, id: u64, request: DaemonRequest) -> Result<Value, String> {
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().insert(id, tx);
+
+        if let Err(e) = self.send(id, request) {
+            self.pending.lock().remove(&id);
+            return Err(e);
+        }
+
+        rx.recv_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .unwrap_or_else(|_| {
+                self.pending.lock().remove(&id);
+                Err("Session daemon did not respond".to_string())
+            })
+    }
+
+    /// Sends a request without waiting for it to be carried out
+    fn notify(&self, request: DaemonRequest) -> Result<(), String> {
+        self.send(NOTIFICATION_ID, request)
+    }
+}
+
+/// Returns the daemon connection, connecting on first use
+fn daemon_client(app: &AppHandle) -> Result<Arc<DaemonClient>, String> {
+    let mut slot = DAEMON_CLIENT.lock();
+    if let Some(client) = slot.as_ref() {
+        return Ok(client.clone());
+    }
+
+    let path = socket_path()?;
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| format!("Session daemon is not running ({}): {}", path.display(), e))?;
+    let reader = stream
+        .try_clone()
+        .map_err(|e| format!("Failed to clone daemon stream: {}", e))?;
+
+    let (outgoing, queue) = mpsc::channel();
+    let client = Arc::new(DaemonClient {
+        outgoing,
+        next_id: AtomicU64::new(NOTIFICATION_ID + 1),
+        pending: Mutex::new(HashMap::new()),
+        opening: Mutex::new(HashMap::new()),
+    });
+    *slot = Some(client.clone());
+    println!("[Daemon] Connected to session daemon at {}", path.display());
+
+    thread::spawn(move || write_daemon_messages(stream, queue));
+    let app = app.clone();
+    let reader_client = client.clone();
+    thread::spawn(move || read_daemon_messages(app, reader_client, reader));
+
+    Ok(client)
+}
+
+/// Sends queued requests until the client is dropped or the daemon goes away
+fn write_daemon_messages(mut stream: UnixStream, queue: mpsc::Receiver<Vec<u8>>) {
+    for line in queue {
+        if let Err(e) = stream.write_all(&line) {
+            eprintln!("[Daemon] Failed to send to session daemon: {}", e);
+            // The reader sees the socket close and cleans up
+            let _ = stream.shutdown(Shutdown::Both);
+            break;
+        }
+    }
+}
+
+/// Dispatches responses and re-emits events until the daemon goes away
+fn read_daemon_messages(app: AppHandle, client: Arc<DaemonClient>, stream: UnixStream) {
+    // The daemon attaches a session before answering the open (or attach)
+    // request, so its first output and status changes can arrive ahead of
+    // the response; they are held until the session is registered
+    let mut early_events: HashMap<String, Vec<(String, Value)>> = HashMap::new();
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        match serde_json::from_str::<DaemonMessage>(&line) {
+            Ok(DaemonMessage::Response { id, result, error }) => {
+                let opening = client.opening.lock().remove(&id);
+                if let (Some(on_output), Some(session)) =
+                    (opening, result.as_ref().and_then(opened_session))
+                {
+                    let session_id = register_remote(client.clone(), session, on_output);
+                    for (event, payload) in early_events.remove(&session_id).unwrap_or_default() {
+                        deliver_event(&app, event, &session_id, payload);
+                    }
+                }
+                if client.opening.lock().is_empty() {
+                    early_events.clear();
+                }
+
+                if let Some(tx) = client.pending.lock().remove(&id) {
+                    let _ = tx.send(match error {
+                        Some(error) => Err(error),
+                        None => Ok(result.unwrap_or(Value::Null)),
+                    });
+                }
+            }
+            Ok(DaemonMessage::Event {
+                event,
+                session_id,
+                payload,
+            }) => {
+                let session_event =
+                    payload.get("data").is_some() || payload.get("status").is_some();
+                if session_event
+                    && sessions().get(&session_id).is_err()
+                    && !client.opening.lock().is_empty()
+                {
+                    early_events
+                        .entry(session_id)
+                        .or_default()
+                        .push((event, payload));
+                    continue;
+                }
+                deliver_event(&app, event, &session_id, payload);
+            }
+            Err(e) => eprintln!("[Daemon] Invalid message from daemon: {}", e),
+        }
+    }
+
+    println!("[Daemon] Lost connection to session daemon");
+    {
+        let mut slot = DAEMON_CLIENT.lock();
+        if slot
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, &client))
+        {
+            *slot = None;
+        }
+    }
+    // Dropping the senders fails any request still waiting
+    client.pending.lock().clear();
+
+    for session in sessions().all() {
+        if let Some(remote) = session.as_any().downcast_ref::<RemoteSession>() {
+            sessions().unregister(&remote.info.session_id);
+        }
+    }
+    let _ = app.emit("daemon-status", DaemonStatus { connected: false });
+}
+
+/// Re-emits a daemon event for the webview, updating the remote session
+fn deliver_event(app: &AppHandle, event: String, session_id: &str, payload: Value) {
+    // Keep logging and scrollback working for remote sessions
+    if let Some(data) = payload.get("data").and_then(Value::as_str) {
+        record_output(session_id, data);
+        // Output goes straight to the pane's channel when it has one
+        if send_to_channel(session_id, data) {
+            return;
+        }
+    }
+    if let Some(status) = payload
+        .get("status")
+        .and_then(Value::as_str)
+        .and_then(remote_status)
+    {
+        if let Ok(session) = sessions().get(session_id) {
+            if let Some(remote) = session.as_any().downcast_ref::<RemoteSession>() {
+                *remote.status.lock() = status;
+            }
+        }
+        // Forget remote sessions that ended inside the daemon
+        if status != SessionStatus::Connected {
+            sessions().unregister(session_id);
+        }
+    }
+    let _ = app.emit(&event, payload);
+}
+
+/// The session in the response to an open request (a bare session) or an
+/// attach request (the session with its scrollback)
+fn opened_session(result: &Value) -> Option<SessionInfo> {
+    let session = result.get("session").unwrap_or(result);
+    serde_json::from_value(session.clone()).ok()
+}
+
+/// Maps the `status` of a forwarded `*-status` event to a session status
+fn remote_status(status: &str) -> Option<SessionStatus> {
+    match status {
+        "connected" => Some(SessionStatus::Connected),
+        "disconnected" | "exited" => Some(SessionStatus::Disconnected),
+        "error" => Some(SessionStatus::Error),
+        _ => None,
+    }
+}
+
+/// A session hosted by the daemon, as seen from the GUI
+pub struct RemoteSession {
+    pub info: SessionInfo,
+    /// Kept up to date from the session's forwarded status events
+    status: Mutex<SessionStatus>,
+    client: Arc<DaemonClient>,
+}
+
+impl Session for RemoteSession {
+    /// Queued without waiting for the daemon; typing never blocks on it
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.client.notify(DaemonRequest::Write {
+            session_id: self.info.session_id.clone(),
+            data: STANDARD.encode(data),
+        })
+    }
+
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        self.client.notify(DaemonRequest::Resize {
+            session_id: self.info.session_id.clone(),
+            cols,
+            rows,
+        })
+    }
+
+    fn close(&self) -> Result<(), String> {
+        self.client
+            .request(DaemonRequest::Close {
+                session_id: self.info.session_id.clone(),
+            })
+            .map(|_| ())
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            status: self.status(),
+            ..self.info.clone()
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        *self.status.lock()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Runs a transport command in the daemon if `session_id` is one of its
+/// sessions; `None` means the session is local and the caller handles it.
+pub fn remote_command<T: DeserializeOwned>(
+    session_id: &str,
+    command: impl FnOnce() -> SessionCommand,
+) -> Option<Result<T, String>> {
+    let session = sessions().get(session_id).ok()?;
+    let remote = session.as_any().downcast_ref::<RemoteSession>()?;
+    Some(command_result(&remote.client, command()))
+}
+
+/// Passes the answer to a prompt on to the daemon, which asked for it if no
+/// local connection did; `None` without a daemon connection.
+pub fn remote_answer(command: SessionCommand) -> Option<Result<(), String>> {
+    let client = DAEMON_CLIENT.lock().clone()?;
+    Some(command_result(&client, command))
+}
+
+fn command_result<T: DeserializeOwned>(
+    client: &DaemonClient,
+    command: SessionCommand,
+) -> Result<T, String> {
+    serde_json::from_value(client.request(DaemonRequest::Command(command))?)
+        .map_err(|e| format!("Invalid daemon response: {}", e))
+}
+
+/// Registers a daemon session in the local session manager
+fn register_remote(
+    client: Arc<DaemonClient>,
+    info: SessionInfo,
+    on_output: Option<Channel<String>>,
+) -> String {
+    let session_id = info.session_id.clone();
+    let status = Mutex::new(info.status);
+    sessions().insert(
+        &session_id,
+        Arc::new(RemoteSession {
+            info,
+            status,
+            client,
+        }),
+    );
+    if let Some(channel) = on_output {
+        register_output_channel(&session_id, channel);
+    }
+    session_id
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Starts the session daemon in the background if it is not already running
+///
+/// Runs on the blocking pool, since it waits for the daemon to come up.
+#[tauri::command]
+pub async fn daemon_start() -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(start_daemon)
+        .await
+        .map_err(|e| format!("Daemon start task failed: {}", e))?
+}
+
+fn start_daemon() -> Result<(), String> {
+    let path = socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate packet executable: {}", e))?;
+    println!(
+        "[Daemon] Starting session daemon: {} --daemon",
+        exe.display()
+    );
+
+    // Own process group, so closing the GUI or its terminal does not kill it
+    Command::new(exe)
+        .arg("--daemon")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("Failed to start session daemon: {}", e))?;
+
+    let deadline = Instant::now() + Duration::from_millis(STARTUP_TIMEOUT_MS);
+    while Instant::now() < deadline {
+        if UnixStream::connect(&path).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    Err("Session daemon did not start in time".to_string())
+}
+
+/// Opens a new session inside the daemon and attaches to it
+///
+/// Runs on the blocking pool, since the daemon only replies once the
+/// connection is up, which may wait for the user to answer host key or
+/// login prompts.
+///
+/// # Returns
+/// Session ID string on success
+#[tauri::command]
+pub async fn daemon_open_session(
+    app: AppHandle,
+    session: OpenSession,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    let client = daemon_client(&app)?;
+    let response = tauri::async_runtime::spawn_blocking(move || {
+        client.request_session(DaemonRequest::Open(session), on_output)
+    })
+    .await
+    .map_err(|e| format!("Daemon request task failed: {}", e))??;
+    let info: SessionInfo =
+        serde_json::from_value(response).map_err(|e| format!("Invalid daemon response: {}", e))?;
+    Ok(info.session_id)
+}
+
+/// Reattaches to a daemon session (e.g. an orphaned one after a restart)
+///
+/// # Returns
+/// The session's scrollback, for rehydrating the pane
+#[tauri::command]
+pub async fn daemon_attach(
+    app: AppHandle,
+    session_id: String,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    println!("[Daemon] daemon_attach called: session_id={}", session_id);
+    let client = daemon_client(&app)?;
+    let response = tauri::async_runtime::spawn_blocking(move || {
+        client.request_session(DaemonRequest::Attach { session_id }, on_output)
+    })
+    .await
+    .map_err(|e| format!("Daemon request task failed: {}", e))??;
+    let attached: AttachResult =
+        serde_json::from_value(response).map_err(|e| format!("Invalid daemon response: {}", e))?;
+    Ok(attached.scrollback)
+}
+
+/// Detaches from a daemon session, leaving it running
+#[tauri::command]
+pub async fn daemon_detach(app: AppHandle, session_id: String) -> Result<(), String> {
+    println!("[Daemon] daemon_detach called: session_id={}", session_id);
+    // Unregistering a local session here would leave it running unreachable
+    let session = sessions().get(&session_id)?;
+    if session.as_any().downcast_ref::<RemoteSession>().is_none() {
+        return Err(format!(
+            "Session {} is not hosted by the session daemon",
+            session_id
+        ));
+    }
+    let client = daemon_client(&app)?;
+    let detach = DaemonRequest::Detach {
+        session_id: session_id.clone(),
+    };
+    tauri::async_runtime::spawn_blocking(move || client.request(detach))
+        .await
+        .map_err(|e| format!("Daemon request task failed: {}", e))??;
+    sessions().unregister(&session_id);
+    Ok(())
+}
+
+/// Lists all daemon sessions, including orphaned ones
+#[tauri::command]
+pub async fn daemon_list_sessions(app: AppHandle) -> Result<Vec<DaemonSessionInfo>, String> {
+    let client = daemon_client(&app)?;
+    let response =
+        tauri::async_runtime::spawn_blocking(move || client.request(DaemonRequest::List))
+            .await
+            .map_err(|e| format!("Daemon request task failed: {}", e))??;
+    serde_json::from_value(response).map_err(|e| format!("Invalid daemon response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let envelope = RequestEnvelope {
+            id: 7,
+            request: DaemonRequest::Write {
+                session_id: "abc".to_string(),
+                data: STANDARD.encode("show ver\r"),
+            },
+        };
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["method"], "write");
+        assert_eq!(json["params"]["sessionId"], "abc");
+
+        let parsed: RequestEnvelope = serde_json::from_str(
+            r#"{"id":1,"method":"open","params":{"type":"telnet","host":"localhost","port":5000}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            parsed.request,
+            DaemonRequest::Open(OpenSession::Telnet { port: 5000, .. })
+        ));
+    }
+
+    #[test]
+    fn test_command_wire_format() {
+        let request = DaemonRequest::Command(SessionCommand::SerialBreak {
+            session_id: "abc".to_string(),
+            duration_ms: Some(250),
+        });
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["method"], "command");
+        assert_eq!(json["params"]["name"], "serial_break");
+        assert_eq!(json["params"]["durationMs"], 250);
+
+        let parsed: DaemonRequest = serde_json::from_str(
+            r#"{"method":"command","params":{"name":"respond_host_key","requestId":"s1","trust":true}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            parsed,
+            DaemonRequest::Command(SessionCommand::RespondHostKey { trust: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_opened_session_from_responses() {
+        let session = serde_json::json!({
+            "sessionId": "abc",
+            "kind": "telnet",
+            "label": "localhost:5000",
+            "status": "connected"
+        });
+        let opened = opened_session(&session).unwrap();
+        assert_eq!(opened.session_id, "abc");
+
+        let attached = serde_json::json!({ "session": session, "scrollback": "R1#" });
+        assert_eq!(opened_session(&attached).unwrap().session_id, "abc");
+        assert!(opened_session(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_remote_status_from_events() {
+        assert_eq!(remote_status("connected"), Some(SessionStatus::Connected));
+        assert_eq!(remote_status("exited"), Some(SessionStatus::Disconnected));
+        assert_eq!(remote_status("error"), Some(SessionStatus::Error));
+        assert_eq!(remote_status("negotiating"), None);
+    }
+
+    #[test]
+    fn test_request_id_of_invalid_request() {
+        let line = r#"{"id":12,"method":"write","params":{"sessionId":"abc"}}"#;
+        assert!(serde_json::from_str::<RequestEnvelope>(line).is_err());
+        assert_eq!(request_id(line), Some(12));
+        assert_eq!(request_id(r#"{"method":"list"}"#), None);
+        assert_eq!(request_id("not json"), None);
+    }
+
+    #[test]
+    fn test_message_parsing() {
+        let event: DaemonMessage = serde_json::from_str(
+            r#"{"event":"telnet-output","sessionId":"abc","payload":{"data":"R1#"}}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(event, DaemonMessage::Event { ref event, .. } if event == "telnet-output")
+        );
+
+        let response: DaemonMessage =
+            serde_json::from_str(r#"{"id":3,"error":"Session not found: x"}"#).unwrap();
+        assert!(matches!(
+            response,
+            DaemonMessage::Response {
+                id: 3,
+                error: Some(_),
+                ..
+            }
+        ));
+    }
+}
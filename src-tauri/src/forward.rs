@@ -0,0 +1,1233 @@
+//! SSH Port Forwarding Module for Packet
+//!
+//! Port forwards carried by an SSH session, like OpenSSH's `-L`, `-R` and
+//! `-D` options: tunnels to device web UIs on a management network, or
+//! remote GNS3 console ports made reachable from the server side.
+//!
+//! # Kinds
+//!
+//! - Local: connections to a local port are sent to `destHost:destPort`
+//!   through a `direct-tcpip` channel; the server resolves the destination.
+//! - Remote: the server listens on a port (`tcpip-forward`) and every
+//!   connection it accepts is sent to `destHost:destPort` from this machine.
+//! - Dynamic: a local SOCKS4/SOCKS5 proxy; each client names its own
+//!   destination, which is then reached like a local forward.
+//!
+//! # How It Works
+//!
+//! Every channel shares the session's socket, so all channel work happens
+//! on the session's I/O task, which owns a [`Forwarder`]. Local sockets are
+//! served by small tokio tasks that hand data to the I/O task over a queue.
+//! Each connection has a few chunks in flight in each direction at most, so
+//! a slow side slows the other one down instead of buffering without bound.
+//! Either side finishing sending only closes that direction; a connection
+//! ends once both have, so half-closed streams keep working.
+//!
+//! Forwards are added and removed at runtime with `add_ssh_forward` and
+//! `remove_ssh_forward`, and `list_ssh_forwards` reports each one with its
+//! connection and byte counters. They end with the session. Sessions hosted
+//! by the daemon take their forwards when they are opened, and the commands
+//! are passed on to the daemon for them.
+
+use crate::daemon::{remote_command, SessionCommand};
+use crate::session::sessions;
+use crate::ssh::{SshInput, SshSession};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, Listener};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, error::TrySendError, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Notify, Semaphore};
+use uuid::Uuid;
+
+/// Buffer size for forwarded data (16KB)
+const FORWARD_BUFFER_SIZE: usize = 16384;
+
+/// Chunks a connection may have queued in each direction
+const FORWARD_QUEUE_CHUNKS: usize = 4;
+
+/// Time a SOCKS client has to send its request
+const SOCKS_TIMEOUT_SECS: u64 = 10;
+
+/// Connection timeout for the destinations of remote forwards
+const CONNECTION_TIMEOUT_SECS: u64 = 30;
+
+/// Pause after a failed accept (e.g. out of file descriptors)
+const ACCEPT_RETRY_MS: u64 = 100;
+
+/// Kind of port forward, after OpenSSH's `-L`, `-R` and `-D`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    Local,
+    Remote,
+    Dynamic,
+}
+
+/// A port forward to set up on an SSH session
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForwardSpec {
+    pub kind: ForwardKind,
+    /// Address to listen on (on the server for remote forwards); loopback
+    /// when missing
+    #[serde(default, rename = "bindAddress")]
+    pub bind_address: Option<String>,
+    /// Port to listen on; 0 picks a free one
+    #[serde(rename = "bindPort")]
+    pub bind_port: u16,
+    /// Where connections are sent; unused by dynamic forwards
+    #[serde(default, rename = "destHost")]
+    pub dest_host: Option<String>,
+    #[serde(default, rename = "destPort")]
+    pub dest_port: Option<u16>,
+}
+
+impl ForwardSpec {
+    fn bind_address(&self) -> &str {
+        match self.bind_address.as_deref() {
+            Some(address) if !address.is_empty() => address,
+            _ if self.kind == ForwardKind::Remote => "localhost",
+            _ => "127.0.0.1",
+        }
+    }
+
+    /// Where a local or remote forward sends its connections
+    fn destination(&self) -> Result<(String, u16), String> {
+        match (self.dest_host.as_deref(), self.dest_port) {
+            (Some(host), Some(port)) if !host.is_empty() && port != 0 => {
+                Ok((host.to_string(), port))
+            }
+            _ => Err(format!("{} needs a destination host and port", self)),
+        }
+    }
+}
+
+/// Formats the forward as OpenSSH's command line option, e.g. `-L 8443:10.0.0.1:443`
+impl fmt::Display for ForwardSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = match self.kind {
+            ForwardKind::Local => 'L',
+            ForwardKind::Remote => 'R',
+            ForwardKind::Dynamic => 'D',
+        };
+        write!(f, "-{} ", flag)?;
+        if let Some(address) = self.bind_address.as_deref().filter(|a| !a.is_empty()) {
+            write!(f, "{}:", bracketed(address))?;
+        }
+        write!(f, "{}", self.bind_port)?;
+        if self.kind != ForwardKind::Dynamic {
+            let host = self.dest_host.as_deref().unwrap_or("?");
+            write!(f, ":{}:{}", bracketed(host), self.dest_port.unwrap_or(0))?;
+        }
+        Ok(())
+    }
+}
+
+/// Puts IPv6 addresses in brackets so ports can follow them
+fn bracketed(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// A forward and its counters (for frontend display)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForwardInfo {
+    pub id: String,
+    #[serde(flatten)]
+    pub spec: ForwardSpec,
+    /// Port actually listened on, which differs from `bindPort` when that was 0
+    #[serde(rename = "boundPort")]
+    pub bound_port: u16,
+    #[serde(rename = "activeConnections")]
+    pub active_connections: u64,
+    #[serde(rename = "totalConnections")]
+    pub total_connections: u64,
+    /// Bytes sent into the tunnel by local clients (or remote forward destinations)
+    #[serde(rename = "bytesSent")]
+    pub bytes_sent: u64,
+    /// Bytes that came out of the tunnel
+    #[serde(rename = "bytesReceived")]
+    pub bytes_received: u64,
+}
+
+/// A running forward, shared by the I/O task and the listing command
+pub struct ForwardEntry {
+    id: String,
+    spec: ForwardSpec,
+    bound_port: u16,
+    active_connections: AtomicU64,
+    total_connections: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl ForwardEntry {
+    fn new(spec: ForwardSpec, bound_port: u16) -> Self {
+        ForwardEntry {
+            id: Uuid::new_v4().to_string(),
+            spec,
+            bound_port,
+            active_connections: AtomicU64::new(0),
+            total_connections: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    fn info(&self) -> ForwardInfo {
+        ForwardInfo {
+            id: self.id.clone(),
+            spec: self.spec.clone(),
+            bound_port: self.bound_port,
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The forwards running on a session
+pub type ForwardTable = Arc<Mutex<Vec<Arc<ForwardEntry>>>>;
+
+/// Forward changes queued for a session's I/O task
+pub enum ForwardRequest {
+    Add {
+        spec: ForwardSpec,
+        reply: oneshot::Sender<Result<ForwardInfo, String>>,
+    },
+    Remove {
+        id: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+}
+
+/// SOCKS version spoken by a dynamic forward's client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SocksVersion {
+    V4,
+    V5,
+}
+
+/// The destination a SOCKS client asked for
+type SocksTarget = (SocksVersion, String, u16);
+
+/// What the local socket tasks tell the I/O task
+enum ForwardEvent {
+    /// A local client wants a channel to `host:port`
+    Open(OpenRequest),
+    /// Data read from a connection's local socket
+    Data { conn: u64, data: Vec<u8> },
+    /// The local side finished sending
+    Eof { conn: u64 },
+    /// The local side failed or went away
+    Closed { conn: u64 },
+}
+
+/// A local client waiting for its channel
+struct OpenRequest {
+    forward: Arc<ForwardEntry>,
+    stream: TcpStream,
+    peer: SocketAddr,
+    host: String,
+    port: u16,
+    /// Set for dynamic forwards, whose client expects a SOCKS reply
+    socks: Option<SocksVersion>,
+}
+
+/// libssh2 work that may take several attempts in non-blocking mode
+enum PendingOp {
+    Open(OpenRequest),
+    /// Remote forward waiting for the server to start listening
+    Listen {
+        spec: ForwardSpec,
+        reply: oneshot::Sender<Result<ForwardInfo, String>>,
+    },
+}
+
+/// How a connection gets its local socket
+enum LocalSocket {
+    /// Accepted by a local or dynamic forward
+    Accepted {
+        stream: TcpStream,
+        socks: Option<SocksVersion>,
+    },
+    /// Dialled for a connection coming in on a remote forward
+    Connect { host: String, port: u16 },
+}
+
+/// Where a forward's connections come from
+enum ForwardListener {
+    /// Accept loop of a local or dynamic forward
+    Local(JoinHandle<()>),
+    /// Listener on the server for a remote forward
+    Remote(Listener),
+}
+
+struct ActiveForward {
+    entry: Arc<ForwardEntry>,
+    listener: ForwardListener,
+}
+
+impl Drop for ActiveForward {
+    fn drop(&mut self) {
+        // Remote listeners are cancelled on the server when dropped
+        if let ForwardListener::Local(task) = &self.listener {
+            task.abort();
+        }
+    }
+}
+
+/// A forwarded connection
+struct Connection {
+    forward: Arc<ForwardEntry>,
+    channel: Channel,
+    /// Chunks from the local socket the channel has not accepted yet
+    to_remote: VecDeque<Vec<u8>>,
+    /// Returned to the socket task as chunks reach the channel
+    credits: Arc<Semaphore>,
+    /// Channel data for the socket task; dropped once the remote side closes
+    to_local: Option<mpsc::Sender<Vec<u8>>>,
+    /// Channel data the socket task had no room for yet
+    held: Option<Vec<u8>>,
+    /// The local side finished sending, so EOF follows the queued data
+    local_eof: bool,
+    eof_sent: bool,
+    task: JoinHandle<()>,
+}
+
+/// Outcome of moving a connection's data
+enum Transfer {
+    Idle,
+    Progress,
+    /// Both sides finished sending, and everything was passed on
+    Done,
+    Failed,
+}
+
+/// Drives the forwards of one SSH session; owned by its I/O task
+pub struct Forwarder {
+    session_id: String,
+    table: ForwardTable,
+    forwards: Vec<ActiveForward>,
+    ops: VecDeque<PendingOp>,
+    connections: HashMap<u64, Connection>,
+    /// Channels whose close has not completed yet
+    closing: Vec<Channel>,
+    next_conn: u64,
+    events: UnboundedSender<ForwardEvent>,
+    queue: UnboundedReceiver<ForwardEvent>,
+    /// Signalled when a socket task makes room for more channel data
+    room: Arc<Notify>,
+    buf: Vec<u8>,
+}
+
+impl Forwarder {
+    pub fn new(session_id: &str, table: ForwardTable) -> Self {
+        let (events, queue) = mpsc::unbounded_channel();
+        Forwarder {
+            session_id: session_id.to_string(),
+            table,
+            forwards: Vec::new(),
+            ops: VecDeque::new(),
+            connections: HashMap::new(),
+            closing: Vec::new(),
+            next_conn: 0,
+            events,
+            queue,
+            room: Arc::new(Notify::new()),
+            buf: vec![0u8; FORWARD_BUFFER_SIZE],
+        }
+    }
+
+    /// Applies a request from the commands. Remote forwards are answered
+    /// once the server has accepted them.
+    pub fn request(&mut self, request: ForwardRequest) {
+        match request {
+            ForwardRequest::Add { spec, reply } => match spec.kind {
+                ForwardKind::Remote => match spec.destination() {
+                    Ok(_) => self.ops.push_back(PendingOp::Listen { spec, reply }),
+                    Err(e) => {
+                        let _ = reply.send(Err(e));
+                    }
+                },
+                _ => {
+                    let _ = reply.send(self.listen_local(spec));
+                }
+            },
+            ForwardRequest::Remove { id, reply } => {
+                let _ = reply.send(self.remove(&id));
+            }
+        }
+    }
+
+    /// Starts the listener of a local or dynamic forward
+    fn listen_local(&mut self, spec: ForwardSpec) -> Result<ForwardInfo, String> {
+        let destination = match spec.kind {
+            ForwardKind::Dynamic => None,
+            _ => Some(spec.destination()?),
+        };
+
+        let address = format!("{}:{}", bracketed(spec.bind_address()), spec.bind_port);
+        let listener = std::net::TcpListener::bind(&address)
+            .and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                TcpListener::from_std(listener)
+            })
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        let bound_port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?
+            .port();
+
+        let entry = Arc::new(ForwardEntry::new(spec, bound_port));
+        let task = tauri::async_runtime::spawn(accept_local(
+            listener,
+            entry.clone(),
+            destination,
+            self.events.clone(),
+        ));
+        Ok(self.started(entry, ForwardListener::Local(task)))
+    }
+
+    /// Records a forward that is now listening
+    fn started(&mut self, entry: Arc<ForwardEntry>, listener: ForwardListener) -> ForwardInfo {
+        println!(
+            "[SSH] Forwarding {} on port {} for {}",
+            entry.spec, entry.bound_port, self.session_id
+        );
+        self.table.lock().push(entry.clone());
+        let info = entry.info();
+        self.forwards.push(ActiveForward { entry, listener });
+        info
+    }
+
+    /// Stops a forward and closes its connections
+    fn remove(&mut self, id: &str) -> Result<(), String> {
+        let index = self
+            .forwards
+            .iter()
+            .position(|forward| forward.entry.id == id)
+            .ok_or_else(|| format!("No forward {} on this session", id))?;
+        let forward = self.forwards.remove(index);
+        self.table.lock().retain(|entry| entry.id != id);
+
+        let conns: Vec<u64> = self
+            .connections
+            .iter()
+            .filter(|(_, conn)| Arc::ptr_eq(&conn.forward, &forward.entry))
+            .map(|(conn, _)| *conn)
+            .collect();
+        for conn in conns {
+            self.finish(conn, true);
+        }
+        self.ops.retain(|op| match op {
+            PendingOp::Open(open) => !Arc::ptr_eq(&open.forward, &forward.entry),
+            PendingOp::Listen { .. } => true,
+        });
+
+        println!(
+            "[SSH] Removed forward {} from {}",
+            forward.entry.spec, self.session_id
+        );
+        Ok(())
+    }
+
+    /// Waits until a socket task needs the I/O task, and takes note of it
+    pub async fn wait(&mut self) {
+        tokio::select! {
+            Some(event) = self.queue.recv() => self.handle(event),
+            _ = self.room.notified() => {}
+        }
+    }
+
+    fn handle(&mut self, event: ForwardEvent) {
+        match event {
+            ForwardEvent::Open(open) => self.ops.push_back(PendingOp::Open(open)),
+            ForwardEvent::Data { conn, data } => {
+                if let Some(conn) = self.connections.get_mut(&conn) {
+                    conn.to_remote.push_back(data);
+                }
+            }
+            ForwardEvent::Eof { conn } => {
+                if let Some(conn) = self.connections.get_mut(&conn) {
+                    conn.local_eof = true;
+                }
+            }
+            ForwardEvent::Closed { conn } => self.finish(conn, true),
+        }
+    }
+
+    /// Moves whatever data libssh2 and the socket tasks can take without
+    /// blocking. Returns whether anything happened, in which case libssh2
+    /// may have buffered more data for any channel.
+    pub fn pump(&mut self, session: &ssh2::Session) -> bool {
+        let mut progress = self.run_ops(session);
+        progress |= self.accept_remote();
+
+        let mut finished = Vec::new();
+        for (id, conn) in self.connections.iter_mut() {
+            match transfer(conn, &mut self.buf) {
+                Transfer::Idle => {}
+                Transfer::Progress => progress = true,
+                Transfer::Done => finished.push((*id, false)),
+                Transfer::Failed => finished.push((*id, true)),
+            }
+        }
+        for (conn, failed) in finished {
+            self.finish(conn, failed);
+            progress = true;
+        }
+
+        // A close waits for the server's reply
+        self.closing.retain_mut(|channel| {
+            matches!(
+                channel.close().map_err(std::io::Error::from),
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock
+            )
+        });
+
+        progress
+    }
+
+    /// Works through the queued channel opens and remote listens in order
+    fn run_ops(&mut self, session: &ssh2::Session) -> bool {
+        let mut progress = false;
+        while let Some(op) = self.ops.front() {
+            let result = match op {
+                PendingOp::Open(open) => session
+                    .channel_direct_tcpip(
+                        &open.host,
+                        open.port,
+                        Some((&open.peer.ip().to_string(), open.peer.port())),
+                    )
+                    .map(OpResult::Channel),
+                PendingOp::Listen { spec, .. } => session
+                    .channel_forward_listen(spec.bind_port, Some(spec.bind_address()), None)
+                    .map(|(listener, port)| OpResult::Listener(listener, port)),
+            };
+            let result = result.map_err(std::io::Error::from);
+            if matches!(result, Err(ref e) if e.kind() == ErrorKind::WouldBlock) {
+                break;
+            }
+            progress = true;
+
+            match (self.ops.pop_front(), result) {
+                (Some(PendingOp::Open(open)), Ok(OpResult::Channel(channel))) => {
+                    let socket = LocalSocket::Accepted {
+                        stream: open.stream,
+                        socks: open.socks,
+                    };
+                    self.connect(open.forward, channel, socket);
+                }
+                (Some(PendingOp::Open(open)), Err(e)) => {
+                    println!(
+                        "[SSH] Forward {} could not reach {}:{}: {}",
+                        open.forward.spec, open.host, open.port, e
+                    );
+                    if let Some(version) = open.socks {
+                        tauri::async_runtime::spawn(socks_refuse(open.stream, version));
+                    }
+                }
+                (
+                    Some(PendingOp::Listen { spec, reply }),
+                    Ok(OpResult::Listener(listener, port)),
+                ) => {
+                    let entry = Arc::new(ForwardEntry::new(spec, port));
+                    let info = self.started(entry, ForwardListener::Remote(listener));
+                    let _ = reply.send(Ok(info));
+                }
+                (Some(PendingOp::Listen { spec, reply }), Err(e)) => {
+                    let _ = reply.send(Err(format!(
+                        "The server refused to listen on {}:{}: {}",
+                        spec.bind_address(),
+                        spec.bind_port,
+                        e
+                    )));
+                }
+                _ => {}
+            }
+        }
+        progress
+    }
+
+    /// Takes the connections the server accepted on remote forwards
+    fn accept_remote(&mut self) -> bool {
+        let mut accepted = Vec::new();
+        for forward in self.forwards.iter_mut() {
+            let ForwardListener::Remote(listener) = &mut forward.listener else {
+                continue;
+            };
+            loop {
+                match listener.accept().map_err(std::io::Error::from) {
+                    Ok(channel) => accepted.push((forward.entry.clone(), channel)),
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        eprintln!(
+                            "[SSH] Forward {} failed to accept: {}",
+                            forward.entry.spec, e
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+
+        let progress = !accepted.is_empty();
+        for (forward, channel) in accepted {
+            if let Ok((host, port)) = forward.spec.destination() {
+                self.connect(forward, channel, LocalSocket::Connect { host, port });
+            }
+        }
+        progress
+    }
+
+    /// Starts relaying between a channel and its local socket
+    fn connect(&mut self, forward: Arc<ForwardEntry>, channel: Channel, socket: LocalSocket) {
+        self.next_conn += 1;
+        let conn = self.next_conn;
+        forward.active_connections.fetch_add(1, Ordering::Relaxed);
+        forward.total_connections.fetch_add(1, Ordering::Relaxed);
+
+        let credits = Arc::new(Semaphore::new(FORWARD_QUEUE_CHUNKS));
+        let (to_local, from_channel) = mpsc::channel(FORWARD_QUEUE_CHUNKS);
+        let task = tauri::async_runtime::spawn(serve_socket(
+            conn,
+            socket,
+            from_channel,
+            credits.clone(),
+            self.events.clone(),
+            self.room.clone(),
+        ));
+
+        self.connections.insert(
+            conn,
+            Connection {
+                forward,
+                channel,
+                to_remote: VecDeque::new(),
+                credits,
+                to_local: Some(to_local),
+                held: None,
+                local_eof: false,
+                eof_sent: false,
+                task,
+            },
+        );
+    }
+
+    /// Closes a connection's channel; `failed` also drops its local socket
+    /// without waiting for queued data
+    fn finish(&mut self, conn: u64, failed: bool) {
+        if let Some(conn) = self.connections.remove(&conn) {
+            if failed {
+                conn.task.abort();
+            }
+            conn.forward
+                .active_connections
+                .fetch_sub(1, Ordering::Relaxed);
+            self.closing.push(conn.channel);
+        }
+    }
+}
+
+impl Drop for Forwarder {
+    fn drop(&mut self) {
+        for conn in self.connections.values() {
+            conn.task.abort();
+        }
+        self.table.lock().clear();
+    }
+}
+
+/// What a finished channel open or remote listen produced
+enum OpResult {
+    Channel(Channel),
+    Listener(Listener, u16),
+}
+
+/// Moves data between a connection's channel and its socket task
+fn transfer(conn: &mut Connection, buf: &mut [u8]) -> Transfer {
+    let mut progress = false;
+
+    // Channel to local socket, as long as the socket task keeps up
+    while let Some(to_local) = &conn.to_local {
+        if let Some(data) = conn.held.take() {
+            match to_local.try_send(data) {
+                Ok(()) => progress = true,
+                Err(TrySendError::Full(data)) => {
+                    conn.held = Some(data);
+                    break;
+                }
+                Err(TrySendError::Closed(_)) => return Transfer::Failed,
+            }
+        }
+
+        match conn.channel.read(buf) {
+            Ok(0) if conn.channel.eof() => {
+                // The socket task writes what it has, then shuts down its
+                // sending side; the other direction stays open
+                conn.to_local = None;
+                progress = true;
+            }
+            Ok(0) => break,
+            Ok(n) => {
+                conn.forward
+                    .bytes_received
+                    .fetch_add(n as u64, Ordering::Relaxed);
+                conn.held = Some(buf[..n].to_vec());
+                progress = true;
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => return Transfer::Failed,
+        }
+    }
+
+    // Local socket to channel
+    while let Some(chunk) = conn.to_remote.front_mut() {
+        match conn.channel.write(chunk) {
+            Ok(0) => break,
+            Ok(n) => {
+                conn.forward
+                    .bytes_sent
+                    .fetch_add(n as u64, Ordering::Relaxed);
+                chunk.drain(..n);
+                if chunk.is_empty() {
+                    conn.to_remote.pop_front();
+                    conn.credits.add_permits(1);
+                }
+                progress = true;
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => return Transfer::Failed,
+        }
+    }
+
+    if conn.local_eof && !conn.eof_sent && conn.to_remote.is_empty() {
+        match conn.channel.send_eof().map_err(std::io::Error::from) {
+            Ok(()) => {
+                conn.eof_sent = true;
+                progress = true;
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => return Transfer::Failed,
+        }
+    }
+
+    if conn.to_local.is_none() && conn.eof_sent {
+        Transfer::Done
+    } else if progress {
+        Transfer::Progress
+    } else {
+        Transfer::Idle
+    }
+}
+
+/// Accepts the clients of a local or dynamic forward. `destination` is
+/// `None` for dynamic forwards, whose clients speak SOCKS.
+async fn accept_local(
+    listener: TcpListener,
+    forward: Arc<ForwardEntry>,
+    destination: Option<(String, u16)>,
+    events: UnboundedSender<ForwardEvent>,
+) {
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("[SSH] Forward {} failed to accept: {}", forward.spec, e);
+                tokio::time::sleep(Duration::from_millis(ACCEPT_RETRY_MS)).await;
+                continue;
+            }
+        };
+
+        match &destination {
+            Some((host, port)) => {
+                let open = OpenRequest {
+                    forward: forward.clone(),
+                    stream,
+                    peer,
+                    host: host.clone(),
+                    port: *port,
+                    socks: None,
+                };
+                if events.send(ForwardEvent::Open(open)).is_err() {
+                    return;
+                }
+            }
+            None => {
+                let forward = forward.clone();
+                let events = events.clone();
+                tauri::async_runtime::spawn(async move {
+                    let request = tokio::time::timeout(
+                        Duration::from_secs(SOCKS_TIMEOUT_SECS),
+                        socks_request(&mut stream),
+                    )
+                    .await
+                    .unwrap_or_else(|_| Err("SOCKS request timed out".to_string()));
+
+                    match request {
+                        Ok((version, host, port)) => {
+                            let _ = events.send(ForwardEvent::Open(OpenRequest {
+                                forward,
+                                stream,
+                                peer,
+                                host,
+                                port,
+                                socks: Some(version),
+                            }));
+                        }
+                        Err(e) => println!("[SSH] SOCKS client {} rejected: {}", peer, e),
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Relays between a connection's local socket and the I/O task
+async fn serve_socket(
+    conn: u64,
+    socket: LocalSocket,
+    mut from_channel: mpsc::Receiver<Vec<u8>>,
+    credits: Arc<Semaphore>,
+    events: UnboundedSender<ForwardEvent>,
+    room: Arc<Notify>,
+) {
+    let stream = match socket {
+        LocalSocket::Accepted { mut stream, socks } => {
+            let reply = socks.map(|version| socks_reply(version, true));
+            if let Some(reply) = reply {
+                if stream.write_all(&reply).await.is_err() {
+                    let _ = events.send(ForwardEvent::Closed { conn });
+                    return;
+                }
+            }
+            stream
+        }
+        LocalSocket::Connect { host, port } => {
+            let connect = TcpStream::connect((host.as_str(), port));
+            match tokio::time::timeout(Duration::from_secs(CONNECTION_TIMEOUT_SECS), connect).await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    println!(
+                        "[SSH] Remote forward could not reach {}:{}: {}",
+                        host, port, e
+                    );
+                    let _ = events.send(ForwardEvent::Closed { conn });
+                    return;
+                }
+                Err(_) => {
+                    println!("[SSH] Remote forward timed out reaching {}:{}", host, port);
+                    let _ = events.send(ForwardEvent::Closed { conn });
+                    return;
+                }
+            }
+        }
+    };
+    let _ = stream.set_nodelay(true);
+    let (mut reader, mut writer) = stream.into_split();
+
+    let reading = async {
+        let mut buf = vec![0u8; FORWARD_BUFFER_SIZE];
+        loop {
+            // One credit per chunk waiting for the channel
+            match credits.acquire().await {
+                Ok(permit) => permit.forget(),
+                Err(_) => break,
+            }
+            match reader.read(&mut buf).await {
+                Ok(0) => {
+                    let _ = events.send(ForwardEvent::Eof { conn });
+                    break;
+                }
+                Ok(n) => {
+                    let data = buf[..n].to_vec();
+                    if events.send(ForwardEvent::Data { conn, data }).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    let _ = events.send(ForwardEvent::Closed { conn });
+                    break;
+                }
+            }
+        }
+    };
+
+    let writing = async {
+        while let Some(data) = from_channel.recv().await {
+            room.notify_one();
+            if writer.write_all(&data).await.is_err() {
+                let _ = events.send(ForwardEvent::Closed { conn });
+                return;
+            }
+        }
+        let _ = writer.shutdown().await;
+    };
+
+    // Each direction ends on its own; errors get the task aborted
+    tokio::join!(reading, writing);
+}
+
+/// Reads a SOCKS4, SOCKS4a or SOCKS5 CONNECT request (without
+/// authentication) and returns the destination it names
+async fn socks_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+) -> Result<SocksTarget, String> {
+    let io = |e: std::io::Error| format!("SOCKS handshake failed: {}", e);
+
+    match stream.read_u8().await.map_err(io)? {
+        4 => {
+            let command = stream.read_u8().await.map_err(io)?;
+            let port = stream.read_u16().await.map_err(io)?;
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await.map_err(io)?;
+            // User ID, which is not checked
+            read_socks_string(stream).await?;
+
+            if command != 1 {
+                let _ = stream
+                    .write_all(&socks_reply(SocksVersion::V4, false))
+                    .await;
+                return Err(format!("Unsupported SOCKS4 command {}", command));
+            }
+            // SOCKS4a: 0.0.0.x means a hostname follows
+            let host = if ip[..3] == [0, 0, 0] && ip[3] != 0 {
+                read_socks_string(stream).await?
+            } else {
+                Ipv4Addr::from(ip).to_string()
+            };
+            Ok((SocksVersion::V4, host, port))
+        }
+        5 => {
+            let count = stream.read_u8().await.map_err(io)?;
+            let mut methods = vec![0u8; count as usize];
+            stream.read_exact(&mut methods).await.map_err(io)?;
+            if !methods.contains(&0) {
+                let _ = stream.write_all(&[5, 0xff]).await;
+                return Err("SOCKS5 client requires authentication".to_string());
+            }
+            stream.write_all(&[5, 0]).await.map_err(io)?;
+
+            let mut header = [0u8; 4];
+            stream.read_exact(&mut header).await.map_err(io)?;
+            let host = match header[3] {
+                1 => {
+                    let mut ip = [0u8; 4];
+                    stream.read_exact(&mut ip).await.map_err(io)?;
+                    Ipv4Addr::from(ip).to_string()
+                }
+                3 => {
+                    let len = stream.read_u8().await.map_err(io)?;
+                    let mut name = vec![0u8; len as usize];
+                    stream.read_exact(&mut name).await.map_err(io)?;
+                    String::from_utf8(name).map_err(|_| "Invalid SOCKS5 hostname".to_string())?
+                }
+                4 => {
+                    let mut ip = [0u8; 16];
+                    stream.read_exact(&mut ip).await.map_err(io)?;
+                    Ipv6Addr::from(ip).to_string()
+                }
+                other => return Err(format!("Unsupported SOCKS5 address type {}", other)),
+            };
+            let port = stream.read_u16().await.map_err(io)?;
+
+            if header[1] != 1 {
+                // 7: command not supported
+                let _ = stream.write_all(&[5, 7, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+                return Err(format!("Unsupported SOCKS5 command {}", header[1]));
+            }
+            Ok((SocksVersion::V5, host, port))
+        }
+        other => Err(format!("Unknown SOCKS version {}", other)),
+    }
+}
+
+/// Reads a NUL-terminated SOCKS4 string
+async fn read_socks_string<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    loop {
+        match stream.read_u8().await {
+            Ok(0) => break,
+            Ok(_) if bytes.len() >= 255 => return Err("SOCKS4 string too long".to_string()),
+            Ok(byte) => bytes.push(byte),
+            Err(e) => return Err(format!("SOCKS handshake failed: {}", e)),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "Invalid SOCKS4 string".to_string())
+}
+
+/// The reply to a SOCKS CONNECT request. The bound address is not reported.
+fn socks_reply(version: SocksVersion, success: bool) -> Vec<u8> {
+    match version {
+        SocksVersion::V4 => vec![0, if success { 0x5a } else { 0x5b }, 0, 0, 0, 0, 0, 0],
+        // 1: general failure
+        SocksVersion::V5 => vec![5, if success { 0 } else { 1 }, 0, 1, 0, 0, 0, 0, 0, 0],
+    }
+}
+
+/// Tells a SOCKS client its destination could not be reached
+async fn socks_refuse(mut stream: TcpStream, version: SocksVersion) {
+    let _ = stream.write_all(&socks_reply(version, false)).await;
+}
+
+/// Runs `f` on an SSH session in this process
+fn with_ssh_session<T>(
+    session_id: &str,
+    f: impl FnOnce(&SshSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let session = sessions().get(session_id)?;
+    let ssh = session
+        .as_any()
+        .downcast_ref::<SshSession>()
+        .ok_or_else(|| format!("Session {} is not an SSH session", session_id))?;
+    f(ssh)
+}
+
+/// Queues a forward request for a session's I/O task
+fn queue_request(session_id: &str, request: ForwardRequest) -> Result<(), String> {
+    with_ssh_session(session_id, |ssh| {
+        ssh.writer
+            .send(SshInput::Forward(request))
+            .map_err(|_| "SSH connection closed".to_string())
+    })
+}
+
+/// Adds a forward to an SSH session once its I/O task has set it up
+pub async fn add_forward(session_id: &str, spec: ForwardSpec) -> Result<ForwardInfo, String> {
+    let (reply, result) = oneshot::channel();
+    queue_request(session_id, ForwardRequest::Add { spec, reply })?;
+    result
+        .await
+        .map_err(|_| "SSH connection closed".to_string())?
+}
+
+/// Runs a forward command in the session daemon if the session lives
+/// there, off the async runtime since it waits for the daemon's reply
+async fn remote_forward_command<T: DeserializeOwned + Send + 'static>(
+    session_id: &str,
+    command: SessionCommand,
+) -> Option<Result<T, String>> {
+    let session_id = session_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || remote_command(&session_id, || command))
+        .await
+        .unwrap_or_else(|e| Some(Err(format!("Session daemon request failed: {}", e))))
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Adds a port forward to an SSH session
+///
+/// Returns the forward with the port it listens on, which is picked by the
+/// system (or the server) when `bindPort` is 0.
+#[tauri::command]
+pub async fn add_ssh_forward(
+    session_id: String,
+    forward: ForwardSpec,
+) -> Result<ForwardInfo, String> {
+    println!(
+        "[SSH] add_ssh_forward called: session_id={}, {}",
+        session_id, forward
+    );
+    let command = SessionCommand::AddForward {
+        session_id: session_id.clone(),
+        forward: forward.clone(),
+    };
+    if let Some(result) = remote_forward_command(&session_id, command).await {
+        return result;
+    }
+    add_forward(&session_id, forward).await
+}
+
+/// Stops a port forward and closes its connections
+#[tauri::command]
+pub async fn remove_ssh_forward(session_id: String, forward_id: String) -> Result<(), String> {
+    let command = SessionCommand::RemoveForward {
+        session_id: session_id.clone(),
+        forward_id: forward_id.clone(),
+    };
+    if let Some(result) = remote_forward_command(&session_id, command).await {
+        return result;
+    }
+    let (reply, result) = oneshot::channel();
+    queue_request(
+        &session_id,
+        ForwardRequest::Remove {
+            id: forward_id,
+            reply,
+        },
+    )?;
+    result
+        .await
+        .map_err(|_| "SSH connection closed".to_string())?
+}
+
+/// Lists the port forwards of an SSH session with their counters
+#[tauri::command]
+pub fn list_ssh_forwards(session_id: String) -> Result<Vec<ForwardInfo>, String> {
+    if let Some(result) = remote_command(&session_id, || SessionCommand::ListForwards {
+        session_id: session_id.clone(),
+    }) {
+        return result;
+    }
+    with_ssh_session(&session_id, |ssh| {
+        Ok(ssh
+            .forwards
+            .lock()
+            .iter()
+            .map(|entry| entry.info())
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(kind: ForwardKind, dest: Option<(&str, u16)>) -> ForwardSpec {
+        ForwardSpec {
+            kind,
+            bind_address: None,
+            bind_port: 8443,
+            dest_host: dest.map(|(host, _)| host.to_string()),
+            dest_port: dest.map(|(_, port)| port),
+        }
+    }
+
+    #[test]
+    fn test_spec_display_and_destination() {
+        let local = spec(ForwardKind::Local, Some(("10.0.0.1", 443)));
+        assert_eq!(local.to_string(), "-L 8443:10.0.0.1:443");
+        assert_eq!(local.bind_address(), "127.0.0.1");
+        assert_eq!(local.destination().unwrap(), ("10.0.0.1".to_string(), 443));
+
+        let mut remote = spec(ForwardKind::Remote, Some(("2001:db8::1", 5000)));
+        remote.bind_address = Some("0.0.0.0".to_string());
+        assert_eq!(remote.to_string(), "-R 0.0.0.0:8443:[2001:db8::1]:5000");
+
+        let dynamic = spec(ForwardKind::Dynamic, None);
+        assert_eq!(dynamic.to_string(), "-D 8443");
+        assert!(spec(ForwardKind::Local, None).destination().is_err());
+    }
+
+    fn handshake(request: &[u8]) -> (Result<SocksTarget, String>, Vec<u8>) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (mut client, mut server) = tokio::io::duplex(1024);
+            client.write_all(request).await.unwrap();
+            let result = socks_request(&mut server).await;
+            drop(server);
+            let mut replies = Vec::new();
+            client.read_to_end(&mut replies).await.unwrap();
+            (result, replies)
+        })
+    }
+
+    #[test]
+    fn test_socks5_request() {
+        let mut request = vec![5, 1, 0, 5, 1, 0, 3, 11];
+        request.extend_from_slice(b"r1.mgmt.lab");
+        request.extend_from_slice(&443u16.to_be_bytes());
+        let (result, replies) = handshake(&request);
+        assert_eq!(
+            result.unwrap(),
+            (SocksVersion::V5, "r1.mgmt.lab".to_string(), 443)
+        );
+        assert_eq!(replies, [5, 0]);
+
+        // Only username/password offered
+        let (result, replies) = handshake(&[5, 1, 2]);
+        assert!(result.is_err());
+        assert_eq!(replies, [5, 0xff]);
+    }
+
+    #[test]
+    fn test_socks4_request() {
+        let mut request = vec![4, 1, 0x1f, 0x90, 10, 0, 0, 1];
+        request.extend_from_slice(b"netops\0");
+        let (result, _) = handshake(&request);
+        assert_eq!(
+            result.unwrap(),
+            (SocksVersion::V4, "10.0.0.1".to_string(), 8080)
+        );
+
+        // SOCKS4a with a hostname
+        let mut request = vec![4, 1, 0, 80, 0, 0, 0, 1, 0];
+        request.extend_from_slice(b"gns3-server\0");
+        let (result, _) = handshake(&request);
+        assert_eq!(
+            result.unwrap(),
+            (SocksVersion::V4, "gns3-server".to_string(), 80)
+        );
+
+        assert_eq!(socks_reply(SocksVersion::V4, false)[1], 0x5b);
+    }
+
+    #[test]
+    fn test_socket_half_close() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+
+            let (to_local, from_channel) = mpsc::channel(FORWARD_QUEUE_CHUNKS);
+            let (events, mut queue) = mpsc::unbounded_channel();
+            let task = tokio::spawn(serve_socket(
+                1,
+                LocalSocket::Accepted {
+                    stream,
+                    socks: None,
+                },
+                from_channel,
+                Arc::new(Semaphore::new(FORWARD_QUEUE_CHUNKS)),
+                events,
+                Arc::new(Notify::new()),
+            ));
+
+            // The remote side replies and finishes sending
+            to_local.send(b"reply".to_vec()).await.unwrap();
+            drop(to_local);
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).await.unwrap();
+            assert_eq!(received, b"reply");
+
+            // The client can still send until it closes its side too
+            client.write_all(b"late").await.unwrap();
+            client.shutdown().await.unwrap();
+            assert!(matches!(
+                queue.recv().await,
+                Some(ForwardEvent::Data { conn: 1, data }) if data == b"late"
+            ));
+            assert!(matches!(
+                queue.recv().await,
+                Some(ForwardEvent::Eof { conn: 1 })
+            ));
+            task.await.unwrap();
+        });
+    }
+}
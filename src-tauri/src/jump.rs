@@ -0,0 +1,342 @@
+//! Jump Host Module for Packet
+//!
+//! Reaches SSH and telnet targets through a chain of SSH bastions, like
+//! OpenSSH's ProxyJump.
+//!
+//! # How It Works
+//!
+//! The first bastion is dialled over TCP. Every hop logs in as a normal SSH
+//! session (host key check and authentication included) and opens a
+//! `direct-tcpip` channel to the next hop, or finally to the target. Since
+//! libssh2 and the telnet tasks both need a real socket, each channel is
+//! bridged to one end of a Unix socket pair by a relay thread; the next hop
+//! (or the target session) runs over the other end.
+//!
+//! Closing the target session closes its end of the pair, which stops the
+//! last relay and drops that hop's session, and so on down the chain. A
+//! bastion's own ProxyJump from `~/.ssh/config` is not followed.
+//!
+//! Each hop costs one OS thread for as long as the session is open. The
+//! thread sleeps in `poll` while the tunnel is idle, but wakes once a second
+//! to check its sockets, so a session through three bastions holds three
+//! threads. That is fine for the handful of tunnelled consoles a user keeps
+//! open; libssh2 sessions cannot be driven by the tokio runtime directly.
+
+use crate::session::EventSink;
+use crate::ssh::{login_session, SshAuthMethod, SshLogin};
+use serde::{Deserialize, Serialize};
+use ssh2::{BlockDirections, Channel};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/// Connection timeout in seconds
+const CONNECTION_TIMEOUT_SECS: u64 = 30;
+
+/// Buffer size for relaying tunnel traffic (16KB)
+const RELAY_BUFFER_SIZE: usize = 16384;
+
+/// Longest a relay sleeps before checking its sockets again
+const RELAY_POLL_TIMEOUT_MS: i32 = 1000;
+
+/// An SSH bastion to tunnel through
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JumpHost {
+    /// Hostname, address or `~/.ssh/config` alias
+    pub host: String,
+    /// Taken from `~/.ssh/config` (or 22) when missing
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Taken from `~/.ssh/config` (or the local user) when missing
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Defaults to the keys `~/.ssh/config` names for the host and the agent
+    #[serde(default = "default_jump_auth")]
+    pub auth: SshAuthMethod,
+}
+
+fn default_jump_auth() -> SshAuthMethod {
+    SshAuthMethod::Config
+}
+
+/// The connection a session runs over: a TCP socket, or the local end of a
+/// tunnel through jump hosts
+pub enum HopStream {
+    Direct(TcpStream),
+    Tunnel(UnixStream),
+}
+
+impl AsRawFd for HopStream {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            HopStream::Direct(stream) => stream.as_raw_fd(),
+            HopStream::Tunnel(stream) => stream.as_raw_fd(),
+        }
+    }
+}
+
+/// Parses a ProxyJump value: comma-separated `[user@]host[:port]` hops,
+/// optionally as `ssh://` URLs, with IPv6 addresses in brackets
+pub fn parse_proxy_jump(spec: &str) -> Vec<JumpHost> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (username, address) = match hop.rsplit_once('@') {
+                Some((user, address)) => (Some(user.to_string()), address),
+                None => (None, hop),
+            };
+
+            let (host, port) = if let Some(bracketed) = address.strip_prefix('[') {
+                match bracketed.split_once(']') {
+                    Some((host, rest)) => (host, rest.strip_prefix(':')),
+                    None => (bracketed, None),
+                }
+            } else {
+                match address.rsplit_once(':') {
+                    Some((host, port)) if !host.contains(':') => (host, Some(port)),
+                    _ => (address, None),
+                }
+            };
+
+            JumpHost {
+                host: host.to_string(),
+                port: port.and_then(|port| port.parse().ok()),
+                username,
+                auth: default_jump_auth(),
+            }
+        })
+        .collect()
+}
+
+/// Opens a TCP connection with the connection timeout
+pub fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addr = format!("{}:{}", host, port);
+    let socket_addr = addr
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve address '{}': {}", addr, e))?
+        .next()
+        .ok_or_else(|| format!("No addresses found for '{}'", addr))?;
+
+    let stream =
+        TcpStream::connect_timeout(&socket_addr, Duration::from_secs(CONNECTION_TIMEOUT_SECS))
+            .map_err(|e| format!("TCP connection to {} failed: {}", addr, e))?;
+    stream
+        .set_nodelay(true)
+        .map_err(|e| format!("Failed to set nodelay: {}", e))?;
+    Ok(stream)
+}
+
+/// Connects to `host:port`, through `jump_hosts` in order if there are any.
+///
+/// `request_id` (the ID of the session being opened) identifies each hop's
+/// host key and login prompts.
+pub fn connect(
+    sink: &EventSink,
+    request_id: &str,
+    jump_hosts: &[JumpHost],
+    host: &str,
+    port: u16,
+) -> Result<HopStream, String> {
+    if jump_hosts.is_empty() {
+        connect_tcp(host, port).map(HopStream::Direct)
+    } else {
+        tunnel(sink, request_id, jump_hosts, host, port).map(HopStream::Tunnel)
+    }
+}
+
+/// Opens a tunnel to `host:port` through `jump_hosts` (at least one), and
+/// returns its local end. `host` is resolved by the last bastion.
+pub fn tunnel(
+    sink: &EventSink,
+    request_id: &str,
+    jump_hosts: &[JumpHost],
+    host: &str,
+    port: u16,
+) -> Result<UnixStream, String> {
+    let logins = jump_hosts
+        .iter()
+        .map(|hop| SshLogin::resolve(&hop.host, hop.port, hop.username.clone(), hop.auth.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = logins.first().ok_or("No jump hosts given")?;
+
+    let mut stream = HopStream::Direct(connect_tcp(&first.hostname, first.port)?);
+    for (index, login) in logins.iter().enumerate() {
+        let (next_host, next_port) = logins
+            .get(index + 1)
+            .map(|next| (next.hostname.as_str(), next.port))
+            .unwrap_or((host, port));
+        let socket_fd = stream.as_raw_fd();
+        let hop_id = format!("{}-jump{}", request_id, index + 1);
+        let session = login_session(sink, &hop_id, stream, login)?;
+        let channel = session
+            .channel_direct_tcpip(next_host, next_port, None)
+            .map_err(|e| {
+                format!(
+                    "{} could not open a tunnel to {}:{}: {}",
+                    login.host, next_host, next_port, e
+                )
+            })?;
+
+        let (local, remote) =
+            UnixStream::pair().map_err(|e| format!("Failed to create tunnel socket: {}", e))?;
+        spawn_relay(login.host.clone(), session, channel, socket_fd, local)?;
+        stream = HopStream::Tunnel(remote);
+    }
+
+    match stream {
+        HopStream::Tunnel(stream) => Ok(stream),
+        HopStream::Direct(_) => Err("No jump hosts given".to_string()),
+    }
+}
+
+/// Starts the thread that copies between a hop's channel and its socket
+/// pair; it ends, dropping the hop's session, when the tunnel closes
+fn spawn_relay(
+    label: String,
+    session: ssh2::Session,
+    channel: Channel,
+    socket_fd: RawFd,
+    local: UnixStream,
+) -> Result<(), String> {
+    local
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+    std::thread::Builder::new()
+        .name(format!("jump-{}", label))
+        .spawn(move || relay(&session, channel, socket_fd, local))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start tunnel thread: {}", e))
+}
+
+/// Copies data both ways until either side closes. Blocks the calling
+/// thread throughout, waking at least every `RELAY_POLL_TIMEOUT_MS`.
+fn relay(session: &ssh2::Session, mut channel: Channel, socket_fd: RawFd, mut local: UnixStream) {
+    session.set_blocking(false);
+
+    let mut buf = [0u8; RELAY_BUFFER_SIZE];
+    // Data read from one side that the other has not accepted yet
+    let mut to_local: Vec<u8> = Vec::new();
+    let mut to_remote: Vec<u8> = Vec::new();
+
+    loop {
+        let mut progress = false;
+
+        if to_local.is_empty() {
+            match channel.read(&mut buf) {
+                Ok(0) if channel.eof() => return,
+                Ok(0) => {}
+                Ok(n) => {
+                    to_local.extend_from_slice(&buf[..n]);
+                    progress = true;
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(_) => return,
+            }
+        }
+        if !to_local.is_empty() {
+            match local.write(&to_local) {
+                Ok(n) => {
+                    to_local.drain(..n);
+                    progress = true;
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(_) => return,
+            }
+        }
+
+        if to_remote.is_empty() {
+            match local.read(&mut buf) {
+                // The session running over the tunnel is gone
+                Ok(0) => return,
+                Ok(n) => {
+                    to_remote.extend_from_slice(&buf[..n]);
+                    progress = true;
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(_) => return,
+            }
+        }
+        if !to_remote.is_empty() {
+            match channel.write(&to_remote) {
+                Ok(n) => {
+                    to_remote.drain(..n);
+                    progress = true;
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(_) => return,
+            }
+        }
+
+        if progress {
+            continue;
+        }
+
+        // Sleep until libssh2's socket or the local end can move data
+        let remote_events = match session.block_directions() {
+            BlockDirections::Outbound => libc::POLLOUT,
+            BlockDirections::Both => libc::POLLIN | libc::POLLOUT,
+            _ => libc::POLLIN,
+        };
+        let mut local_events = 0;
+        if to_remote.is_empty() {
+            local_events |= libc::POLLIN;
+        }
+        if !to_local.is_empty() {
+            local_events |= libc::POLLOUT;
+        }
+        let mut fds = [
+            libc::pollfd {
+                fd: socket_fd,
+                events: remote_events,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: local.as_raw_fd(),
+                events: local_events,
+                revents: 0,
+            },
+        ];
+        // SAFETY: `fds` is a valid array of two pollfd structs for the whole call
+        unsafe {
+            libc::poll(
+                fds.as_mut_ptr(),
+                fds.len() as libc::nfds_t,
+                RELAY_POLL_TIMEOUT_MS,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proxy_jump() {
+        let hops =
+            parse_proxy_jump("netops@bastion.example.net:2222, edge ,ssh://root@[2001:db8::1]:22");
+        assert_eq!(hops.len(), 3);
+
+        assert_eq!(hops[0].host, "bastion.example.net");
+        assert_eq!(hops[0].port, Some(2222));
+        assert_eq!(hops[0].username.as_deref(), Some("netops"));
+        assert!(matches!(hops[0].auth, SshAuthMethod::Config));
+
+        assert_eq!(hops[1].host, "edge");
+        assert_eq!(hops[1].port, None);
+        assert_eq!(hops[1].username, None);
+
+        assert_eq!(hops[2].host, "2001:db8::1");
+        assert_eq!(hops[2].port, Some(22));
+        assert_eq!(hops[2].username.as_deref(), Some("root"));
+
+        // A bare IPv6 address has no port
+        assert_eq!(parse_proxy_jump("2001:db8::2")[0].host, "2001:db8::2");
+        assert!(parse_proxy_jump("").is_empty());
+    }
+}
@@ -0,0 +1,455 @@
+//! Host Key Verification Module for Packet
+//!
+//! Checks SSH server host keys before any credentials are sent.
+//!
+//! # Trust Stores
+//!
+//! Keys are looked up in two OpenSSH-format files:
+//! - `~/.ssh/known_hosts`, shared with OpenSSH and never written by Packet
+//! - `<config dir>/packet/known_hosts`, managed by Packet
+//!
+//! A matching key lets the connection continue. A different key of the same
+//! type for a known host, or a key marked `@revoked`, fails the connection
+//! outright, since that is what a man-in-the-middle looks like. An unknown
+//! host (or one only known by keys of other types, as OpenSSH treats it)
+//! triggers an `ssh-host-key` event carrying the key's SHA256 fingerprint;
+//! the connection waits until the frontend answers with `respond_host_key`,
+//! and accepted keys are appended to Packet's file.
+//!
+//! Sessions the daemon opens for a Packet window ask that window, which
+//! answers through the daemon. Ones it opens on its own (port forwards,
+//! command line) have nobody to ask and refuse unknown hosts until the key
+//! is trusted from the Packet window or added to `~/.ssh/known_hosts`.
+
+use crate::daemon::{can_prompt, remote_answer, SessionCommand};
+use crate::session::EventSink;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssh2::{CheckResult, HostKeyType, KnownHostFileKind, KnownHosts};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long a connection waits for the user to answer a host key prompt
+const HOST_KEY_PROMPT_TIMEOUT_SECS: u64 = 120;
+
+/// Connections waiting for an answer to a host key prompt, by request ID
+static PENDING_PROMPTS: Lazy<Mutex<HashMap<String, mpsc::SyncSender<bool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Event payload asking the user whether to trust an unknown host key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HostKeyPrompt {
+    #[serde(rename = "requestId")]
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    #[serde(rename = "keyType")]
+    pub key_type: String,
+    /// OpenSSH-style fingerprint, e.g. `SHA256:nThbg6kX...`
+    pub fingerprint: String,
+    /// The host is already trusted with a key of another type
+    #[serde(rename = "otherKeyKnown")]
+    pub other_key_known: bool,
+}
+
+/// Which file a trusted key came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnownHostSource {
+    /// `~/.ssh/known_hosts`
+    OpenSsh,
+    /// Packet's own known_hosts file
+    Packet,
+}
+
+/// A trusted host key (for frontend display)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KnownHostEntry {
+    /// Host patterns as written in the file (hashed names start with `|1|`)
+    pub hosts: Vec<String>,
+    #[serde(rename = "keyType")]
+    pub key_type: String,
+    pub fingerprint: String,
+    pub source: KnownHostSource,
+}
+
+/// Checks the server's host key for `host:port`, asking the user through
+/// `sink` when the host is unknown.
+///
+/// `request_id` identifies the prompt (the session ID being opened).
+pub fn verify_host_key(
+    session: &ssh2::Session,
+    sink: &EventSink,
+    request_id: &str,
+    host: &str,
+    port: u16,
+) -> Result<(), String> {
+    let (key, key_type) = session.host_key().ok_or("Server did not send a host key")?;
+    let key_type = key_type_name(key_type).ok_or("Server sent an unsupported host key type")?;
+    let fingerprint = fingerprint(key);
+    // libssh2's own port handling falls back to the bare host, which OpenSSH
+    // does not; `[host]:port` keeps servers sharing an address apart
+    let pattern = host_pattern(host, port);
+    let mut other_key_known = false;
+
+    for (source, path) in trust_stores() {
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        if is_revoked(&content, key) {
+            return Err(format!(
+                "Host key {} for {}:{} is marked as revoked in {}",
+                fingerprint,
+                host,
+                port,
+                path.display()
+            ));
+        }
+
+        // Only keys of the type the server offered can confirm or contradict it
+        let known_hosts = load_known_hosts(session, &content, Some(key_type))?;
+        match known_hosts.check(&pattern, key) {
+            CheckResult::Match => {
+                println!("[SSH] Host key for {} matches {}", host, path.display());
+                return Ok(());
+            }
+            CheckResult::Mismatch => {
+                return Err(format!(
+                    "HOST KEY MISMATCH for {}:{}! The server sent {} key {}, which does not match \
+                     the key trusted in {}. Someone may be intercepting the connection. If the \
+                     key was changed on purpose, remove the old key{} and connect again.",
+                    host,
+                    port,
+                    key_type,
+                    fingerprint,
+                    path.display(),
+                    match source {
+                        KnownHostSource::OpenSsh => " with `ssh-keygen -R`",
+                        KnownHostSource::Packet => "",
+                    }
+                ));
+            }
+            CheckResult::NotFound | CheckResult::Failure => {}
+        }
+
+        let all_types = load_known_hosts(session, &content, None)?;
+        if let CheckResult::Mismatch = all_types.check(&pattern, key) {
+            other_key_known = true;
+        }
+    }
+
+    // Unknown host: trust on first use, if there is someone to ask
+    let app = match sink {
+        EventSink::App(_) => sink,
+        EventSink::Daemon if can_prompt() => sink,
+        EventSink::Daemon => {
+            return Err(format!(
+                "Unknown host key for {}:{} ({} {}). Connect once from the Packet window to \
+                 trust it, or add it to ~/.ssh/known_hosts.",
+                host, port, key_type, fingerprint
+            ))
+        }
+    };
+
+    let (answer, response) = mpsc::sync_channel(1);
+    PENDING_PROMPTS
+        .lock()
+        .insert(request_id.to_string(), answer);
+    app.emit(
+        "ssh-host-key",
+        request_id,
+        HostKeyPrompt {
+            request_id: request_id.to_string(),
+            host: host.to_string(),
+            port,
+            key_type: key_type.to_string(),
+            fingerprint: fingerprint.clone(),
+            other_key_known,
+        },
+    );
+    println!(
+        "[SSH] Waiting for host key confirmation for {}:{}",
+        host, port
+    );
+
+    let trusted = response.recv_timeout(Duration::from_secs(HOST_KEY_PROMPT_TIMEOUT_SECS));
+    PENDING_PROMPTS.lock().remove(request_id);
+
+    match trusted {
+        Ok(true) => {
+            trust_host_key(host, port, key_type, key)?;
+            println!(
+                "[SSH] Trusted {} key {} for {}:{}",
+                key_type, fingerprint, host, port
+            );
+            Ok(())
+        }
+        Ok(false) => Err(format!("Host key for {}:{} was not trusted", host, port)),
+        Err(_) => Err("Timed out waiting for host key confirmation".to_string()),
+    }
+}
+
+/// Appends a key to Packet's known_hosts file
+fn trust_host_key(host: &str, port: u16, key_type: &str, key: &[u8]) -> Result<(), String> {
+    let path = packet_known_hosts_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let line = format!(
+        "{} {} {}\n",
+        host_pattern(host, port),
+        key_type,
+        STANDARD.encode(key)
+    );
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to save host key: {}", e))
+}
+
+/// Loads the plain entries of a known_hosts file into libssh2, optionally
+/// only those for one key type.
+///
+/// libssh2 compares keys of any type, so without the filter a host known by
+/// its ECDSA key would "mismatch" an Ed25519 key. Lines are fed one at a time
+/// so an entry it does not understand (security keys) doesn't hide the rest.
+fn load_known_hosts(
+    session: &ssh2::Session,
+    content: &str,
+    key_type: Option<&str>,
+) -> Result<KnownHosts, String> {
+    let mut known_hosts = session
+        .known_hosts()
+        .map_err(|e| format!("Failed to initialise known hosts: {}", e))?;
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let plain = fields.len() >= 3 && !fields[0].starts_with(['#', '@']);
+        if plain && key_type.is_none_or(|key_type| fields[1] == key_type) {
+            let _ = known_hosts.read_str(line, KnownHostFileKind::OpenSSH);
+        }
+    }
+    Ok(known_hosts)
+}
+
+/// Whether `key` appears on a `@revoked` line
+fn is_revoked(content: &str, key: &[u8]) -> bool {
+    content.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.len() >= 4
+            && fields[0] == "@revoked"
+            && STANDARD
+                .decode(fields[3])
+                .is_ok_and(|revoked| revoked == key)
+    })
+}
+
+/// Known hosts files in lookup order
+fn trust_stores() -> Vec<(KnownHostSource, PathBuf)> {
+    let mut stores = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        stores.push((
+            KnownHostSource::OpenSsh,
+            home.join(".ssh").join("known_hosts"),
+        ));
+    }
+    stores.push((KnownHostSource::Packet, packet_known_hosts_path()));
+    stores
+}
+
+/// Path of the known_hosts file managed by Packet
+fn packet_known_hosts_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("packet")
+        .join("known_hosts")
+}
+
+/// Host as written in known_hosts: bare for port 22, `[host]:port` otherwise
+fn host_pattern(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// OpenSSH name of a host key algorithm
+fn key_type_name(key_type: HostKeyType) -> Option<&'static str> {
+    match key_type {
+        HostKeyType::Rsa => Some("ssh-rsa"),
+        HostKeyType::Dss => Some("ssh-dss"),
+        HostKeyType::Ecdsa256 => Some("ecdsa-sha2-nistp256"),
+        HostKeyType::Ecdsa384 => Some("ecdsa-sha2-nistp384"),
+        HostKeyType::Ecdsa521 => Some("ecdsa-sha2-nistp521"),
+        HostKeyType::Ed25519 => Some("ssh-ed25519"),
+        HostKeyType::Unknown => None,
+    }
+}
+
+/// OpenSSH-style SHA256 fingerprint of a raw public key
+pub fn fingerprint(key: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(key)))
+}
+
+/// Parses the plain key entries of a known_hosts file. `@cert-authority` and
+/// `@revoked` lines are skipped.
+fn parse_known_hosts(content: &str, source: KnownHostSource) -> Vec<KnownHostEntry> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+                return None;
+            }
+            let mut fields = line.split_whitespace();
+            let hosts = fields.next()?;
+            let key_type = fields.next()?;
+            let key = STANDARD.decode(fields.next()?).ok()?;
+            Some(KnownHostEntry {
+                hosts: hosts.split(',').map(str::to_string).collect(),
+                key_type: key_type.to_string(),
+                fingerprint: fingerprint(&key),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Removes the lines trusting `pattern` (optionally only the key with
+/// `fingerprint`), returning the new file content and how many were removed
+fn remove_entries(content: &str, pattern: &str, fingerprint: Option<&str>) -> (String, usize) {
+    let mut kept = String::with_capacity(content.len());
+    let mut removed = 0;
+
+    for line in content.lines() {
+        let matches = parse_known_hosts(line, KnownHostSource::Packet)
+            .first()
+            .is_some_and(|entry| {
+                entry.hosts.iter().any(|host| host == pattern)
+                    && fingerprint.is_none_or(|fingerprint| entry.fingerprint == fingerprint)
+            });
+        if matches {
+            removed += 1;
+        } else {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+
+    (kept, removed)
+}
+
+/// Answers a pending `ssh-host-key` prompt, here or in the session daemon
+#[tauri::command]
+pub fn respond_host_key(request_id: String, trust: bool) -> Result<(), String> {
+    let Some(answer) = PENDING_PROMPTS.lock().remove(&request_id) else {
+        return remote_answer(SessionCommand::RespondHostKey { request_id, trust }).unwrap_or_else(
+            || Err("No host key confirmation is pending for this connection".to_string()),
+        );
+    };
+    answer
+        .send(trust)
+        .map_err(|_| "Connection is no longer waiting for an answer".to_string())
+}
+
+/// Lists trusted host keys from both known_hosts files
+#[tauri::command]
+pub fn list_known_hosts() -> Vec<KnownHostEntry> {
+    trust_stores()
+        .into_iter()
+        .flat_map(|(source, path)| {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            parse_known_hosts(&content, source)
+        })
+        .collect()
+}
+
+/// Removes a trusted key from Packet's known_hosts file
+///
+/// Removes every key for `host:port`, or only the one with `fingerprint`.
+/// Returns the number of keys removed. Keys in `~/.ssh/known_hosts` are left
+/// to OpenSSH (`ssh-keygen -R`).
+#[tauri::command]
+pub fn remove_known_host(
+    host: String,
+    port: u16,
+    fingerprint: Option<String>,
+) -> Result<usize, String> {
+    let path = packet_known_hosts_path();
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let pattern = host_pattern(&host, port);
+    let (kept, removed) = remove_entries(&content, &pattern, fingerprint.as_deref());
+
+    if removed == 0 {
+        return Err(format!(
+            "No key for {} in Packet's known hosts (keys in ~/.ssh/known_hosts are removed with `ssh-keygen -R`)",
+            pattern
+        ));
+    }
+
+    fs::write(&path, kept).map_err(|e| format!("Failed to update known hosts: {}", e))?;
+    println!("[SSH] Removed {} host key(s) for {}", removed, pattern);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN_HOSTS: &str = "\
+# comment
+r1.lab,10.0.0.1 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOTq7TTwvhhJpwgqIF8aCxi77LQvHpkaqPxgDz7nF0Bg
+[10.0.0.2]:2222 ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQC7
+@cert-authority *.lab ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOTq7TTwvhhJpwgqIF8aCxi77LQvHpkaqPxgDz7nF0Bg
+";
+
+    #[test]
+    fn test_host_pattern() {
+        assert_eq!(host_pattern("r1.lab", 22), "r1.lab");
+        assert_eq!(host_pattern("10.0.0.2", 2222), "[10.0.0.2]:2222");
+    }
+
+    #[test]
+    fn test_parse_and_remove() {
+        let entries = parse_known_hosts(KNOWN_HOSTS, KnownHostSource::Packet);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hosts, vec!["r1.lab", "10.0.0.1"]);
+        assert_eq!(entries[0].key_type, "ssh-ed25519");
+        // Same as `ssh-keygen -lf`
+        assert_eq!(
+            entries[0].fingerprint,
+            "SHA256:cu09ntKrM2nJgfb+h6Ol7s3bXv83Ki4XEfxwv7RdvMA"
+        );
+        assert_eq!(entries[1].hosts, vec!["[10.0.0.2]:2222"]);
+
+        let (kept, removed) = remove_entries(KNOWN_HOSTS, "10.0.0.1", Some("SHA256:wrong"));
+        assert_eq!(removed, 0);
+        assert_eq!(kept, KNOWN_HOSTS);
+
+        let (kept, removed) = remove_entries(KNOWN_HOSTS, "10.0.0.1", None);
+        assert_eq!(removed, 1);
+        assert!(!kept.contains("r1.lab"));
+        assert!(kept.contains("# comment") && kept.contains("@cert-authority"));
+    }
+
+    #[test]
+    fn test_revoked() {
+        let key = STANDARD.decode("AAAAB3NzaC1yc2EAAAADAQABAAAAgQC7").unwrap();
+        assert!(!is_revoked(KNOWN_HOSTS, &key));
+        let revoked = format!(
+            "{}@revoked * ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQC7\n",
+            KNOWN_HOSTS
+        );
+        assert!(is_revoked(&revoked, &key));
+    }
+}
@@ -9,6 +9,9 @@
 //! - Multiple concurrent terminal sessions (local PTY, telnet, or SSH)
 //! - Telnet connections to GNS3 network devices
 //! - SSH connections to network devices and servers
+//! - Serial consoles on local ports (USB console cables)
+//! - VM consoles on Unix domain sockets (QEMU/libvirt), raw or telnet framed
+//! - Raw TCP consoles with no telnet processing
 //! - Session logging with command-based control (:l/:el commands)
 //! - Broadcast commands to selected terminals
 //! - Per-terminal broadcast toggle
@@ -30,20 +33,62 @@
 //! - Local PTY sessions (bash/shell)
 //! - Telnet sessions (GNS3 routers/switches)
 //! - SSH sessions (network devices, servers)
+//! - Serial sessions (console cables)
+//! - Unix socket sessions (VM consoles)
+//! - Raw TCP sessions (consoles without telnet negotiation)
+//!
+//! All of them implement the common `Session` trait and are tracked by a
+//! single `SessionManager`, so write/resize/close/list work the same way
+//! for every connection type.
+//!
+//! Started with `--daemon`, Packet runs headless and hosts sessions for GUI
+//! instances that attach over a Unix socket, so sessions survive closing
+//! the window.
 
+mod auth_prompt;
+mod broadcast;
 mod cli;
+mod daemon;
+mod forward;
+mod jump;
+mod known_hosts;
 mod logging;
+mod output;
 mod profiles;
 mod pty;
+mod scrollback;
+mod serial;
+mod session;
+mod socket;
 mod ssh;
+mod ssh_config;
 mod telnet;
+mod utf8;
 
-use cli::{get_cli_connection, init_cli, parse_args_to_connection};
+use auth_prompt::respond_auth_prompt;
+use broadcast::{broadcast_telnet_command, broadcast_write};
+use cli::{daemon_mode, get_cli_connection, init_cli, parse_args_to_connection};
+use daemon::{
+    daemon_attach, daemon_detach, daemon_list_sessions, daemon_open_session, daemon_start,
+};
+use forward::{add_ssh_forward, list_ssh_forwards, remove_ssh_forward};
+use known_hosts::{list_known_hosts, remove_known_host, respond_host_key};
 use logging::{list_session_logs, start_logging, stop_logging};
+use output::subscribe_output;
 use profiles::{create_profile, delete_profile, get_profile, list_profiles, update_profile, ProfileStore};
 use pty::{kill_pty, resize_pty, spawn_pty, write_to_pty, PtyState};
-use ssh::{connect_ssh, disconnect_ssh, list_ssh_sessions, resize_ssh, write_ssh};
-use telnet::{connect_telnet, disconnect_telnet, list_telnet_sessions, write_telnet};
+use scrollback::{get_scrollback, set_scrollback_limit};
+use serial::{connect_serial, list_serial_ports, serial_break, serial_configure, serial_status};
+use session::{list_sessions, session_close, session_resize, session_write};
+use socket::{connect_raw, connect_unix_socket};
+use ssh::{
+    connect_ssh, disconnect_ssh, list_agent_identities, list_ssh_sessions, resize_ssh, write_ssh,
+};
+use ssh_config::{list_ssh_config_hosts, resolve_ssh_config};
+use telnet::{
+    connect_telnet, disconnect_telnet, list_telnet_sessions, resize_telnet, send_telnet_command,
+    telnet_com_port_break, telnet_com_port_configure, telnet_com_port_status, write_telnet,
+};
 use tauri::{Emitter, Manager};
 
 /// Application entry point for Tauri.
@@ -59,6 +104,15 @@ use tauri::{Emitter, Manager};
 pub fn run() {
     // Parse CLI arguments first
     let cli_connection = init_cli();
+
+    // Headless session daemon: no window, just the session backends
+    if daemon_mode() {
+        if let Err(e) = daemon::run_daemon() {
+            eprintln!("[Daemon] {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     
     if let Some(ref conn) = cli_connection {
         println!("[Packet] Starting with connection: {} ({}:{})", 
@@ -87,6 +141,7 @@ pub fn run() {
                         "host": conn.host,
                         "port": conn.port,
                         "name": conn.name,
+                        "raw": conn.raw,
                     }));
                 }
             }
@@ -94,6 +149,20 @@ pub fn run() {
         .manage(PtyState::default())
         .manage(ProfileStore::new())
         .invoke_handler(tauri::generate_handler![
+            // Generic session commands (any connection type)
+            session_write,
+            session_resize,
+            session_close,
+            list_sessions,
+            broadcast_write,
+            broadcast_telnet_command,
+            subscribe_output,
+            // Session daemon commands (detachable sessions)
+            daemon_start,
+            daemon_open_session,
+            daemon_attach,
+            daemon_detach,
+            daemon_list_sessions,
             // PTY commands (local shell)
             spawn_pty,
             write_to_pty,
@@ -104,16 +173,47 @@ pub fn run() {
             write_telnet,
             disconnect_telnet,
             list_telnet_sessions,
+            resize_telnet,
+            send_telnet_command,
+            telnet_com_port_configure,
+            telnet_com_port_break,
+            telnet_com_port_status,
             // SSH commands (network devices, servers)
             connect_ssh,
             write_ssh,
             resize_ssh,
             disconnect_ssh,
             list_ssh_sessions,
+            list_agent_identities,
+            respond_auth_prompt,
+            // ~/.ssh/config commands
+            resolve_ssh_config,
+            list_ssh_config_hosts,
+            // SSH port forward commands
+            add_ssh_forward,
+            remove_ssh_forward,
+            list_ssh_forwards,
+            // SSH host key commands
+            respond_host_key,
+            list_known_hosts,
+            remove_known_host,
+            // Serial commands (console cables)
+            connect_serial,
+            serial_configure,
+            serial_break,
+            serial_status,
+            list_serial_ports,
+            // Unix socket commands (QEMU/libvirt consoles)
+            connect_unix_socket,
+            // Raw TCP commands
+            connect_raw,
             // Logging commands
             start_logging,
             stop_logging,
             list_session_logs,
+            // Scrollback commands
+            get_scrollback,
+            set_scrollback_limit,
             // Profile commands
             create_profile,
             update_profile,
@@ -0,0 +1,223 @@
+//! Output Delivery Module for Packet
+//!
+//! Coalesces session output and delivers it straight to the pane that
+//! displays it. Emitting one global event per read floods the webview when a
+//! device dumps `show tech` or a debug storm, and every listener has to
+//! filter by session ID.
+//!
+//! # Architecture
+//!
+//! Reader tasks push decoded output into an [`OutputSender`]. A pump task per
+//! session merges everything that arrives within a short window (or until a
+//! size cap) and sends the batch on the session's `tauri::ipc::Channel`,
+//! registered when the session is opened or later via `subscribe_output`
+//! (e.g. by a pane restored after the webview reloaded). Sessions without a
+//! channel fall back to the legacy `*-output` events, which is also how
+//! batches travel from the session daemon to the GUI.
+//!
+//! A session's channel slot is created when it registers with the session
+//! manager and dropped when it unregisters, so a connection that fails
+//! halfway never leaves one behind.
+
+use crate::session::{sessions, EventSink};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::ipc::Channel;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+/// Longest time output is held back to be merged with what follows
+const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(8);
+
+/// A batch is flushed early once it reaches this size (64KB)
+const OUTPUT_FLUSH_BYTES: usize = 64 * 1024;
+
+/// The channel a session's output is delivered on, shared with its pump
+type ChannelSlot = Arc<Mutex<Option<Channel<String>>>>;
+
+/// Global state for all output channels
+/// Maps session_id -> channel slot
+static OUTPUT_CHANNELS: Lazy<Mutex<HashMap<String, ChannelSlot>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the channel slot of a registered session.
+///
+/// Unknown sessions get a detached slot that is never stored, so late calls
+/// for a session that already closed cannot bring its entry back.
+fn channel_slot(session_id: &str) -> ChannelSlot {
+    OUTPUT_CHANNELS
+        .lock()
+        .get(session_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Sends output on the session's channel.
+///
+/// Returns false if no channel is registered, so the caller can fall back
+/// to emitting an event.
+pub fn send_to_channel(session_id: &str, data: &str) -> bool {
+    let slot = OUTPUT_CHANNELS.lock().get(session_id).cloned();
+    match slot {
+        Some(slot) => send_on_slot(&slot, data),
+        None => false,
+    }
+}
+
+fn send_on_slot(slot: &ChannelSlot, data: &str) -> bool {
+    match slot.lock().as_ref() {
+        Some(channel) => channel.send(data.to_string()).is_ok(),
+        None => false,
+    }
+}
+
+/// Creates the empty channel slot of a session being registered
+pub fn init_output_channel(session_id: &str) {
+    OUTPUT_CHANNELS
+        .lock()
+        .insert(session_id.to_string(), ChannelSlot::default());
+}
+
+/// Registers the channel a session's output is delivered on
+pub fn register_output_channel(session_id: &str, channel: Channel<String>) {
+    *channel_slot(session_id).lock() = Some(channel);
+}
+
+/// Drops the output channel for a session (called when session is closed).
+pub fn cleanup_output_channel(session_id: &str) {
+    OUTPUT_CHANNELS.lock().remove(session_id);
+}
+
+/// Handle reader tasks use to queue decoded output for delivery
+pub struct OutputSender {
+    tx: UnboundedSender<String>,
+    pump: JoinHandle<()>,
+}
+
+impl OutputSender {
+    /// Queues output; never blocks the reader
+    pub fn send(&self, data: String) {
+        let _ = self.tx.send(data);
+    }
+
+    /// Flushes queued output and waits for the pump to deliver it.
+    ///
+    /// Called before a final status event so it is not overtaken by output.
+    pub async fn finish(self) {
+        drop(self.tx);
+        let _ = self.pump.await;
+    }
+}
+
+/// Starts the pump that batches a session's output.
+///
+/// `event` and `payload` describe the fallback event used when no channel
+/// is registered. The pump stops once the [`OutputSender`] is dropped,
+/// after flushing what is left.
+pub fn spawn_output_pump<P, F>(
+    sink: EventSink,
+    session_id: &str,
+    channel: Option<Channel<String>>,
+    event: &'static str,
+    payload: F,
+) -> OutputSender
+where
+    P: Serialize + Clone + 'static,
+    F: Fn(&str, String) -> P + Send + 'static,
+{
+    let slot = channel_slot(session_id);
+    if let Some(channel) = channel {
+        *slot.lock() = Some(channel);
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let pump = tauri::async_runtime::spawn(run_output_pump(
+        sink,
+        session_id.to_string(),
+        slot,
+        rx,
+        event,
+        payload,
+    ));
+    OutputSender { tx, pump }
+}
+
+async fn run_output_pump<P, F>(
+    sink: EventSink,
+    session_id: String,
+    slot: ChannelSlot,
+    mut rx: UnboundedReceiver<String>,
+    event: &'static str,
+    payload: F,
+) where
+    P: Serialize + Clone,
+    F: Fn(&str, String) -> P,
+{
+    let mut open = true;
+    while open {
+        let Some(mut batch) = rx.recv().await else {
+            break;
+        };
+
+        // Merge whatever else arrives within the window
+        let deadline = Instant::now() + OUTPUT_FLUSH_INTERVAL;
+        while batch.len() < OUTPUT_FLUSH_BYTES {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(more)) => batch.push_str(&more),
+                Ok(None) => {
+                    open = false;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        if !send_on_slot(&slot, &batch) {
+            sink.emit(event, &session_id, payload(&session_id, batch));
+        }
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Delivers a session's output on a new channel (e.g. after the pane was recreated).
+///
+/// # Arguments
+/// * `session_id` - The terminal session ID
+/// * `on_output` - Channel that receives batched output text
+#[tauri::command]
+pub fn subscribe_output(session_id: String, on_output: Channel<String>) -> Result<(), String> {
+    sessions().get(&session_id)?;
+    register_output_channel(&session_id, on_output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_only_exist_for_registered_sessions() {
+        channel_slot("output-test-unknown");
+        assert!(!OUTPUT_CHANNELS.lock().contains_key("output-test-unknown"));
+
+        init_output_channel("output-test");
+        assert!(Arc::ptr_eq(
+            &channel_slot("output-test"),
+            &channel_slot("output-test")
+        ));
+
+        // A pump flushing after the session closed must not re-create the slot
+        cleanup_output_channel("output-test");
+        assert!(!send_to_channel("output-test", "late output"));
+        channel_slot("output-test");
+        assert!(!OUTPUT_CHANNELS.lock().contains_key("output-test"));
+    }
+}
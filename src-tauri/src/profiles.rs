@@ -5,6 +5,11 @@
  * Profiles are stored in JSON format in the app's config directory.
  */
 
+use crate::forward::ForwardSpec;
+use crate::jump::JumpHost;
+use crate::pty::PtyCommand;
+use crate::socket::RawOptions;
+use crate::telnet::{ComPortSettings, TelnetOptions};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -19,6 +24,14 @@ use uuid::Uuid;
 pub enum ProfileConnectionType {
     Ssh,
     Telnet,
+    /// Local program in a PTY (host and port are unused)
+    Local,
+    /// Local serial port (host holds the device path, port is unused)
+    Serial,
+    /// Console on a Unix domain socket (host holds the socket path, port is unused)
+    Unix,
+    /// Raw TCP connection with no telnet processing
+    Raw,
 }
 
 /// SSH authentication method for profiles
@@ -27,6 +40,11 @@ pub enum ProfileConnectionType {
 pub enum ProfileAuthMethod {
     Password,
     PublicKey,
+    Agent,
+    #[serde(rename = "keyboard-interactive")]
+    KeyboardInteractive,
+    /// Keys named in ~/.ssh/config
+    Config,
 }
 
 /// A saved connection profile
@@ -41,6 +59,25 @@ pub struct ConnectionProfile {
     pub username: Option<String>,
     pub auth_method: Option<ProfileAuthMethod>,
     pub key_path: Option<String>,
+    // Telnet-specific fields (newline mode, serial control, TLS); on Unix
+    // socket profiles, set when the socket speaks telnet
+    #[serde(default)]
+    pub telnet_options: Option<TelnetOptions>,
+    // Local-specific fields
+    #[serde(default)]
+    pub command: Option<PtyCommand>,
+    // Serial-specific fields
+    #[serde(default)]
+    pub serial_settings: Option<ComPortSettings>,
+    // Raw TCP-specific fields (line ending translation)
+    #[serde(default)]
+    pub raw_options: Option<RawOptions>,
+    // SSH bastions to tunnel SSH and telnet connections through
+    #[serde(default)]
+    pub jump_hosts: Option<Vec<JumpHost>>,
+    // SSH port forwards started after connecting
+    #[serde(default)]
+    pub forwards: Option<Vec<ForwardSpec>>,
     // Don't store passwords for security - user must enter each time
     pub created_at: String,
     pub updated_at: String,
@@ -119,15 +156,28 @@ pub fn create_profile(
     username: Option<String>,
     auth_method: Option<String>,
     key_path: Option<String>,
+    telnet_options: Option<TelnetOptions>,
+    command: Option<PtyCommand>,
+    serial_settings: Option<ComPortSettings>,
+    raw_options: Option<RawOptions>,
+    jump_hosts: Option<Vec<JumpHost>>,
+    forwards: Option<Vec<ForwardSpec>>,
 ) -> Result<ConnectionProfile, String> {
     let conn_type = match connection_type.to_lowercase().as_str() {
         "ssh" => ProfileConnectionType::Ssh,
         "telnet" => ProfileConnectionType::Telnet,
+        "local" => ProfileConnectionType::Local,
+        "serial" => ProfileConnectionType::Serial,
+        "unix" => ProfileConnectionType::Unix,
+        "raw" => ProfileConnectionType::Raw,
         _ => return Err("Invalid connection type".to_string()),
     };
     
     let auth = auth_method.map(|m| match m.to_lowercase().as_str() {
         "publickey" => ProfileAuthMethod::PublicKey,
+        "agent" => ProfileAuthMethod::Agent,
+        "keyboard-interactive" => ProfileAuthMethod::KeyboardInteractive,
+        "config" => ProfileAuthMethod::Config,
         _ => ProfileAuthMethod::Password,
     });
     
@@ -141,6 +191,12 @@ pub fn create_profile(
         username,
         auth_method: auth,
         key_path,
+        telnet_options,
+        command,
+        serial_settings,
+        raw_options,
+        jump_hosts,
+        forwards,
         created_at: now.clone(),
         updated_at: now,
     };
@@ -166,15 +222,28 @@ pub fn update_profile(
     username: Option<String>,
     auth_method: Option<String>,
     key_path: Option<String>,
+    telnet_options: Option<TelnetOptions>,
+    command: Option<PtyCommand>,
+    serial_settings: Option<ComPortSettings>,
+    raw_options: Option<RawOptions>,
+    jump_hosts: Option<Vec<JumpHost>>,
+    forwards: Option<Vec<ForwardSpec>>,
 ) -> Result<ConnectionProfile, String> {
     let conn_type = match connection_type.to_lowercase().as_str() {
         "ssh" => ProfileConnectionType::Ssh,
         "telnet" => ProfileConnectionType::Telnet,
+        "local" => ProfileConnectionType::Local,
+        "serial" => ProfileConnectionType::Serial,
+        "unix" => ProfileConnectionType::Unix,
+        "raw" => ProfileConnectionType::Raw,
         _ => return Err("Invalid connection type".to_string()),
     };
     
     let auth = auth_method.map(|m| match m.to_lowercase().as_str() {
         "publickey" => ProfileAuthMethod::PublicKey,
+        "agent" => ProfileAuthMethod::Agent,
+        "keyboard-interactive" => ProfileAuthMethod::KeyboardInteractive,
+        "config" => ProfileAuthMethod::Config,
         _ => ProfileAuthMethod::Password,
     });
     
@@ -190,6 +259,12 @@ pub fn update_profile(
         username,
         auth_method: auth,
         key_path,
+        telnet_options,
+        command,
+        serial_settings,
+        raw_options,
+        jump_hosts,
+        forwards,
         created_at: existing.created_at.clone(),
         updated_at: now_timestamp(),
     };
@@ -2,36 +2,119 @@
 //!
 //! This module handles the creation, management, and cleanup of PTY sessions
 //! for the Packet terminal broadcast application.
+//!
+//! The PTY master is driven with non-blocking I/O on the tokio runtime, so a
+//! shell that is waiting for input does not tie up an OS thread.
+//!
+//! Sessions run the user's `$SHELL` by default. A [`PtyCommand`] runs another
+//! program instead (e.g. `virsh console`, `docker exec -it` or a netmiko REPL)
+//! with its own arguments, working directory and environment.
+//!
+//! # Process Lifecycle
+//!
+//! The shell is spawned as a session leader, so it heads its own process
+//! group. Its exit is observed through a pidfd and reported as a `pty-status`
+//! event with the exit code. Closing a session sends SIGHUP and SIGTERM to
+//! the shell's process group and escalates to SIGKILL after a grace period, so
+//! closed tabs do not leave shells or long-running commands behind. Job
+//! control shells run each job in a separate group, so every group in the
+//! shell's terminal session is signalled.
+//!
+//! pidfds and the `/proc` scan for job groups are Linux only. Elsewhere the
+//! shell is polled for its exit, and only its own group is signalled.
 
-use crate::logging::{cleanup_session_logs, write_to_logs};
-use once_cell::sync::Lazy;
+use crate::output::{spawn_output_pump, OutputSender};
+use crate::session::{record_final_output, record_output, sessions, EventSink, Session, SessionInfo, SessionKind, SessionStatus};
+use crate::utf8::Utf8Decoder;
 use parking_lot::Mutex;
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use portable_pty::{native_pty_system, Child, CommandBuilder, ExitStatus, MasterPty, PtySize};
 use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::collections::HashMap;
+use std::fs::File;
 use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, BorrowedFd, OwnedFd};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::thread;
-use tauri::{AppHandle, Emitter};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
 use uuid::Uuid;
 
 /// Buffer size for reading PTY output (8KB)
 const PTY_READ_BUFFER_SIZE: usize = 8192;
 
+/// How long a process group gets to exit after SIGHUP/SIGTERM before SIGKILL
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Poll interval for reaping when no pidfd is available
+const REAP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Represents an active PTY session with its associated handles.
 pub struct PtySession {
+    /// Session identifier
+    pub id: String,
+    /// Command line running in the PTY
+    pub command: String,
     /// Master PTY handle for resize and control operations
-    pub master: Box<dyn MasterPty + Send>,
-    /// Writer handle for sending input to the PTY
-    pub writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    pub master: Mutex<Box<dyn MasterPty + Send>>,
+    /// Queue of input bytes, drained by the session's I/O task
+    pub writer: UnboundedSender<Vec<u8>>,
+    /// Whether the shell is still running
+    pub running: Arc<Mutex<bool>>,
+    /// Wakes the I/O task to terminate the shell when the session is closed
+    pub shutdown: Arc<Notify>,
 }
 
-/// Global static state for all PTY sessions.
-/// Using Lazy + Mutex ensures thread-safe access across all Tauri commands.
-static PTY_SESSIONS: Lazy<Arc<Mutex<HashMap<String, PtySession>>>> = Lazy::new(|| {
-    println!("[PTY] Initializing global PTY session store");
-    Arc::new(Mutex::new(HashMap::new()))
-});
+impl Session for PtySession {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.writer
+            .send(data.to_vec())
+            .map_err(|_| "Failed to write to PTY: session closed".to_string())
+    }
+
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        self.master
+            .lock()
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| format!("Failed to resize PTY: {}", e))
+    }
+
+    fn close(&self) -> Result<(), String> {
+        // The I/O task signals the process group and reaps the shell
+        self.shutdown.notify_one();
+        Ok(())
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            kind: SessionKind::Pty,
+            label: self.command.clone(),
+            status: self.status(),
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        if *self.running.lock() {
+            SessionStatus::Connected
+        } else {
+            SessionStatus::Disconnected
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
 /// Event payload emitted when PTY output is available.
 #[derive(Clone, Serialize, Deserialize)]
@@ -41,19 +124,112 @@ pub struct PtyOutput {
     pub data: String,
 }
 
-/// Spawns a new PTY session with the user's default shell.
+/// Event payload emitted when the shell in a PTY exits.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PtyStatus {
+    #[serde(rename = "ptyId")]
+    pub pty_id: String,
+    /// Always "exited" for now
+    pub status: String,
+    pub message: String,
+    /// Exit code of the shell, if it could be reaped
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<u32>,
+}
+
+/// Program to run in a PTY instead of the user's shell.
+///
+/// Every field is optional; an empty command starts `$SHELL`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PtyCommand {
+    /// Program to run, looked up in `PATH` (defaults to `$SHELL`)
+    pub program: Option<String>,
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+    /// Working directory (defaults to the home directory; supports ~)
+    pub cwd: Option<String>,
+    /// Extra environment variables, applied over the inherited environment
+    pub env: HashMap<String, String>,
+}
+
+impl PtyCommand {
+    /// Command line shown as the session label
+    fn label(&self, program: &str) -> String {
+        std::iter::once(program)
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Builds the command to spawn, with Packet's terminal settings
+    fn to_builder(&self) -> Result<(CommandBuilder, String), String> {
+        // Determine the user's shell (fallback to bash)
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
+        let program = self
+            .program
+            .as_deref()
+            .map(str::trim)
+            .filter(|program| !program.is_empty())
+            .unwrap_or(&shell);
+
+        let mut cmd = CommandBuilder::new(program);
+        cmd.args(&self.args);
+        cmd.env("TERM", "xterm-256color");
+        cmd.env("COLORTERM", "truecolor");
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+
+        // portable-pty silently falls back to the home directory otherwise
+        if let Some(cwd) = self.cwd.as_deref().filter(|cwd| !cwd.trim().is_empty()) {
+            let dir = expand_home(cwd.trim());
+            if !dir.is_dir() {
+                return Err(format!("Working directory does not exist: {}", dir.display()));
+            }
+            cmd.cwd(dir);
+        }
+
+        Ok((cmd, self.label(program)))
+    }
+}
+
+/// Expands a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Spawns a new PTY session running `command`, or the user's default shell.
+///
+/// Output is delivered on `on_output` when given, otherwise as `pty-output` events.
 #[tauri::command]
 pub fn spawn_pty(
     app: AppHandle,
     cols: u16,
     rows: u16,
+    command: Option<PtyCommand>,
+    on_output: Option<Channel<String>>,
 ) -> Result<String, String> {
     println!("[PTY] spawn_pty called with cols={}, rows={}", cols, rows);
-    
-    // Force initialization of the lazy static
-    let sessions_ptr = PTY_SESSIONS.clone();
-    println!("[PTY] Got sessions reference: {:p}", Arc::as_ptr(&sessions_ptr));
-    
+    open_pty(EventSink::App(app), cols, rows, command, on_output)
+}
+
+/// Spawns a PTY session whose events are delivered to `sink`.
+pub fn open_pty(
+    sink: EventSink,
+    cols: u16,
+    rows: u16,
+    command: Option<PtyCommand>,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    let (cmd, label) = command.unwrap_or_default().to_builder()?;
+    println!("[PTY] Running: {}", label);
+
     let pty_system = native_pty_system();
 
     // Open a new PTY pair (master + slave)
@@ -66,163 +242,428 @@ pub fn spawn_pty(
         })
         .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
-    // Determine the user's shell (fallback to bash)
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-    println!("[PTY] Using shell: {}", shell);
-
-    // Configure the shell command with proper terminal settings
-    let mut cmd = CommandBuilder::new(&shell);
-    cmd.env("TERM", "xterm-256color");
-    cmd.env("COLORTERM", "truecolor");
-
-    // Spawn the shell process attached to the slave PTY
-    let _child = pair
+    // Spawn the process attached to the slave PTY
+    let child = pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+        .map_err(|e| format!("Failed to spawn {}: {}", label, e))?;
 
     // Generate unique identifier for this session
     let pty_id = Uuid::new_v4().to_string();
     println!("[PTY] Generated PTY ID: {}", pty_id);
 
-    // Clone the reader for the background thread
-    let mut reader = pair
-        .master
-        .try_clone_reader()
-        .map_err(|e| format!("Failed to clone reader: {}", e))?;
-
-    // Take ownership of the writer
-    let writer = pair
-        .master
-        .take_writer()
-        .map_err(|e| format!("Failed to take writer: {}", e))?;
-
-    // Store the session in global state
-    {
-        let mut sessions = sessions_ptr.lock();
-        sessions.insert(
-            pty_id.clone(),
-            PtySession {
-                master: pair.master,
-                writer: Arc::new(Mutex::new(writer)),
-            },
-        );
-        println!("[PTY] Session {} stored. Total sessions: {}", pty_id, sessions.len());
-        println!("[PTY] Session IDs: {:?}", sessions.keys().collect::<Vec<_>>());
+    // Duplicate the master fd for non-blocking async I/O
+    let master_file = clone_master_fd(pair.master.as_ref())?;
+    let (writer, input) = mpsc::unbounded_channel();
+    let running = Arc::new(Mutex::new(true));
+    let shutdown = Arc::new(Notify::new());
+
+    // Register the session with the global session manager
+    sessions().insert(
+        &pty_id,
+        Arc::new(PtySession {
+            id: pty_id.clone(),
+            command: label,
+            master: Mutex::new(pair.master),
+            writer,
+            running: running.clone(),
+            shutdown: shutdown.clone(),
+        }),
+    );
+
+    let output = spawn_output_pump(
+        sink.clone(),
+        &pty_id,
+        on_output,
+        "pty-output",
+        |pty_id, data| PtyOutput {
+            pty_id: pty_id.to_string(),
+            data,
+        },
+    );
+    tauri::async_runtime::spawn(run_pty(
+        sink,
+        pty_id.clone(),
+        PtyIo {
+            master: master_file,
+            child,
+        },
+        input,
+        output,
+        running,
+        shutdown,
+    ));
+
+    println!("[PTY] spawn_pty returning id: {}", pty_id);
+    Ok(pty_id)
+}
+
+/// Returns a non-blocking duplicate of the PTY master file descriptor
+fn clone_master_fd(master: &(dyn MasterPty + Send)) -> Result<File, String> {
+    let fd = master
+        .as_raw_fd()
+        .ok_or_else(|| "PTY master has no file descriptor".to_string())?;
+
+    // SAFETY: `fd` belongs to `master`, which outlives this borrow
+    let owned = unsafe { BorrowedFd::borrow_raw(fd) }
+        .try_clone_to_owned()
+        .map_err(|e| format!("Failed to duplicate PTY fd: {}", e))?;
+
+    // SAFETY: fcntl on a valid, owned descriptor
+    let nonblocking = unsafe {
+        let flags = libc::fcntl(owned.as_raw_fd(), libc::F_GETFL);
+        flags >= 0 && libc::fcntl(owned.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) >= 0
+    };
+    if !nonblocking {
+        return Err(format!(
+            "Failed to make PTY non-blocking: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(File::from(owned))
+}
+
+/// Opens a pidfd for `pid`, which becomes readable once the process exits
+#[cfg(target_os = "linux")]
+fn open_pidfd(pid: u32) -> Option<OwnedFd> {
+    use std::os::fd::{FromRawFd, RawFd};
+
+    // SAFETY: pidfd_open takes no pointers; a non-negative result is a new fd we own
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        return None;
     }
+    // SAFETY: see above
+    Some(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// pidfds are Linux only; the shell is polled for its exit instead
+#[cfg(not(target_os = "linux"))]
+fn open_pidfd(_pid: u32) -> Option<OwnedFd> {
+    None
+}
+
+/// Process groups in the session `sid`, starting with the session leader's
+#[cfg(target_os = "linux")]
+fn session_process_groups(sid: libc::pid_t) -> Vec<libc::pid_t> {
+    let mut groups = vec![sid];
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return groups;
+    };
+    for entry in entries.flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        if let Some((pgrp, session)) = parse_proc_stat(&stat) {
+            if session == sid && !groups.contains(&pgrp) {
+                groups.push(pgrp);
+            }
+        }
+    }
+    groups
+}
 
-    // Spawn background thread to read PTY output and emit events
-    let pty_id_clone = pty_id.clone();
-    let sessions_for_cleanup = sessions_ptr.clone();
+/// Without `/proc` only the session leader's own group is known
+#[cfg(not(target_os = "linux"))]
+fn session_process_groups(sid: libc::pid_t) -> Vec<libc::pid_t> {
+    vec![sid]
+}
 
-    thread::spawn(move || {
-        println!("[PTY] Reader thread started for {}", pty_id_clone);
-        let mut buf = [0u8; PTY_READ_BUFFER_SIZE];
+/// Extracts (process group, session) from a `/proc/<pid>/stat` line
+#[cfg(target_os = "linux")]
+fn parse_proc_stat(stat: &str) -> Option<(libc::pid_t, libc::pid_t)> {
+    // "pid (comm) state ppid pgrp session ..."; comm may contain spaces and ')'
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let mut fields = rest.split_whitespace().skip(2);
+    let pgrp = fields.next()?.parse().ok()?;
+    let session = fields.next()?.parse().ok()?;
+    Some((pgrp, session))
+}
+
+/// The shell process running in a PTY
+struct PtyChild {
+    child: Box<dyn Child + Send + Sync>,
+    /// pidfd for the shell; `None` on kernels without pidfd support
+    exit_fd: Option<AsyncFd<OwnedFd>>,
+    /// Exit status, once reaped
+    status: Option<ExitStatus>,
+}
+
+impl PtyChild {
+    fn new(child: Box<dyn Child + Send + Sync>) -> Self {
+        let exit_fd = child
+            .process_id()
+            .and_then(open_pidfd)
+            .and_then(|fd| AsyncFd::new(fd).ok());
+        PtyChild {
+            child,
+            exit_fd,
+            status: None,
+        }
+    }
+
+    /// Resolves once the shell has exited; never resolves without a pidfd
+    async fn exited(&self) {
+        match &self.exit_fd {
+            Some(fd) => {
+                let _ = fd.readable().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Waits up to `timeout` for the shell to exit
+    async fn wait_exit(&mut self, timeout: Duration) -> bool {
+        if self.exit_fd.is_some() {
+            return tokio::time::timeout(timeout, self.exited()).await.is_ok();
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
         loop {
-            match reader.read(&mut buf) {
-                Ok(0) => {
-                    println!("[PTY] EOF for {}", pty_id_clone);
+            if let Ok(Some(status)) = self.child.try_wait() {
+                self.status = Some(status);
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(REAP_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Process groups in the shell's terminal session; none once it is reaped
+    fn process_groups(&self) -> Vec<libc::pid_t> {
+        // portable-pty starts the shell with setsid(), so its PID is both the
+        // PGID and the session ID. Until the shell is reaped it cannot be reused.
+        match (self.child.process_id(), &self.status) {
+            (Some(pid), None) => session_process_groups(pid as libc::pid_t),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Asks the process groups to exit, escalating to SIGKILL after the grace period
+    async fn terminate(&mut self) {
+        // Listed while the shell is unreaped; a group's ID is not reused while
+        // anything in it still runs, so they stay valid after the shell is
+        // reaped (as the fallback's wait does)
+        let mut groups = self.process_groups();
+        signal_groups(&groups, libc::SIGHUP);
+        signal_groups(&groups, libc::SIGTERM);
+        if !self.wait_exit(KILL_GRACE_PERIOD).await {
+            println!("[PTY] Process group ignored SIGTERM, sending SIGKILL");
+        }
+        for pgrp in self.process_groups() {
+            if !groups.contains(&pgrp) {
+                groups.push(pgrp);
+            }
+        }
+        // Also takes down anything the shell left running in its groups
+        signal_groups(&groups, libc::SIGKILL);
+    }
+
+    /// Reaps the shell and returns its exit status
+    async fn reap(&mut self) -> Option<ExitStatus> {
+        while self.status.is_none() {
+            match self.child.try_wait() {
+                Ok(Some(status)) => self.status = Some(status),
+                Ok(None) => tokio::time::sleep(REAP_POLL_INTERVAL).await,
+                Err(e) => {
+                    eprintln!("[PTY] Failed to reap shell: {}", e);
                     break;
                 }
-                Ok(n) => {
-                    let data = String::from_utf8_lossy(&buf[..n]).to_string();
-                    
-                    // Write to any active log files for this session
-                    write_to_logs(&pty_id_clone, &data);
-                    
-                    let _ = app.emit(
-                        "pty-output",
-                        PtyOutput {
-                            pty_id: pty_id_clone.clone(),
-                            data,
-                        },
-                    );
+            }
+        }
+        self.status.clone()
+    }
+}
+
+/// Sends a signal to each process group
+fn signal_groups(groups: &[libc::pid_t], signal: libc::c_int) {
+    for &pgrp in groups {
+        // SAFETY: killpg has no memory arguments
+        unsafe {
+            libc::killpg(pgrp, signal);
+        }
+    }
+}
+
+/// The PTY master and shell owned by a session's I/O task
+struct PtyIo {
+    master: File,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+/// Why the I/O loop stopped
+enum PtyExit {
+    /// The shell exited
+    Exited,
+    /// The PTY reported EOF or an error
+    Hangup,
+    /// Closed locally via `kill_pty` / `session_close`
+    Closed,
+}
+
+/// Pumps output from and input to a PTY until the shell exits
+async fn run_pty(
+    sink: EventSink,
+    pty_id: String,
+    io: PtyIo,
+    mut input: UnboundedReceiver<Vec<u8>>,
+    output: OutputSender,
+    running: Arc<Mutex<bool>>,
+    shutdown: Arc<Notify>,
+) {
+    println!("[PTY] Reader task started for {}", pty_id);
+
+    let mut child = PtyChild::new(io.child);
+    let master = match AsyncFd::new(io.master) {
+        Ok(master) => master,
+        Err(e) => {
+            eprintln!("[PTY] Failed to register PTY for {}: {}", pty_id, e);
+            child.terminate().await;
+            child.reap().await;
+            *running.lock() = false;
+            sessions().unregister(&pty_id);
+            return;
+        }
+    };
+
+    let mut buf = [0u8; PTY_READ_BUFFER_SIZE];
+    let mut decoder = Utf8Decoder::new();
+    // Input the PTY has not accepted yet; output keeps flowing meanwhile
+    let mut pending: Vec<u8> = Vec::new();
+
+    let exit = loop {
+        tokio::select! {
+            ready = master.readable() => {
+                let Ok(mut guard) = ready else {
+                    break PtyExit::Hangup;
+                };
+                match guard.try_io(|fd| fd.get_ref().read(&mut buf)) {
+                    Ok(Ok(0)) => {
+                        println!("[PTY] EOF for {}", pty_id);
+                        break PtyExit::Hangup;
+                    }
+                    Ok(Ok(n)) => {
+                        // Multibyte characters may be split across reads
+                        let data = decoder.decode(&buf[..n]);
+                        if data.is_empty() {
+                            continue;
+                        }
+
+                        // Write to log files and scrollback for this session
+                        record_output(&pty_id, &data);
+                        output.send(data);
+                    }
+                    Ok(Err(e)) => {
+                        // Linux reports EIO once every process has closed the slave
+                        println!("[PTY] Hangup for {}: {}", pty_id, e);
+                        break PtyExit::Hangup;
+                    }
+                    Err(_would_block) => continue,
+                }
+            }
+            ready = master.writable(), if !pending.is_empty() => {
+                let Ok(mut guard) = ready else {
+                    break PtyExit::Hangup;
+                };
+                match guard.try_io(|fd| fd.get_ref().write(&pending)) {
+                    Ok(Ok(n)) => {
+                        pending.drain(..n);
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("[PTY] Write error for {}: {}", pty_id, e);
+                        break PtyExit::Hangup;
+                    }
+                    Err(_would_block) => continue,
                 }
-                Err(e) => {
-                    eprintln!("[PTY] Read error for {}: {}", pty_id_clone, e);
+            }
+            data = input.recv() => match data {
+                Some(data) => pending.extend_from_slice(&data),
+                // The session was dropped without being closed
+                None => break PtyExit::Closed,
+            },
+            _ = shutdown.notified() => break PtyExit::Closed,
+            _ = child.exited() => break PtyExit::Exited,
+        }
+    };
+
+    match exit {
+        PtyExit::Exited => {
+            // Pick up whatever the shell printed last
+            let mut reader = master.get_ref();
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
                     break;
                 }
+                let data = decoder.decode(&buf[..n]);
+                record_output(&pty_id, &data);
+                output.send(data);
             }
         }
-        
-        // Clean up session when reader exits
-        cleanup_session_logs(&pty_id_clone);
-        let mut sessions = sessions_for_cleanup.lock();
-        sessions.remove(&pty_id_clone);
-        println!("[PTY] Session {} cleaned up. Remaining: {}", pty_id_clone, sessions.len());
-    });
+        PtyExit::Hangup => {
+            // The shell normally exits right after its terminal goes away
+            if !child.wait_exit(KILL_GRACE_PERIOD).await {
+                child.terminate().await;
+            }
+        }
+        PtyExit::Closed => {
+            println!("[PTY] Terminating process group for {}", pty_id);
+            child.terminate().await;
+        }
+    }
 
-    println!("[PTY] spawn_pty returning id: {}", pty_id);
-    Ok(pty_id)
+    let status = child.reap().await;
+    println!(
+        "[PTY] Shell for {} exited: {}",
+        pty_id,
+        status
+            .as_ref()
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "unknown status".to_string())
+    );
+
+    record_final_output(&pty_id, &mut decoder, &output);
+    // Deliver the last output before reporting the exit
+    output.finish().await;
+    sink.emit(
+        "pty-status",
+        &pty_id,
+        PtyStatus {
+            pty_id: pty_id.clone(),
+            status: "exited".to_string(),
+            message: status
+                .as_ref()
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "Process exited".to_string()),
+            exit_code: status.as_ref().map(|status| status.exit_code()),
+        },
+    );
+
+    // Clean up session when the task exits
+    *running.lock() = false;
+    sessions().unregister(&pty_id);
 }
 
 /// Writes input data to a PTY session.
 #[tauri::command]
 pub fn write_to_pty(pty_id: String, data: String) -> Result<(), String> {
-    println!("[PTY] write_to_pty called: pty_id={}, data_len={}", pty_id, data.len());
-    
-    let sessions_ptr = PTY_SESSIONS.clone();
-    println!("[PTY] Got sessions reference: {:p}", Arc::as_ptr(&sessions_ptr));
-    
-    let sessions = sessions_ptr.lock();
-    println!("[PTY] Current sessions: {:?}", sessions.keys().collect::<Vec<_>>());
-
-    if let Some(session) = sessions.get(&pty_id) {
-        let mut writer = session.writer.lock();
-        writer
-            .write_all(data.as_bytes())
-            .map_err(|e| format!("Failed to write to PTY: {}", e))?;
-        writer
-            .flush()
-            .map_err(|e| format!("Failed to flush PTY: {}", e))?;
-        println!("[PTY] Write successful to {}", pty_id);
-        Ok(())
-    } else {
-        let err = format!("PTY session not found: {}", pty_id);
-        eprintln!("[PTY] {}", err);
-        Err(err)
-    }
+    sessions().write(&pty_id, data.as_bytes())
 }
 
 /// Resizes a PTY session to new dimensions.
 #[tauri::command]
 pub fn resize_pty(pty_id: String, cols: u16, rows: u16) -> Result<(), String> {
     println!("[PTY] resize_pty called: pty_id={}, cols={}, rows={}", pty_id, cols, rows);
-    
-    let sessions_ptr = PTY_SESSIONS.clone();
-    let sessions = sessions_ptr.lock();
-
-    if let Some(session) = sessions.get(&pty_id) {
-        session
-            .master
-            .resize(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| format!("Failed to resize PTY: {}", e))?;
-        println!("[PTY] Resize successful for {}", pty_id);
-        Ok(())
-    } else {
-        Err(format!("PTY session not found: {}", pty_id))
-    }
+    sessions().resize(&pty_id, cols, rows)
 }
 
 /// Terminates and cleans up a PTY session.
 #[tauri::command]
 pub fn kill_pty(pty_id: String) -> Result<(), String> {
     println!("[PTY] kill_pty called: pty_id={}", pty_id);
-    
-    let sessions_ptr = PTY_SESSIONS.clone();
-    let mut sessions = sessions_ptr.lock();
-
-    if sessions.remove(&pty_id).is_some() {
-        println!("[PTY] Session {} killed. Remaining: {}", pty_id, sessions.len());
-        Ok(())
-    } else {
-        Err(format!("PTY session not found: {}", pty_id))
-    }
+    sessions().close(&pty_id)
 }
 
 // Dummy struct to satisfy Tauri's manage() - we don't actually use it
@@ -233,3 +674,45 @@ impl Default for PtyState {
         PtyState
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_proc_stat() {
+        let stat = "4242 (sleep) S 4200 4242 4200 34817 4242 4194304 95 0 0 0";
+        assert_eq!(parse_proc_stat(stat), Some((4242, 4200)));
+
+        // The command name may itself contain spaces and parentheses
+        let stat = "77 (tmux: (server) x) S 1 77 77 0 -1 4194368";
+        assert_eq!(parse_proc_stat(stat), Some((77, 77)));
+
+        assert_eq!(parse_proc_stat("garbage"), None);
+    }
+
+    #[test]
+    fn test_pty_command_builder() {
+        let command = PtyCommand {
+            program: Some("virsh".to_string()),
+            args: vec!["console".to_string(), "r1".to_string()],
+            env: HashMap::from([(
+                "LIBVIRT_DEFAULT_URI".to_string(),
+                "qemu:///system".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let (cmd, label) = command.to_builder().unwrap();
+        assert_eq!(label, "virsh console r1");
+        assert_eq!(cmd.get_argv().len(), 3);
+        assert_eq!(cmd.get_env("LIBVIRT_DEFAULT_URI").unwrap(), "qemu:///system");
+        assert_eq!(cmd.get_env("TERM").unwrap(), "xterm-256color");
+
+        let missing = PtyCommand {
+            cwd: Some("/nonexistent/packet".to_string()),
+            ..Default::default()
+        };
+        assert!(missing.to_builder().is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Scrollback Buffer Module for Packet
+//!
+//! Keeps a bounded copy of each session's recent output in the backend so a
+//! pane can be rehydrated after the webview reloads or the pane is recreated,
+//! and so output can be searched after the fact.
+//!
+//! # Architecture
+//!
+//! Each session gets a ring buffer of output chunks capped at a configurable
+//! number of bytes. When the cap is exceeded the oldest chunks are dropped.
+//! Chunks are stored as decoded text, so reads always return valid UTF-8.
+//!
+//! A buffer is created when its session registers with the session manager
+//! and released when it unregisters. Output for any other ID (e.g. a late
+//! flush after the session closed) is dropped instead of starting a buffer
+//! nobody would free.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default memory cap per session (1 MiB)
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// Bounded buffer of recent output for one session
+pub struct ScrollbackBuffer {
+    chunks: VecDeque<String>,
+    /// Total bytes currently held across all chunks
+    len: usize,
+    /// Maximum number of bytes to retain
+    limit: usize,
+}
+
+impl ScrollbackBuffer {
+    pub fn new(limit: usize) -> Self {
+        ScrollbackBuffer {
+            chunks: VecDeque::new(),
+            len: 0,
+            limit,
+        }
+    }
+
+    /// Appends output, evicting the oldest data once over the limit
+    pub fn push(&mut self, data: &str) {
+        if data.is_empty() {
+            return;
+        }
+        self.chunks.push_back(data.to_string());
+        self.len += data.len();
+        self.trim();
+    }
+
+    /// Changes the memory cap, trimming immediately if needed
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    /// Returns up to `max_bytes` of the most recent output
+    pub fn tail(&self, max_bytes: usize) -> String {
+        let mut taken = 0;
+        let mut start = self.chunks.len();
+
+        // Walk back over whole chunks that fit
+        while start > 0 && taken + self.chunks[start - 1].len() <= max_bytes {
+            start -= 1;
+            taken += self.chunks[start].len();
+        }
+
+        let mut output = String::with_capacity(max_bytes.min(self.len));
+        if start > 0 && taken < max_bytes {
+            // Take the end of the next older chunk, on a char boundary
+            let chunk = &self.chunks[start - 1];
+            output.push_str(&chunk[ceil_char_boundary(chunk, chunk.len() - (max_bytes - taken))..]);
+        }
+        for chunk in self.chunks.range(start..) {
+            output.push_str(chunk);
+        }
+        output
+    }
+
+    /// Total bytes currently held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn trim(&mut self) {
+        while self.len > self.limit {
+            let Some(front) = self.chunks.front_mut() else {
+                break;
+            };
+            let excess = self.len - self.limit;
+            if front.len() <= excess {
+                self.len -= front.len();
+                self.chunks.pop_front();
+            } else {
+                // Drop only the start of the oldest chunk
+                let cut = ceil_char_boundary(front, excess);
+                front.drain(..cut);
+                self.len -= cut;
+            }
+        }
+    }
+}
+
+/// Smallest char boundary in `s` at or after `index`
+fn ceil_char_boundary(s: &str, index: usize) -> usize {
+    (index..=s.len())
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(s.len())
+}
+
+/// Global state for all scrollback buffers
+/// Maps session_id -> ScrollbackBuffer
+static SCROLLBACK: Lazy<Mutex<HashMap<String, ScrollbackBuffer>>> = Lazy::new(|| {
+    println!("[Scrollback] Initializing global scrollback store");
+    Mutex::new(HashMap::new())
+});
+
+/// Memory cap applied to newly created buffers
+static DEFAULT_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_SCROLLBACK_BYTES);
+
+/// Creates the empty buffer of a session being registered
+pub fn init_scrollback(session_id: &str) {
+    SCROLLBACK.lock().insert(
+        session_id.to_string(),
+        ScrollbackBuffer::new(DEFAULT_LIMIT.load(Ordering::Relaxed)),
+    );
+}
+
+/// Records output for a session.
+/// This is called internally when terminal output is received; output for
+/// sessions that are not registered is ignored.
+pub fn append_scrollback(session_id: &str, data: &str) {
+    if let Some(buffer) = SCROLLBACK.lock().get_mut(session_id) {
+        buffer.push(data);
+    }
+}
+
+/// Drops the scrollback buffer for a session (called when session is closed).
+pub fn cleanup_scrollback(session_id: &str) {
+    if let Some(buffer) = SCROLLBACK.lock().remove(session_id) {
+        println!(
+            "[Scrollback] Released {} bytes for session {}",
+            buffer.len(),
+            session_id
+        );
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Returns the most recent output of a session.
+///
+/// # Arguments
+/// * `session_id` - The terminal session ID
+/// * `max_bytes` - Upper bound on the returned size (defaults to the whole buffer)
+#[tauri::command]
+pub fn get_scrollback(session_id: String, max_bytes: Option<usize>) -> String {
+    SCROLLBACK
+        .lock()
+        .get(&session_id)
+        .map(|buffer| buffer.tail(max_bytes.unwrap_or(usize::MAX)))
+        .unwrap_or_default()
+}
+
+/// Sets the scrollback memory cap.
+///
+/// # Arguments
+/// * `session_id` - Session to update, or `None` to change the default for new sessions
+/// * `max_bytes` - Maximum bytes of output to retain
+#[tauri::command]
+pub fn set_scrollback_limit(session_id: Option<String>, max_bytes: usize) -> Result<(), String> {
+    match session_id {
+        Some(session_id) => {
+            SCROLLBACK
+                .lock()
+                .get_mut(&session_id)
+                .ok_or_else(|| format!("Session not found: {}", session_id))?
+                .set_limit(max_bytes);
+        }
+        None => DEFAULT_LIMIT.store(max_bytes, Ordering::Relaxed),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_oldest_output() {
+        let mut buffer = ScrollbackBuffer::new(8);
+        buffer.push("hello ");
+        buffer.push("world");
+        assert_eq!(buffer.len(), 8);
+        assert_eq!(buffer.tail(usize::MAX), "lo world");
+    }
+
+    #[test]
+    fn test_tail_respects_char_boundaries() {
+        let mut buffer = ScrollbackBuffer::new(64);
+        buffer.push("a─b");
+        // "─" is 3 bytes; cutting inside it must skip the whole char
+        assert_eq!(buffer.tail(3), "b");
+        assert_eq!(buffer.tail(4), "─b");
+    }
+
+    #[test]
+    fn test_only_registered_sessions_have_buffers() {
+        init_scrollback("scrollback-test");
+        append_scrollback("scrollback-test", "R1#");
+        assert_eq!(get_scrollback("scrollback-test".to_string(), None), "R1#");
+        assert!(set_scrollback_limit(Some("scrollback-test".to_string()), 2).is_ok());
+        assert_eq!(get_scrollback("scrollback-test".to_string(), None), "1#");
+
+        // Output flushed after the session closed must not bring the buffer back
+        cleanup_scrollback("scrollback-test");
+        append_scrollback("scrollback-test", "late output");
+        assert!(!SCROLLBACK.lock().contains_key("scrollback-test"));
+        assert!(set_scrollback_limit(Some("scrollback-test".to_string()), 64).is_err());
+    }
+}
@@ -0,0 +1,664 @@
+//! Serial Console Module for Packet
+//!
+//! This module connects to physical serial ports (e.g. `/dev/ttyUSB0` from a
+//! USB console cable), the console most network devices ship with.
+//!
+//! # Architecture
+//!
+//! Each serial session maintains:
+//! - The device, opened exclusively with the requested line settings
+//! - A reader task that emits output events to the frontend
+//! - A writer task fed by a queue of input for the device
+//! - A second handle on the device used to change settings, send a break
+//!   and read the modem lines while the tasks own the stream
+//!
+//! Line settings use the same types as RFC 2217 telnet sessions, so a
+//! console reached through ser2net and one plugged in locally are configured
+//! the same way. Unset fields default to 9600 8N1 without flow control.
+
+use crate::daemon::{remote_command, SessionCommand};
+use crate::output::{spawn_output_pump, OutputSender};
+use crate::session::{record_final_output, record_output, sessions, EventSink, Session, SessionInfo, SessionKind, SessionStatus};
+use crate::telnet::{ComPortSettings, ComPortStatus, FlowControl, ModemState, Parity, StopBits};
+use crate::utf8::Utf8Decoder;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+use tokio_serial::{SerialPort, SerialPortBuilder, SerialPortType, SerialStream};
+use uuid::Uuid;
+
+/// Buffer size for reading serial output
+const SERIAL_READ_BUFFER_SIZE: usize = 4096;
+
+/// Baud rate used when none is given
+const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// Serial break length when none is given
+const DEFAULT_BREAK_MS: u64 = 500;
+
+/// Represents an active serial session
+pub struct SerialSession {
+    /// Session identifier
+    pub id: String,
+    /// Queue of outgoing bytes, drained by the session's writer task
+    pub writer: UnboundedSender<Vec<u8>>,
+    /// Device path for display
+    pub path: String,
+    /// Whether the device is still open
+    pub running: Arc<Mutex<bool>>,
+    /// Wakes the reader task when the session is closed locally
+    pub shutdown: Arc<Notify>,
+    /// Handle on the device for settings, break and modem lines
+    pub control: Mutex<Box<dyn SerialPort>>,
+}
+
+impl Session for SerialSession {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.writer
+            .send(data.to_vec())
+            .map_err(|_| "Failed to write: device closed".to_string())
+    }
+
+    fn resize(&self, _cols: u16, _rows: u16) -> Result<(), String> {
+        // A serial line has no notion of window size
+        Ok(())
+    }
+
+    fn close(&self) -> Result<(), String> {
+        // Signal the reader task to stop; the device closes once both tasks exit
+        *self.running.lock() = false;
+        self.shutdown.notify_one();
+        Ok(())
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            kind: SessionKind::Serial,
+            label: self.path.clone(),
+            status: self.status(),
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        if *self.running.lock() {
+            SessionStatus::Connected
+        } else {
+            SessionStatus::Disconnected
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Event payload emitted when serial output is available
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerialOutput {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub data: String,
+}
+
+/// Event payload for serial session status updates
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerialConnectionStatus {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub status: String,
+    pub message: String,
+}
+
+/// A serial device found on this machine
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerialDevice {
+    pub path: String,
+    /// `usb`, `pci`, `bluetooth` or `unknown`
+    pub kind: String,
+    /// Manufacturer and product, when the device reports them
+    pub description: Option<String>,
+}
+
+/// Opens a serial port
+///
+/// Output is delivered on `on_output` when given, otherwise as `serial-output` events.
+#[tauri::command]
+pub fn connect_serial(
+    app: AppHandle,
+    path: String,
+    settings: Option<ComPortSettings>,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    open_serial(
+        EventSink::App(app),
+        path,
+        settings.unwrap_or_default(),
+        on_output,
+    )
+}
+
+/// Opens a serial port whose events are delivered to `sink`.
+pub fn open_serial(
+    sink: EventSink,
+    path: String,
+    settings: ComPortSettings,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    println!("[Serial] Opening {} with {:?}", path, settings);
+
+    let port = port_builder(&path, &settings)?
+        .open_native()
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let control = port
+        .try_clone()
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    // Registering the device with the reactor needs the runtime's context
+    let stream = tauri::async_runtime::block_on(async { SerialStream::try_from(port) })
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+
+    // Generate session ID
+    let session_id = Uuid::new_v4().to_string();
+    println!("[Serial] Generated session ID: {}", session_id);
+
+    let running = Arc::new(Mutex::new(true));
+    let shutdown = Arc::new(Notify::new());
+    let (writer, outgoing) = mpsc::unbounded_channel();
+
+    // Register session with the global session manager
+    sessions().insert(
+        &session_id,
+        Arc::new(SerialSession {
+            id: session_id.clone(),
+            writer,
+            path: path.clone(),
+            running: running.clone(),
+            shutdown: shutdown.clone(),
+            control: Mutex::new(control),
+        }),
+    );
+
+    sink.emit(
+        "serial-status",
+        &session_id,
+        SerialConnectionStatus {
+            session_id: session_id.clone(),
+            status: "connected".to_string(),
+            message: format!("Opened {}", path),
+        },
+    );
+
+    let output = spawn_output_pump(
+        sink.clone(),
+        &session_id,
+        on_output,
+        "serial-output",
+        |session_id, data| SerialOutput {
+            session_id: session_id.to_string(),
+            data,
+        },
+    );
+    tauri::async_runtime::spawn(run_serial(
+        sink,
+        session_id.clone(),
+        stream,
+        outgoing,
+        output,
+        running,
+        shutdown,
+    ));
+
+    println!("[Serial] connect_serial returning id: {}", session_id);
+    Ok(session_id)
+}
+
+/// Drives a serial port until it is closed or goes away
+async fn run_serial(
+    sink: EventSink,
+    session_id: String,
+    stream: SerialStream,
+    mut outgoing: UnboundedReceiver<Vec<u8>>,
+    output: OutputSender,
+    running: Arc<Mutex<bool>>,
+    shutdown: Arc<Notify>,
+) {
+    println!("[Serial] Reader task started for {}", session_id);
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    // Writer task: forwards queued input until the session is dropped
+    let writer_session_id = session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(data) = outgoing.recv().await {
+            if let Err(e) = writer.write_all(&data).await {
+                eprintln!("[Serial] Write error for {}: {}", writer_session_id, e);
+                break;
+            }
+        }
+    });
+
+    let mut buf = [0u8; SERIAL_READ_BUFFER_SIZE];
+    let mut decoder = Utf8Decoder::new();
+
+    loop {
+        let read = tokio::select! {
+            read = reader.read(&mut buf) => read,
+            _ = shutdown.notified() => {
+                println!("[Serial] Reader task stopping for {}", session_id);
+                break;
+            }
+        };
+
+        match read {
+            Ok(0) => {
+                println!("[Serial] Device closed for {}", session_id);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "serial-status",
+                    &session_id,
+                    SerialConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "disconnected".to_string(),
+                        message: "Device closed".to_string(),
+                    },
+                );
+                break;
+            }
+            Ok(n) => {
+                let data = decoder.decode(&buf[..n]);
+                if !data.is_empty() {
+                    // Write to log files and scrollback for this session
+                    record_output(&session_id, &data);
+                    output.send(data);
+                }
+            }
+            Err(e) => {
+                // Unplugging a USB adapter ends up here
+                eprintln!("[Serial] Read error for {}: {}", session_id, e);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "serial-status",
+                    &session_id,
+                    SerialConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "error".to_string(),
+                        message: format!("Read error: {}", e),
+                    },
+                );
+                break;
+            }
+        }
+    }
+
+    // Clean up session; dropping it closes the writer queue and the device
+    *running.lock() = false;
+    sessions().unregister(&session_id);
+}
+
+/// Runs `f` on a serial session
+fn with_serial_session<T>(
+    session_id: &str,
+    f: impl FnOnce(&SerialSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let session = sessions().get(session_id)?;
+    let serial = session
+        .as_any()
+        .downcast_ref::<SerialSession>()
+        .ok_or_else(|| format!("Session {} is not a serial session", session_id))?;
+    f(serial)
+}
+
+/// Changes the line settings of an open serial session
+#[tauri::command]
+pub fn serial_configure(session_id: String, settings: ComPortSettings) -> Result<(), String> {
+    println!(
+        "[Serial] serial_configure called: session_id={}, settings={:?}",
+        session_id, settings
+    );
+    if let Some(result) = remote_command(&session_id, || SessionCommand::SerialConfigure {
+        session_id: session_id.clone(),
+        settings: settings.clone(),
+    }) {
+        return result;
+    }
+    // Checked as a whole first, so a rejected field leaves the line unchanged
+    let changes = LineChanges::new(&settings)?;
+    with_serial_session(&session_id, |serial| {
+        changes.apply(serial.control.lock().as_mut())
+    })
+}
+
+/// Sends a serial break of `duration_ms` (default 500ms)
+#[tauri::command]
+pub fn serial_break(session_id: String, duration_ms: Option<u64>) -> Result<(), String> {
+    println!(
+        "[Serial] serial_break called: session_id={}, duration_ms={:?}",
+        session_id, duration_ms
+    );
+    if let Some(result) = remote_command(&session_id, || SessionCommand::SerialBreak {
+        session_id: session_id.clone(),
+        duration_ms,
+    }) {
+        return result;
+    }
+    with_serial_session(&session_id, |serial| {
+        serial
+            .control
+            .lock()
+            .set_break()
+            .map_err(|e| format!("Failed to send break: {}", e))
+    })?;
+
+    // Release the break after the duration, unless the session is gone by then
+    let duration = Duration::from_millis(duration_ms.unwrap_or(DEFAULT_BREAK_MS));
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(duration).await;
+        let _ = with_serial_session(&session_id, |serial| {
+            serial
+                .control
+                .lock()
+                .clear_break()
+                .map_err(|e| e.to_string())
+        });
+    });
+    Ok(())
+}
+
+/// Returns the current line settings and modem lines of a serial session
+#[tauri::command]
+pub fn serial_status(session_id: String) -> Result<ComPortStatus, String> {
+    if let Some(result) = remote_command(&session_id, || SessionCommand::SerialStatus {
+        session_id: session_id.clone(),
+    }) {
+        return result;
+    }
+    with_serial_session(&session_id, |serial| {
+        let mut port = serial.control.lock();
+        let settings = ComPortSettings {
+            baud_rate: port.baud_rate().ok(),
+            data_bits: port.data_bits().ok().map(u8::from),
+            parity: port.parity().ok().map(|parity| match parity {
+                tokio_serial::Parity::None => Parity::None,
+                tokio_serial::Parity::Odd => Parity::Odd,
+                tokio_serial::Parity::Even => Parity::Even,
+            }),
+            stop_bits: port.stop_bits().ok().map(|stop_bits| match stop_bits {
+                tokio_serial::StopBits::One => StopBits::One,
+                tokio_serial::StopBits::Two => StopBits::Two,
+            }),
+            flow_control: port.flow_control().ok().map(|flow| match flow {
+                tokio_serial::FlowControl::None => FlowControl::None,
+                tokio_serial::FlowControl::Software => FlowControl::Software,
+                tokio_serial::FlowControl::Hardware => FlowControl::Hardware,
+            }),
+        };
+        // Pseudo-terminals and some adapters have no modem lines
+        let modem = (|| {
+            Some(ModemState {
+                cd: port.read_carrier_detect().ok()?,
+                ri: port.read_ring_indicator().ok()?,
+                dsr: port.read_data_set_ready().ok()?,
+                cts: port.read_clear_to_send().ok()?,
+            })
+        })();
+        Ok(ComPortStatus {
+            enabled: true,
+            settings,
+            modem,
+            line_state: None,
+        })
+    })
+}
+
+/// Lists the serial devices available on this machine
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<SerialDevice>, String> {
+    let ports = tokio_serial::available_ports()
+        .map_err(|e| format!("Failed to list serial ports: {}", e))?;
+    Ok(ports
+        .into_iter()
+        .map(|port| {
+            let (kind, description) = match port.port_type {
+                SerialPortType::UsbPort(usb) => {
+                    let description = [usb.manufacturer, usb.product]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    ("usb", Some(description).filter(|d| !d.is_empty()))
+                }
+                SerialPortType::PciPort => ("pci", None),
+                SerialPortType::BluetoothPort => ("bluetooth", None),
+                SerialPortType::Unknown => ("unknown", None),
+            };
+            SerialDevice {
+                path: port.port_name,
+                kind: kind.to_string(),
+                description,
+            }
+        })
+        .collect())
+}
+
+// ============================================================================
+// Line Settings
+// ============================================================================
+
+/// Builds the open request for a device, filling unset fields with 9600 8N1
+fn port_builder(path: &str, settings: &ComPortSettings) -> Result<SerialPortBuilder, String> {
+    let baud_rate = check_baud_rate(settings.baud_rate.unwrap_or(DEFAULT_BAUD_RATE))?;
+    Ok(tokio_serial::new(path, baud_rate)
+        .data_bits(data_bits(settings.data_bits.unwrap_or(8))?)
+        .parity(port_parity(settings.parity.unwrap_or(Parity::None))?)
+        .stop_bits(port_stop_bits(settings.stop_bits.unwrap_or(StopBits::One))?)
+        .flow_control(port_flow_control(
+            settings.flow_control.unwrap_or(FlowControl::None),
+        )))
+}
+
+/// The fields of a settings change, each already checked
+struct LineChanges {
+    baud_rate: Option<u32>,
+    data_bits: Option<tokio_serial::DataBits>,
+    parity: Option<tokio_serial::Parity>,
+    stop_bits: Option<tokio_serial::StopBits>,
+    flow_control: Option<tokio_serial::FlowControl>,
+}
+
+impl LineChanges {
+    /// Checks every set field; unset ones are left as they are
+    fn new(settings: &ComPortSettings) -> Result<Self, String> {
+        Ok(LineChanges {
+            baud_rate: settings.baud_rate.map(check_baud_rate).transpose()?,
+            data_bits: settings.data_bits.map(data_bits).transpose()?,
+            parity: settings.parity.map(port_parity).transpose()?,
+            stop_bits: settings.stop_bits.map(port_stop_bits).transpose()?,
+            flow_control: settings.flow_control.map(port_flow_control),
+        })
+    }
+
+    fn apply(&self, port: &mut dyn SerialPort) -> Result<(), String> {
+        if let Some(baud_rate) = self.baud_rate {
+            port.set_baud_rate(baud_rate)
+                .map_err(|e| format!("Failed to set baud rate: {}", e))?;
+        }
+        if let Some(bits) = self.data_bits {
+            port.set_data_bits(bits)
+                .map_err(|e| format!("Failed to set data bits: {}", e))?;
+        }
+        if let Some(parity) = self.parity {
+            port.set_parity(parity)
+                .map_err(|e| format!("Failed to set parity: {}", e))?;
+        }
+        if let Some(stop_bits) = self.stop_bits {
+            port.set_stop_bits(stop_bits)
+                .map_err(|e| format!("Failed to set stop bits: {}", e))?;
+        }
+        if let Some(flow_control) = self.flow_control {
+            port.set_flow_control(flow_control)
+                .map_err(|e| format!("Failed to set flow control: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn check_baud_rate(baud_rate: u32) -> Result<u32, String> {
+    if baud_rate == 0 {
+        return Err("Baud rate must be greater than 0".to_string());
+    }
+    Ok(baud_rate)
+}
+
+fn data_bits(bits: u8) -> Result<tokio_serial::DataBits, String> {
+    match bits {
+        5 => Ok(tokio_serial::DataBits::Five),
+        6 => Ok(tokio_serial::DataBits::Six),
+        7 => Ok(tokio_serial::DataBits::Seven),
+        8 => Ok(tokio_serial::DataBits::Eight),
+        _ => Err(format!("Invalid data bits: {} (expected 5-8)", bits)),
+    }
+}
+
+fn port_parity(parity: Parity) -> Result<tokio_serial::Parity, String> {
+    match parity {
+        Parity::None => Ok(tokio_serial::Parity::None),
+        Parity::Odd => Ok(tokio_serial::Parity::Odd),
+        Parity::Even => Ok(tokio_serial::Parity::Even),
+        Parity::Mark | Parity::Space => {
+            Err("Mark and space parity are not supported on local ports".to_string())
+        }
+    }
+}
+
+fn port_stop_bits(stop_bits: StopBits) -> Result<tokio_serial::StopBits, String> {
+    match stop_bits {
+        StopBits::One => Ok(tokio_serial::StopBits::One),
+        StopBits::Two => Ok(tokio_serial::StopBits::Two),
+        StopBits::OnePointFive => Err("1.5 stop bits are not supported on local ports".to_string()),
+    }
+}
+
+fn port_flow_control(flow_control: FlowControl) -> tokio_serial::FlowControl {
+    match flow_control {
+        FlowControl::None => tokio_serial::FlowControl::None,
+        FlowControl::Software => tokio_serial::FlowControl::Software,
+        FlowControl::Hardware => tokio_serial::FlowControl::Hardware,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_settings() {
+        assert_eq!(data_bits(7).unwrap(), tokio_serial::DataBits::Seven);
+        assert!(data_bits(9).is_err());
+        assert!(check_baud_rate(0).is_err());
+        assert_eq!(
+            port_parity(Parity::Even).unwrap(),
+            tokio_serial::Parity::Even
+        );
+        assert!(port_parity(Parity::Mark).is_err());
+        assert!(port_stop_bits(StopBits::OnePointFive).is_err());
+        assert_eq!(
+            port_flow_control(FlowControl::Hardware),
+            tokio_serial::FlowControl::Hardware
+        );
+
+        // Unset fields fall back to 9600 8N1; invalid ones are rejected up front
+        assert!(port_builder("/dev/ttyUSB0", &ComPortSettings::default()).is_ok());
+        let settings = ComPortSettings {
+            stop_bits: Some(StopBits::OnePointFive),
+            ..Default::default()
+        };
+        assert!(port_builder("/dev/ttyUSB0", &settings).is_err());
+    }
+
+    /// Opens a pseudo-terminal pair; the far end stands in for the device
+    fn open_pty_pair() -> (std::fs::File, String) {
+        use std::os::fd::FromRawFd;
+
+        let mut master = 0;
+        let mut slave = 0;
+        // SAFETY: openpty writes the two descriptors; the name, termios and
+        // window size pointers may be null
+        let result = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(result, 0, "openpty failed");
+        // SAFETY: `slave` is a valid descriptor from openpty
+        let path = unsafe { std::ffi::CStr::from_ptr(libc::ttyname(slave)) }
+            .to_string_lossy()
+            .into_owned();
+        // SAFETY: both descriptors are ours; the port opens the path again
+        unsafe {
+            libc::close(slave);
+            (std::fs::File::from_raw_fd(master), path)
+        }
+    }
+
+    #[test]
+    fn test_serial_session_on_pty() {
+        use std::io::{Read, Write};
+        use std::time::Instant;
+
+        let (mut device, path) = open_pty_pair();
+        let settings = ComPortSettings {
+            baud_rate: Some(115200),
+            ..Default::default()
+        };
+        let session_id = open_serial(EventSink::Daemon, path, settings, None).unwrap();
+
+        // Input reaches the device untouched
+        sessions().write(&session_id, b"show version\r").unwrap();
+        let mut received = [0u8; 13];
+        device.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"show version\r");
+
+        // Device output reaches the session
+        device.write_all(b"R1#").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !crate::scrollback::get_scrollback(session_id.clone(), None).contains("R1#") {
+            assert!(Instant::now() < deadline, "no output from device");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let baud_rate = || {
+            with_serial_session(&session_id, |serial| {
+                serial.control.lock().baud_rate().map_err(|e| e.to_string())
+            })
+            .unwrap()
+        };
+        let rejected = ComPortSettings {
+            baud_rate: Some(9600),
+            parity: Some(Parity::Mark),
+            ..Default::default()
+        };
+        assert!(serial_configure(session_id.clone(), rejected).is_err());
+        assert_eq!(baud_rate(), 115200);
+
+        let accepted = ComPortSettings {
+            baud_rate: Some(9600),
+            ..Default::default()
+        };
+        serial_configure(session_id.clone(), accepted).unwrap();
+        assert_eq!(baud_rate(), 9600);
+
+        sessions().close(&session_id).unwrap();
+    }
+}
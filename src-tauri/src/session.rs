@@ -0,0 +1,251 @@
+//! Unified Session Management Module
+//!
+//! Every terminal transport (local PTY, telnet, SSH, serial, socket) implements the
+//! [`Session`] trait and registers itself with the global [`SessionManager`].
+//! The frontend drives any session through the generic `session_write`,
+//! `session_resize`, `session_close` and `list_sessions` commands without
+//! having to know which connection type is behind a session ID.
+//!
+//! # Architecture
+//!
+//! The manager only stores `Arc<dyn Session>` handles. Lookups clone the
+//! handle and release the map lock before doing any I/O, so a slow device
+//! cannot block writes to every other session. Transport-specific commands
+//! (e.g. listing telnet host/port pairs) downcast through [`Session::as_any`].
+//!
+//! Reader tasks hand decoded output to [`record_output`], which feeds the
+//! cross-cutting consumers (log files, scrollback) in one place. Events are
+//! published through an [`EventSink`], so the same transports can run inside
+//! the GUI or inside the headless session daemon.
+
+use crate::daemon::forward_event;
+use crate::logging::{cleanup_session_logs, write_to_logs};
+use crate::output::{cleanup_output_channel, init_output_channel, OutputSender};
+use crate::scrollback::{append_scrollback, cleanup_scrollback, init_scrollback};
+use crate::utf8::Utf8Decoder;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+
+/// Kind of transport backing a session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKind {
+    Pty,
+    Telnet,
+    Ssh,
+    Serial,
+    /// Raw console on a Unix domain socket
+    Socket,
+    /// Raw TCP connection with no telnet processing
+    Raw,
+}
+
+/// Lifecycle status of a session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Connected,
+    Disconnected,
+    Error,
+}
+
+/// Information about an active session (for frontend display)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub kind: SessionKind,
+    /// Human readable target, e.g. `/bin/bash`, `10.0.0.1:5000` or `admin@core1:22`
+    pub label: String,
+    pub status: SessionStatus,
+}
+
+/// Destination for the events a session publishes (output, status changes)
+#[derive(Clone)]
+pub enum EventSink {
+    /// Emit Tauri events to the webview
+    App(AppHandle),
+    /// Forward to GUI clients attached to the session daemon
+    Daemon,
+}
+
+impl EventSink {
+    /// Publishes an event belonging to `session_id`
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, session_id: &str, payload: S) {
+        match self {
+            EventSink::App(app) => {
+                let _ = app.emit(event, payload);
+            }
+            EventSink::Daemon => forward_event(event, session_id, payload),
+        }
+    }
+}
+
+/// Common interface implemented by every terminal transport.
+pub trait Session: Send + Sync {
+    /// Sends raw input bytes to the session
+    fn write(&self, data: &[u8]) -> Result<(), String>;
+
+    /// Propagates a terminal size change to the session
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String>;
+
+    /// Closes the underlying connection or process
+    fn close(&self) -> Result<(), String>;
+
+    /// Returns display information about the session
+    fn info(&self) -> SessionInfo;
+
+    /// Returns the current lifecycle status
+    fn status(&self) -> SessionStatus;
+
+    /// Allows transport-specific commands to downcast to the concrete type
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Registry of all live sessions, keyed by session ID.
+pub struct SessionManager {
+    sessions: Mutex<HashMap<String, Arc<dyn Session>>>,
+}
+
+/// Global session manager shared by all Tauri commands and reader tasks
+static SESSION_MANAGER: Lazy<SessionManager> = Lazy::new(|| {
+    println!("[Session] Initializing global session manager");
+    SessionManager {
+        sessions: Mutex::new(HashMap::new()),
+    }
+});
+
+/// Returns the global session manager
+pub fn sessions() -> &'static SessionManager {
+    &SESSION_MANAGER
+}
+
+impl SessionManager {
+    /// Registers a new session under the given ID and creates its output
+    /// channel slot and scrollback buffer
+    pub fn insert(&self, session_id: &str, session: Arc<dyn Session>) {
+        init_output_channel(session_id);
+        init_scrollback(session_id);
+        let mut sessions = self.sessions.lock();
+        sessions.insert(session_id.to_string(), session);
+        println!(
+            "[Session] Session {} registered. Total sessions: {}",
+            session_id,
+            sessions.len()
+        );
+    }
+
+    /// Looks up a session handle by ID
+    pub fn get(&self, session_id: &str) -> Result<Arc<dyn Session>, String> {
+        self.sessions
+            .lock()
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| format!("Session not found: {}", session_id))
+    }
+
+    /// Returns handles to every registered session
+    pub fn all(&self) -> Vec<Arc<dyn Session>> {
+        self.sessions.lock().values().cloned().collect()
+    }
+
+    /// Removes a session from the registry and releases its log files, scrollback
+    /// and output channel.
+    ///
+    /// Called by reader tasks when a connection ends on its own; it does
+    /// not call [`Session::close`]. The session leaves the registry first,
+    /// so nothing can re-create what is released after it.
+    pub fn unregister(&self, session_id: &str) -> Option<Arc<dyn Session>> {
+        let removed = {
+            let mut sessions = self.sessions.lock();
+            let removed = sessions.remove(session_id);
+            if removed.is_some() {
+                println!(
+                    "[Session] Session {} cleaned up. Remaining: {}",
+                    session_id,
+                    sessions.len()
+                );
+            }
+            removed
+        };
+        cleanup_session_logs(session_id);
+        cleanup_scrollback(session_id);
+        cleanup_output_channel(session_id);
+        removed
+    }
+
+    /// Writes input to a session
+    pub fn write(&self, session_id: &str, data: &[u8]) -> Result<(), String> {
+        self.get(session_id)?.write(data)
+    }
+
+    /// Resizes a session's terminal
+    pub fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), String> {
+        self.get(session_id)?.resize(cols, rows)
+    }
+
+    /// Closes a session and removes it from the registry
+    pub fn close(&self, session_id: &str) -> Result<(), String> {
+        let session = self
+            .unregister(session_id)
+            .ok_or_else(|| format!("Session not found: {}", session_id))?;
+        session.close()
+    }
+
+    /// Lists display information for every session
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.all().iter().map(|session| session.info()).collect()
+    }
+}
+
+/// Records decoded output from a session's reader.
+///
+/// Writes to any active log files and appends to the session's scrollback.
+pub fn record_output(session_id: &str, data: &str) {
+    write_to_logs(session_id, data);
+    append_scrollback(session_id, data);
+}
+
+/// Records and queues what is left in a session's decoder once its stream
+/// has ended; a character cut off by the end becomes U+FFFD.
+pub fn record_final_output(session_id: &str, decoder: &mut Utf8Decoder, output: &OutputSender) {
+    let tail = decoder.finish();
+    if !tail.is_empty() {
+        record_output(session_id, &tail);
+        output.send(tail);
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Writes input data to any session
+#[tauri::command]
+pub fn session_write(session_id: String, data: String) -> Result<(), String> {
+    sessions().write(&session_id, data.as_bytes())
+}
+
+/// Resizes any session's terminal
+#[tauri::command]
+pub fn session_resize(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    sessions().resize(&session_id, cols, rows)
+}
+
+/// Closes any session
+#[tauri::command]
+pub fn session_close(session_id: String) -> Result<(), String> {
+    println!("[Session] session_close called: session_id={}", session_id);
+    sessions().close(&session_id)
+}
+
+/// Lists all active sessions regardless of connection type
+#[tauri::command]
+pub fn list_sessions() -> Vec<SessionInfo> {
+    sessions().list()
+}
@@ -0,0 +1,591 @@
+//! Socket Console Module for Packet
+//!
+//! GNS3, QEMU and libvirt often expose VM serial consoles as Unix domain
+//! sockets instead of TCP ports (e.g. `-serial unix:/tmp/r1.sock,server`).
+//! Some devices and simulators expose consoles as raw TCP with no telnet
+//! negotiation at all. This module connects to both directly, without a
+//! socat bridge.
+//!
+//! # Architecture
+//!
+//! Each socket session maintains:
+//! - A connection to the socket path or TCP address
+//! - A reader task that emits output events to the frontend
+//! - A writer task fed by a queue of input for the console
+//!
+//! Input is sent untouched apart from optional line ending translation (see
+//! [`RawOptions`]); 0xFF in particular goes out as one byte rather than a
+//! doubled telnet IAC. Output is decoded as UTF-8 for the terminal, so bytes
+//! that are not valid UTF-8, such as a lone 0xFF, are shown as U+FFFD.
+//! Consoles that speak telnet on a Unix socket (QEMU's `telnet` flag) are
+//! handed to the telnet module instead, so they get option negotiation, NAWS
+//! and telnet commands like any other telnet session.
+
+use crate::output::{spawn_output_pump, OutputSender};
+use crate::pty::expand_home;
+use crate::session::{record_final_output, record_output, sessions, EventSink, Session, SessionInfo, SessionKind, SessionStatus};
+use crate::telnet::{open_telnet_unix, TelnetOptions};
+use crate::utf8::Utf8Decoder;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Buffer size for reading socket output
+const SOCKET_READ_BUFFER_SIZE: usize = 4096;
+
+/// Connection timeout for raw TCP sessions
+const CONNECTION_TIMEOUT_SECS: u64 = 10;
+
+/// Line ending sent when the user presses Enter (a bare CR) on a raw connection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawNewline {
+    /// CR, as typed; what most device consoles expect
+    #[default]
+    Cr,
+    /// LF, for line-oriented services
+    Lf,
+    /// CR LF, for text protocols such as SMTP or HTTP
+    CrLf,
+}
+
+/// Per-session settings for raw connections
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RawOptions {
+    pub newline: RawNewline,
+    /// Show a bare LF from the remote end as CR LF, so services that end
+    /// lines with LF alone don't staircase in the terminal
+    #[serde(rename = "lfToCrlf")]
+    pub lf_to_crlf: bool,
+}
+
+/// Applies a session's line ending translation in both directions
+#[derive(Debug, Default)]
+pub struct LineTranslator {
+    options: RawOptions,
+    /// Whether the last byte received was a CR, so a CR LF split across two
+    /// reads is not given a second CR
+    after_cr: bool,
+    /// Whether the last input ended with a CR that was sent as a line ending,
+    /// so the LF of a CR LF split across two writes is not sent again
+    sent_cr: bool,
+}
+
+impl LineTranslator {
+    pub fn new(options: RawOptions) -> Self {
+        Self {
+            options,
+            after_cr: false,
+            sent_cr: false,
+        }
+    }
+
+    /// Translates user input for the wire. A CR already followed by LF is
+    /// left alone, so pasted CR LF text is not doubled, also when a chunked
+    /// paste splits the pair.
+    pub fn encode(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.options.newline == RawNewline::Cr || data.is_empty() {
+            return data.to_vec();
+        }
+
+        let data = match data.strip_prefix(b"\n") {
+            Some(rest) if self.sent_cr => rest,
+            _ => data,
+        };
+        self.sent_cr = data.last() == Some(&b'\r');
+
+        let mut encoded = Vec::with_capacity(data.len() + data.len() / 8);
+        for (i, &byte) in data.iter().enumerate() {
+            if byte == b'\r' && data.get(i + 1) != Some(&b'\n') {
+                match self.options.newline {
+                    RawNewline::Lf => encoded.push(b'\n'),
+                    _ => encoded.extend_from_slice(b"\r\n"),
+                }
+            } else {
+                encoded.push(byte);
+            }
+        }
+        encoded
+    }
+
+    /// Translates bytes from the remote end for display
+    pub fn decode(&mut self, data: &[u8]) -> Vec<u8> {
+        if !self.options.lf_to_crlf {
+            return data.to_vec();
+        }
+
+        let mut decoded = Vec::with_capacity(data.len() + data.len() / 8);
+        for &byte in data {
+            if byte == b'\n' && !self.after_cr {
+                decoded.push(b'\r');
+            }
+            decoded.push(byte);
+            self.after_cr = byte == b'\r';
+        }
+        decoded
+    }
+}
+
+/// Connection underneath a socket session
+pub enum SocketStream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+type SocketReader = Box<dyn AsyncRead + Send + Unpin>;
+type SocketWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+impl SocketStream {
+    /// Registers the stream with the runtime and splits it into halves
+    fn split(self) -> std::io::Result<(SocketReader, SocketWriter)> {
+        match self {
+            SocketStream::Unix(stream) => {
+                let (reader, writer) = tokio::net::UnixStream::from_std(stream)?.into_split();
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            SocketStream::Tcp(stream) => {
+                let (reader, writer) = tokio::net::TcpStream::from_std(stream)?.into_split();
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+        }
+    }
+}
+
+/// Represents an active socket session
+pub struct SocketSession {
+    /// Session identifier
+    pub id: String,
+    /// Queue of outgoing bytes, drained by the session's writer task
+    pub writer: UnboundedSender<Vec<u8>>,
+    /// Socket path, or host:port for raw TCP, for display
+    pub path: String,
+    /// Unix socket console or raw TCP connection
+    pub kind: SessionKind,
+    /// Line ending translation for input
+    pub translator: Mutex<LineTranslator>,
+    /// Whether the connection is still open
+    pub running: Arc<Mutex<bool>>,
+    /// Wakes the reader task when the session is closed locally
+    pub shutdown: Arc<Notify>,
+}
+
+impl Session for SocketSession {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        // Held while queueing, so input is translated in the order it is sent
+        let mut translator = self.translator.lock();
+        self.writer
+            .send(translator.encode(data))
+            .map_err(|_| "Failed to write: connection closed".to_string())
+    }
+
+    fn resize(&self, _cols: u16, _rows: u16) -> Result<(), String> {
+        // A raw console has no way to learn the window size
+        Ok(())
+    }
+
+    fn close(&self) -> Result<(), String> {
+        // Signal the reader task to stop; the socket closes once both tasks exit
+        *self.running.lock() = false;
+        self.shutdown.notify_one();
+        Ok(())
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            kind: self.kind,
+            label: self.path.clone(),
+            status: self.status(),
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        if *self.running.lock() {
+            SessionStatus::Connected
+        } else {
+            SessionStatus::Disconnected
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Event payload emitted when socket output is available
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SocketOutput {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub data: String,
+}
+
+/// Event payload for socket session status updates
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SocketConnectionStatus {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub status: String,
+    pub message: String,
+}
+
+/// Connects to a console on a Unix domain socket
+///
+/// With `telnet` set the session is a telnet session (events are
+/// `telnet-output`/`telnet-status`) and `cols`, `rows` and `options` apply as
+/// for `connect_telnet`. Otherwise bytes are passed through untouched and
+/// output is delivered on `on_output` or as `socket-output` events.
+#[tauri::command]
+pub fn connect_unix_socket(
+    app: AppHandle,
+    path: String,
+    telnet: Option<bool>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    options: Option<TelnetOptions>,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    let sink = EventSink::App(app);
+    if telnet.unwrap_or(false) {
+        let options = options.unwrap_or_default();
+        open_telnet_unix(sink, path, cols.zip(rows), options, on_output)
+    } else {
+        open_unix_socket(sink, path, on_output)
+    }
+}
+
+/// Connects to a raw TCP console with no telnet processing
+///
+/// Output is delivered on `on_output` or as `socket-output` events, and
+/// status changes as `socket-status` events.
+///
+/// Runs on the blocking pool, since resolving and connecting to an
+/// unreachable host can take until the connection timeout.
+#[tauri::command]
+pub async fn connect_raw(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    options: Option<RawOptions>,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        open_raw(
+            EventSink::App(app),
+            host,
+            port,
+            options.unwrap_or_default(),
+            on_output,
+        )
+    })
+    .await
+    .map_err(|e| format!("Raw connection task failed: {}", e))?
+}
+
+/// Opens a raw Unix socket session whose events are delivered to `sink`.
+pub fn open_unix_socket(
+    sink: EventSink,
+    path: String,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    println!("[Socket] Connecting to {}", path);
+
+    let stream =
+        UnixStream::connect(expand_home(&path)).map_err(|e| format!("Connection failed: {}", e))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+    Ok(start_socket(
+        sink,
+        SocketStream::Unix(stream),
+        path,
+        SessionKind::Socket,
+        RawOptions::default(),
+        on_output,
+    ))
+}
+
+/// Opens a raw TCP session whose events are delivered to `sink`.
+pub fn open_raw(
+    sink: EventSink,
+    host: String,
+    port: u16,
+    options: RawOptions,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    println!("[Socket] Connecting to raw TCP {}:{}", host, port);
+
+    let addr_str = format!("{}:{}", host, port);
+    let socket_addr = addr_str
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve address '{}': {}", addr_str, e))?
+        .next()
+        .ok_or_else(|| format!("No addresses found for '{}'", addr_str))?;
+
+    let stream = TcpStream::connect_timeout(
+        &socket_addr,
+        Duration::from_secs(CONNECTION_TIMEOUT_SECS),
+    )
+    .map_err(|e| format!("Connection failed: {}", e))?;
+    stream
+        .set_nodelay(true)
+        .map_err(|e| format!("Failed to set nodelay: {}", e))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+    Ok(start_socket(
+        sink,
+        SocketStream::Tcp(stream),
+        addr_str,
+        SessionKind::Raw,
+        options,
+        on_output,
+    ))
+}
+
+/// Registers a connected socket session and starts its I/O tasks
+fn start_socket(
+    sink: EventSink,
+    stream: SocketStream,
+    label: String,
+    kind: SessionKind,
+    options: RawOptions,
+    on_output: Option<Channel<String>>,
+) -> String {
+    // Generate session ID
+    let session_id = Uuid::new_v4().to_string();
+    println!("[Socket] Generated session ID: {}", session_id);
+
+    let running = Arc::new(Mutex::new(true));
+    let shutdown = Arc::new(Notify::new());
+    let (writer, outgoing) = mpsc::unbounded_channel();
+
+    // Register session with the global session manager
+    sessions().insert(
+        &session_id,
+        Arc::new(SocketSession {
+            id: session_id.clone(),
+            writer,
+            path: label.clone(),
+            kind,
+            translator: Mutex::new(LineTranslator::new(options.clone())),
+            running: running.clone(),
+            shutdown: shutdown.clone(),
+        }),
+    );
+
+    sink.emit(
+        "socket-status",
+        &session_id,
+        SocketConnectionStatus {
+            session_id: session_id.clone(),
+            status: "connected".to_string(),
+            message: format!("Connected to {}", label),
+        },
+    );
+
+    let output = spawn_output_pump(
+        sink.clone(),
+        &session_id,
+        on_output,
+        "socket-output",
+        |session_id, data| SocketOutput {
+            session_id: session_id.to_string(),
+            data,
+        },
+    );
+    tauri::async_runtime::spawn(run_socket(
+        sink,
+        session_id.clone(),
+        stream,
+        LineTranslator::new(options),
+        outgoing,
+        output,
+        running,
+        shutdown,
+    ));
+
+    println!("[Socket] Session {} connected to {}", session_id, label);
+    session_id
+}
+
+/// Drives a socket connection until either side closes it
+#[allow(clippy::too_many_arguments)]
+async fn run_socket(
+    sink: EventSink,
+    session_id: String,
+    stream: SocketStream,
+    mut translator: LineTranslator,
+    mut outgoing: UnboundedReceiver<Vec<u8>>,
+    output: OutputSender,
+    running: Arc<Mutex<bool>>,
+    shutdown: Arc<Notify>,
+) {
+    println!("[Socket] Reader task started for {}", session_id);
+
+    let (mut reader, mut writer) = match stream.split() {
+        Ok(halves) => halves,
+        Err(e) => {
+            eprintln!("[Socket] Failed to register stream for {}: {}", session_id, e);
+            *running.lock() = false;
+            sessions().unregister(&session_id);
+            return;
+        }
+    };
+
+    // Writer task: forwards queued input until the session is dropped
+    let writer_session_id = session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(data) = outgoing.recv().await {
+            if let Err(e) = writer.write_all(&data).await {
+                eprintln!("[Socket] Write error for {}: {}", writer_session_id, e);
+                break;
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+
+    let mut buf = [0u8; SOCKET_READ_BUFFER_SIZE];
+    let mut decoder = Utf8Decoder::new();
+
+    loop {
+        let read = tokio::select! {
+            read = reader.read(&mut buf) => read,
+            _ = shutdown.notified() => {
+                println!("[Socket] Reader task stopping for {}", session_id);
+                break;
+            }
+        };
+
+        match read {
+            Ok(0) => {
+                println!("[Socket] Connection closed for {}", session_id);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "socket-status",
+                    &session_id,
+                    SocketConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "disconnected".to_string(),
+                        message: "Connection closed by remote end".to_string(),
+                    },
+                );
+                break;
+            }
+            Ok(n) => {
+                let data = decoder.decode(&translator.decode(&buf[..n]));
+                if !data.is_empty() {
+                    // Write to log files and scrollback for this session
+                    record_output(&session_id, &data);
+                    output.send(data);
+                }
+            }
+            Err(e) => {
+                eprintln!("[Socket] Read error for {}: {}", session_id, e);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "socket-status",
+                    &session_id,
+                    SocketConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "error".to_string(),
+                        message: format!("Read error: {}", e),
+                    },
+                );
+                break;
+            }
+        }
+    }
+
+    // Clean up session; dropping it closes the writer queue and the socket
+    *running.lock() = false;
+    sessions().unregister(&session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_newlines() {
+        let mut cr = LineTranslator::default();
+        assert_eq!(cr.encode(b"show\r"), b"show\r");
+
+        let mut lf = LineTranslator::new(RawOptions {
+            newline: RawNewline::Lf,
+            ..Default::default()
+        });
+        assert_eq!(lf.encode(b"a\rb\r"), b"a\nb\n");
+
+        let mut crlf = LineTranslator::new(RawOptions {
+            newline: RawNewline::CrLf,
+            ..Default::default()
+        });
+        assert_eq!(crlf.encode(b"HELO x\r"), b"HELO x\r\n");
+        // Pasted CR LF is not doubled, and 0xFF is sent as a single byte
+        assert_eq!(crlf.encode(b"a\r\n\xff"), b"a\r\n\xff");
+    }
+
+    #[test]
+    fn test_encode_split_crlf() {
+        let mut crlf = LineTranslator::new(RawOptions {
+            newline: RawNewline::CrLf,
+            ..Default::default()
+        });
+        // A chunked paste that splits CR LF still sends one line ending
+        assert_eq!(crlf.encode(b"line 1\r"), b"line 1\r\n");
+        assert_eq!(crlf.encode(b"\nline 2\r"), b"line 2\r\n");
+        assert_eq!(crlf.encode(b"\n"), b"");
+        // A later LF is not part of the pair
+        assert_eq!(crlf.encode(b"\n"), b"\n");
+
+        let mut lf = LineTranslator::new(RawOptions {
+            newline: RawNewline::Lf,
+            ..Default::default()
+        });
+        assert_eq!(lf.encode(b"a\r"), b"a\n");
+        assert_eq!(lf.encode(b"\nb"), b"b");
+    }
+
+    #[test]
+    fn test_decode_lf_to_crlf() {
+        let mut off = LineTranslator::default();
+        assert_eq!(off.decode(b"a\nb"), b"a\nb");
+
+        let mut on = LineTranslator::new(RawOptions {
+            lf_to_crlf: true,
+            ..Default::default()
+        });
+        assert_eq!(on.decode(b"a\nb\r\n"), b"a\r\nb\r\n");
+        // CR LF split across reads keeps a single CR
+        assert_eq!(on.decode(b"c\r"), b"c\r");
+        assert_eq!(on.decode(b"\nd\n"), b"\nd\r\n");
+    }
+
+    #[test]
+    fn test_received_0xff_is_replaced() {
+        // The reader's decode path: no IAC handling, but invalid UTF-8 is replaced
+        let mut translator = LineTranslator::default();
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(&translator.decode(b"a\xffb")), "a\u{FFFD}b");
+        assert_eq!(
+            decoder.decode(&translator.decode(b"\xff\xff")),
+            "\u{FFFD}\u{FFFD}"
+        );
+    }
+}
@@ -1,41 +1,74 @@
 //! SSH Connection Module for Packet
 //!
 //! This module provides SSH connectivity for network devices and servers.
-//! Supports password authentication and key-based authentication.
+//! Supports password, key-based, ssh-agent and keyboard-interactive
+//! authentication.
 //!
 //! # Architecture
 //!
 //! Each SSH session maintains:
-//! - A TCP connection with SSH2 session
+//! - A TCP connection (or a tunnel through jump hosts) with SSH2 session
 //! - A channel for shell interaction  
-//! - A reader thread that emits output events to the frontend
-//! - Writer access for sending commands
+//! - An I/O task that emits output events to the frontend
+//! - A queue of input and window size changes for the channel
+//!
+//! The handshake and authentication run blocking on the calling thread. After
+//! that libssh2 is switched to non-blocking mode and the I/O task waits on the
+//! socket's readiness on the tokio runtime instead of polling. The channel is
+//! only touched from the I/O task, so resizes are queued to it like input and
+//! sent as window-change requests. Port forwards (see the `forward` module)
+//! are channels on the same connection and are driven by the same task.
+//!
+//! The server's host key is checked against the known_hosts files before
+//! authenticating (see the `known_hosts` module).
+//!
+//! The host may be an alias from `~/.ssh/config` (see the `ssh_config`
+//! module): its HostName, Port, User and ServerAliveInterval apply unless the
+//! caller gave them explicitly, and its IdentityFile entries are used by the
+//! `Config` authentication method. Its ProxyJump is used unless jump hosts
+//! are given explicitly (see the `jump` module).
 //!
 //! # Authentication Methods
 //!
 //! 1. Password - Traditional username/password
 //! 2. PublicKey - SSH key file (with optional passphrase)
-
-use crate::logging::{cleanup_session_logs, write_to_logs};
-use once_cell::sync::Lazy;
+//! 3. Agent - Each identity held by the ssh-agent at `SSH_AUTH_SOCK`, in turn
+//! 4. KeyboardInteractive - Server prompts answered by the user (see the
+//!    `auth_prompt` module); also used when a password has expired
+//! 5. Config - The host's IdentityFile keys and agent keys, as OpenSSH
+//!    would try them
+
+use crate::auth_prompt::userauth_interactive;
+use crate::forward::{ForwardRequest, ForwardTable, Forwarder};
+use crate::jump::{self, parse_proxy_jump, JumpHost};
+use crate::known_hosts::{fingerprint, verify_host_key};
+use crate::output::{spawn_output_pump, OutputSender};
+use crate::session::{record_final_output, record_output, sessions, EventSink, Session, SessionInfo, SessionKind, SessionStatus};
+use crate::ssh_config::{SshConfig, SshHostConfig};
+use crate::utf8::Utf8Decoder;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use ssh2::{Channel, Session};
-use std::collections::HashMap;
-use std::io::{Read, Write};
-use std::net::TcpStream;
-use std::path::PathBuf;
+use ssh2::{BlockDirections, Channel};
+use std::any::Any;
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::thread;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
+use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
 use uuid::Uuid;
 
 /// Buffer size for reading SSH output (8KB)
 const SSH_READ_BUFFER_SIZE: usize = 8192;
 
-/// Connection timeout in seconds
-const CONNECTION_TIMEOUT_SECS: u64 = 30;
+/// libssh2's error for a password the server wants changed
+const LIBSSH2_ERROR_PASSWORD_EXPIRED: i32 = -15;
 
 /// Authentication method for SSH connections
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -54,110 +87,190 @@ pub enum SshAuthMethod {
         /// Optional passphrase for encrypted keys
         passphrase: Option<String>,
     },
+
+    /// Keys held by the running ssh-agent
+    #[serde(rename = "agent")]
+    Agent,
+
+    /// Prompts from the server (AAA, RADIUS/TACACS+, OTP), answered in the UI
+    #[serde(rename = "keyboard-interactive")]
+    KeyboardInteractive,
+
+    /// IdentityFile keys from `~/.ssh/config` (or the default keys) and the
+    /// agent's keys, limited to those with IdentitiesOnly
+    #[serde(rename = "config")]
+    Config,
 }
 
-/// Wrapper for Channel that implements Send
-pub(crate) struct SendChannel(Channel);
+/// A public key held by the ssh-agent
+#[derive(Clone, Debug, Serialize)]
+pub struct AgentIdentity {
+    /// Key comment, usually the file it was loaded from or user@host
+    pub comment: String,
+    #[serde(rename = "keyType")]
+    pub key_type: String,
+    pub fingerprint: String,
+}
 
-// Safety: We protect all access with a mutex
-unsafe impl Send for SendChannel {}
+/// Work queued for a session's I/O task
+pub enum SshInput {
+    /// Bytes to write to the channel
+    Data(Vec<u8>),
+    /// New terminal size to report to the remote PTY
+    Resize { cols: u32, rows: u32 },
+    /// Port forward to add or remove
+    Forward(ForwardRequest),
+}
 
 /// Represents an active SSH session
 pub struct SshSession {
-    /// The SSH channel for I/O (wrapped for thread-safe access)
-    pub(crate) channel: Arc<Mutex<SendChannel>>,
+    /// Session identifier
+    pub id: String,
+    /// Queue of input and resizes, drained by the session's I/O task
+    pub writer: UnboundedSender<SshInput>,
     /// Connection info for display
     pub host: String,
     pub port: u16,
     pub username: String,
-    /// Flag to signal reader thread to stop
+    /// Whether the connection is still open
     pub running: Arc<Mutex<bool>>,
+    /// Wakes the I/O task when the session is closed locally
+    pub shutdown: Arc<Notify>,
+    /// Port forwards running on the connection
+    pub forwards: ForwardTable,
 }
 
-/// Global state for all SSH sessions
-static SSH_SESSIONS: Lazy<Arc<Mutex<HashMap<String, SshSession>>>> = Lazy::new(|| {
-    println!("[SSH] Initializing global SSH session store");
-    Arc::new(Mutex::new(HashMap::new()))
-});
+impl Session for SshSession {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.writer
+            .send(SshInput::Data(data.to_vec()))
+            .map_err(|_| "Failed to write to SSH: connection closed".to_string())
+    }
 
-/// Event payload emitted when SSH output is available
-#[derive(Clone, Serialize, Deserialize)]
-pub struct SshOutput {
-    #[serde(rename = "sessionId")]
-    pub session_id: String,
-    pub data: String,
-}
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        // Sent as a window-change request by the I/O task
+        self.writer
+            .send(SshInput::Resize {
+                cols: cols.into(),
+                rows: rows.into(),
+            })
+            .map_err(|_| "Failed to resize SSH: connection closed".to_string())
+    }
 
-/// Event payload for SSH connection status updates
-#[derive(Clone, Serialize, Deserialize)]
-pub struct SshConnectionStatus {
-    #[serde(rename = "sessionId")]
-    pub session_id: String,
-    pub status: String,
-    pub message: String,
-}
+    fn close(&self) -> Result<(), String> {
+        // Signal the I/O task to close the channel and stop
+        *self.running.lock() = false;
+        self.shutdown.notify_one();
+        Ok(())
+    }
 
-/// Establishes a new SSH connection
-///
-/// # Arguments
-/// * `app` - Tauri application handle for emitting events
-/// * `host` - Hostname or IP address
-/// * `port` - SSH port (typically 22)
-/// * `username` - SSH username
-/// * `auth` - Authentication method (password or public key)
-/// * `cols` - Terminal columns
-/// * `rows` - Terminal rows
-///
-/// # Returns
-/// Session ID string on success
-#[tauri::command]
-pub fn connect_ssh(
-    app: AppHandle,
-    host: String,
-    port: u16,
-    username: String,
-    auth: SshAuthMethod,
-    cols: u32,
-    rows: u32,
-) -> Result<String, String> {
-    println!("[SSH] Connecting to {}@{}:{}", username, host, port);
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            kind: SessionKind::Ssh,
+            label: format!("{}@{}:{}", self.username, self.host, self.port),
+            status: self.status(),
+        }
+    }
 
-    let session_id = Uuid::new_v4().to_string();
-    println!("[SSH] Generated session ID: {}", session_id);
+    fn status(&self) -> SessionStatus {
+        if *self.running.lock() {
+            SessionStatus::Connected
+        } else {
+            SessionStatus::Disconnected
+        }
+    }
 
-    let sessions_ptr = SSH_SESSIONS.clone();
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-    // Create TCP connection with timeout
-    let addr = format!("{}:{}", host, port);
-    let tcp = TcpStream::connect_timeout(
-        &addr
-            .parse()
-            .map_err(|e| format!("Invalid address '{}': {}", addr, e))?,
-        Duration::from_secs(CONNECTION_TIMEOUT_SECS),
-    )
-    .map_err(|e| format!("TCP connection failed: {}", e))?;
+/// A host to log into, with `~/.ssh/config` applied
+pub struct SshLogin {
+    /// Name as given, which may be a `~/.ssh/config` alias
+    pub host: String,
+    /// Address to connect to
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuthMethod,
+    pub config: SshHostConfig,
+}
+
+impl SshLogin {
+    /// Fills in whatever was not given explicitly from `~/.ssh/config`,
+    /// then port 22 and the local user
+    pub fn resolve(
+        host: &str,
+        port: Option<u16>,
+        username: Option<String>,
+        auth: SshAuthMethod,
+    ) -> Result<Self, String> {
+        let config = SshConfig::load().resolve(host);
+        let username = username
+            .filter(|username| !username.is_empty())
+            .or_else(|| config.user.clone())
+            .or_else(|| std::env::var("USER").ok())
+            .ok_or_else(|| format!("No username given for {} and none set in ~/.ssh/config", host))?;
+
+        Ok(SshLogin {
+            host: host.to_string(),
+            hostname: config.hostname.clone(),
+            port: port.or(config.port).unwrap_or(22),
+            username,
+            auth,
+            config,
+        })
+    }
+}
 
-    // Configure the stream
-    tcp.set_read_timeout(Some(Duration::from_millis(100)))
-        .map_err(|e| format!("Failed to set read timeout: {}", e))?;
-    tcp.set_nodelay(true)
-        .map_err(|e| format!("Failed to set nodelay: {}", e))?;
+/// Runs the SSH handshake over `stream`, checks the host key and
+/// authenticates.
+///
+/// `request_id` identifies any host key or keyboard-interactive prompts.
+pub fn login_session<S: AsRawFd + 'static>(
+    sink: &EventSink,
+    request_id: &str,
+    stream: S,
+    login: &SshLogin,
+) -> Result<ssh2::Session, String> {
+    let SshLogin {
+        host,
+        hostname,
+        port,
+        username,
+        auth,
+        config,
+    } = login;
+    let port = *port;
 
     // Create SSH session
     let mut session =
-        Session::new().map_err(|e| format!("Failed to create SSH session: {}", e))?;
+        ssh2::Session::new().map_err(|e| format!("Failed to create SSH session: {}", e))?;
 
-    session.set_tcp_stream(tcp);
+    session.set_tcp_stream(stream);
     session
         .handshake()
-        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+        .map_err(|e| format!("SSH handshake with {} failed: {}", host, e))?;
+
+    // Refuse unknown or changed host keys before sending any credentials
+    verify_host_key(&session, sink, request_id, hostname, port)?;
 
     // Authenticate based on method
-    match &auth {
+    match auth {
         SshAuthMethod::Password { password } => {
-            session
-                .userauth_password(&username, password)
-                .map_err(|e| format!("Password authentication failed: {}", e))?;
+            if let Err(e) = session.userauth_password(username, password) {
+                if e.code() != ssh2::ErrorCode::Session(LIBSSH2_ERROR_PASSWORD_EXPIRED) {
+                    return Err(format!("Password authentication failed: {}", e));
+                }
+                // Servers run the password change dialog over keyboard-interactive
+                println!(
+                    "[SSH] Password expired for {}@{}, asking for a new one",
+                    username, host
+                );
+                userauth_interactive(&session, sink, request_id, host, port, username)?;
+            }
         }
         SshAuthMethod::PublicKey {
             key_path,
@@ -177,15 +290,281 @@ pub fn connect_ssh(
             };
 
             session
-                .userauth_pubkey_file(&username, None, &expanded_path, passphrase.as_deref())
+                .userauth_pubkey_file(username, None, &expanded_path, passphrase.as_deref())
                 .map_err(|e| format!("Public key authentication failed: {}", e))?;
         }
+        SshAuthMethod::Agent => {
+            userauth_agent(&session, username)?;
+        }
+        SshAuthMethod::KeyboardInteractive => {
+            userauth_interactive(&session, sink, request_id, host, port, username)?;
+        }
+        SshAuthMethod::Config => {
+            userauth_config(&session, username, config)?;
+        }
     }
 
     if !session.authenticated() {
-        return Err("Authentication failed - check credentials".to_string());
+        return Err(format!(
+            "Authentication to {} failed - check credentials",
+            host
+        ));
+    }
+
+    Ok(session)
+}
+
+/// Connects to the ssh-agent named by `SSH_AUTH_SOCK`
+fn connect_agent(session: &ssh2::Session) -> Result<ssh2::Agent, String> {
+    if std::env::var_os("SSH_AUTH_SOCK").is_none() {
+        return Err("No ssh-agent running (SSH_AUTH_SOCK is not set)".to_string());
+    }
+
+    let mut agent = session
+        .agent()
+        .map_err(|e| format!("Failed to create agent client: {}", e))?;
+    agent
+        .connect()
+        .map_err(|e| format!("Failed to connect to ssh-agent: {}", e))?;
+    agent
+        .list_identities()
+        .map_err(|e| format!("Failed to list agent identities: {}", e))?;
+    Ok(agent)
+}
+
+/// Tries each agent identity until the server accepts one
+fn userauth_agent(session: &ssh2::Session, username: &str) -> Result<(), String> {
+    let mut agent = connect_agent(session)?;
+    let identities = agent
+        .identities()
+        .map_err(|e| format!("Failed to list agent identities: {}", e))?;
+
+    if identities.is_empty() {
+        let _ = agent.disconnect();
+        return Err("The ssh-agent holds no identities (add one with ssh-add)".to_string());
+    }
+
+    for identity in &identities {
+        match agent.userauth(username, identity) {
+            Ok(()) => {
+                println!("[SSH] Authenticated with agent key {}", identity.comment());
+                let _ = agent.disconnect();
+                return Ok(());
+            }
+            Err(e) => {
+                println!("[SSH] Agent key {} rejected: {}", identity.comment(), e);
+            }
+        }
     }
 
+    let _ = agent.disconnect();
+    Err(format!(
+        "Agent authentication failed: none of the {} agent identities were accepted",
+        identities.len()
+    ))
+}
+
+/// Keys OpenSSH tries when no IdentityFile is configured
+fn default_identity_files() -> Vec<String> {
+    let ssh_dir = dirs::home_dir().unwrap_or_default().join(".ssh");
+    ["id_rsa", "id_ecdsa", "id_ed25519"]
+        .iter()
+        .map(|name| ssh_dir.join(name).to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Reads the public key blob from `<key>.pub`
+fn read_public_key(key_path: &str) -> Option<Vec<u8>> {
+    let content = std::fs::read_to_string(format!("{}.pub", key_path)).ok()?;
+    let encoded = content.split_whitespace().nth(1)?;
+    STANDARD.decode(encoded).ok()
+}
+
+/// Authenticates with the keys `~/.ssh/config` names for the host: agent
+/// keys first (only the configured ones with IdentitiesOnly), then key files
+/// that need no passphrase
+fn userauth_config(
+    session: &ssh2::Session,
+    username: &str,
+    config: &SshHostConfig,
+) -> Result<(), String> {
+    let identity_files = if config.identity_files.is_empty() {
+        default_identity_files()
+    } else {
+        config.identity_files.clone()
+    };
+    let configured: Vec<Vec<u8>> = identity_files
+        .iter()
+        .filter_map(|path| read_public_key(path))
+        .collect();
+
+    if let Ok(mut agent) = connect_agent(session) {
+        let identities = agent.identities().unwrap_or_default();
+        let allowed = identities.iter().filter(|identity| {
+            !config.identities_only || configured.iter().any(|blob| blob == identity.blob())
+        });
+        for identity in allowed {
+            if agent.userauth(username, identity).is_ok() {
+                println!("[SSH] Authenticated with agent key {}", identity.comment());
+                let _ = agent.disconnect();
+                return Ok(());
+            }
+        }
+        let _ = agent.disconnect();
+    }
+
+    for path in identity_files
+        .iter()
+        .map(Path::new)
+        .filter(|path| path.exists())
+    {
+        match session.userauth_pubkey_file(username, None, path, None) {
+            Ok(()) => {
+                println!("[SSH] Authenticated with key {}", path.display());
+                return Ok(());
+            }
+            Err(e) => println!("[SSH] Key {} rejected: {}", path.display(), e),
+        }
+    }
+
+    Err(format!(
+        "No key from ~/.ssh/config or the ssh-agent was accepted for {} \
+         (keys with a passphrase must be added to the agent with ssh-add)",
+        username
+    ))
+}
+
+/// Reads the key type from the start of an SSH public key blob
+fn blob_key_type(blob: &[u8]) -> Option<String> {
+    let len = u32::from_be_bytes(blob.get(..4)?.try_into().ok()?) as usize;
+    let name = blob.get(4..4 + len)?;
+    String::from_utf8(name.to_vec()).ok()
+}
+
+/// Lists the identities held by the ssh-agent
+#[tauri::command]
+pub fn list_agent_identities() -> Result<Vec<AgentIdentity>, String> {
+    let session =
+        ssh2::Session::new().map_err(|e| format!("Failed to create SSH session: {}", e))?;
+    let mut agent = connect_agent(&session)?;
+    let identities = agent
+        .identities()
+        .map_err(|e| format!("Failed to list agent identities: {}", e))?;
+    let _ = agent.disconnect();
+
+    Ok(identities
+        .iter()
+        .map(|identity| AgentIdentity {
+            comment: identity.comment().to_string(),
+            key_type: blob_key_type(identity.blob()).unwrap_or_else(|| "unknown".to_string()),
+            fingerprint: fingerprint(identity.blob()),
+        })
+        .collect())
+}
+
+/// Event payload emitted when SSH output is available
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SshOutput {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub data: String,
+}
+
+/// Event payload for SSH connection status updates
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SshConnectionStatus {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub status: String,
+    pub message: String,
+}
+
+/// Establishes a new SSH connection
+///
+/// # Arguments
+/// * `app` - Tauri application handle for emitting events
+/// * `host` - Hostname, IP address or `~/.ssh/config` alias
+/// * `port` - SSH port (from `~/.ssh/config`, or 22, if not given)
+/// * `username` - SSH username (from `~/.ssh/config`, or the local user, if not given)
+/// * `auth` - Authentication method (password or public key)
+/// * `jump_hosts` - SSH bastions to tunnel through, in order (defaults to
+///   the host's ProxyJump from `~/.ssh/config`)
+/// * `cols` - Terminal columns
+/// * `rows` - Terminal rows
+/// * `on_output` - Channel for batched output (falls back to `ssh-output` events)
+///
+/// # Returns
+/// Session ID string on success
+///
+/// Runs on the blocking pool, since connecting may wait for the user to
+/// confirm an unknown host key.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn connect_ssh(
+    app: AppHandle,
+    host: String,
+    port: Option<u16>,
+    username: Option<String>,
+    auth: SshAuthMethod,
+    jump_hosts: Option<Vec<JumpHost>>,
+    cols: u32,
+    rows: u32,
+    on_output: Option<tauri::ipc::Channel<String>>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        open_ssh(
+            EventSink::App(app),
+            host,
+            port,
+            username,
+            auth,
+            jump_hosts.unwrap_or_default(),
+            cols,
+            rows,
+            on_output,
+        )
+    })
+    .await
+    .map_err(|e| format!("SSH connection task failed: {}", e))?
+}
+
+/// Opens an SSH session whose events are delivered to `sink`.
+#[allow(clippy::too_many_arguments)]
+pub fn open_ssh(
+    sink: EventSink,
+    host: String,
+    port: Option<u16>,
+    username: Option<String>,
+    auth: SshAuthMethod,
+    jump_hosts: Vec<JumpHost>,
+    cols: u32,
+    rows: u32,
+    on_output: Option<tauri::ipc::Channel<String>>,
+) -> Result<String, String> {
+    let login = SshLogin::resolve(&host, port, username, auth)?;
+    // Explicit jump hosts win over ProxyJump from ~/.ssh/config
+    let jump_hosts = if jump_hosts.is_empty() {
+        login
+            .config
+            .proxy_jump
+            .as_deref()
+            .map(parse_proxy_jump)
+            .unwrap_or_default()
+    } else {
+        jump_hosts
+    };
+    let port = login.port;
+    let username = login.username.clone();
+
+    println!("[SSH] Connecting to {}@{}:{}", username, login.hostname, port);
+
+    let session_id = Uuid::new_v4().to_string();
+    println!("[SSH] Generated session ID: {}", session_id);
+
+    // Direct TCP connection, or a tunnel through the jump hosts
+    let stream = jump::connect(&sink, &session_id, &jump_hosts, &login.hostname, port)?;
+    let socket_fd = stream.as_raw_fd();
+    let session = login_session(&sink, &session_id, stream, &login)?;
     println!("[SSH] Authentication successful for {}", session_id);
 
     // Open a channel and request PTY
@@ -203,39 +582,38 @@ pub fn connect_ssh(
         .shell()
         .map_err(|e| format!("Failed to start shell: {}", e))?;
 
-    // Make session non-blocking for reading
+    // ServerAliveInterval; the I/O task sends the keepalives
+    if let Some(interval) = login.config.server_alive_interval {
+        session.set_keepalive(false, interval);
+    }
+
+    // From here on the I/O task waits for socket readiness instead
     session.set_blocking(false);
 
     let running = Arc::new(Mutex::new(true));
-    let running_clone = running.clone();
-
-    // Wrap channel in Arc<Mutex> for shared access
-    let channel = Arc::new(Mutex::new(SendChannel(channel)));
-    let channel_for_reader = channel.clone();
-
-    // Store session
-    {
-        let mut sessions = sessions_ptr.lock();
-        sessions.insert(
-            session_id.clone(),
-            SshSession {
-                channel,
-                host: host.clone(),
-                port,
-                username: username.clone(),
-                running,
-            },
-        );
-        println!(
-            "[SSH] Session {} stored. Total sessions: {}",
-            session_id,
-            sessions.len()
-        );
-    }
+    let shutdown = Arc::new(Notify::new());
+    let forwards = ForwardTable::default();
+    let (writer, input) = mpsc::unbounded_channel();
+
+    // Register session with the global session manager
+    sessions().insert(
+        &session_id,
+        Arc::new(SshSession {
+            id: session_id.clone(),
+            writer,
+            host: host.clone(),
+            port,
+            username: username.clone(),
+            running: running.clone(),
+            shutdown: shutdown.clone(),
+            forwards: forwards.clone(),
+        }),
+    );
 
     // Emit connection success
-    let _ = app.emit(
+    sink.emit(
         "ssh-status",
+        &session_id,
         SshConnectionStatus {
             session_id: session_id.clone(),
             status: "connected".to_string(),
@@ -243,124 +621,232 @@ pub fn connect_ssh(
         },
     );
 
-    // Spawn reader thread
-    let session_id_read = session_id.clone();
-    let sessions_for_cleanup = sessions_ptr.clone();
-    let host_display = host.clone();
-    let username_display = username.clone();
-
-    thread::spawn(move || {
-        println!("[SSH] Reader thread started for {}", session_id_read);
-        let mut buf = [0u8; SSH_READ_BUFFER_SIZE];
-
-        loop {
-            // Check if we should stop
-            if !*running_clone.lock() {
-                println!("[SSH] Reader thread stopping for {}", session_id_read);
-                break;
-            }
+    let output = spawn_output_pump(
+        sink.clone(),
+        &session_id,
+        on_output,
+        "ssh-output",
+        |session_id, data| SshOutput {
+            session_id: session_id.to_string(),
+            data,
+        },
+    );
+    tauri::async_runtime::spawn(run_ssh(
+        sink,
+        session_id.clone(),
+        SshIo {
+            session,
+            channel,
+            socket_fd,
+            keepalive: login.config.server_alive_interval,
+            forwards: Forwarder::new(&session_id, forwards),
+        },
+        input,
+        output,
+        running,
+        shutdown,
+    ));
 
-            // Try to read from channel
-            let read_result = {
-                let mut channel_guard = channel_for_reader.lock();
-                channel_guard.0.read(&mut buf)
-            };
+    println!("[SSH] connect_ssh returning id: {}", session_id);
+    Ok(session_id)
+}
 
-            match read_result {
-                Ok(0) => {
-                    // Check if channel is at EOF
-                    let is_eof = {
-                        let channel_guard = channel_for_reader.lock();
-                        channel_guard.0.eof()
-                    };
-
-                    if is_eof {
-                        println!("[SSH] Connection closed for {}", session_id_read);
-                        let _ = app.emit(
-                            "ssh-status",
-                            SshConnectionStatus {
-                                session_id: session_id_read.clone(),
-                                status: "disconnected".to_string(),
-                                message: "Connection closed by remote host".to_string(),
-                            },
-                        );
-                        break;
+/// The libssh2 handles owned by a session's I/O task
+struct SshIo {
+    session: ssh2::Session,
+    channel: Channel,
+    /// Socket owned by `session`, watched for readiness
+    socket_fd: RawFd,
+    /// Seconds between keepalives, if configured
+    keepalive: Option<u32>,
+    /// Port forwards carried by the connection
+    forwards: Forwarder,
+}
+
+/// Why the I/O task stopped
+enum SshExit {
+    /// Closed locally via `disconnect_ssh` / `session_close`
+    Closed,
+    /// The remote side closed the channel
+    Eof,
+    Error(String),
+}
+
+/// Drives an SSH channel until either side closes it
+async fn run_ssh(
+    sink: EventSink,
+    session_id: String,
+    io: SshIo,
+    mut input: UnboundedReceiver<SshInput>,
+    output: OutputSender,
+    running: Arc<Mutex<bool>>,
+    shutdown: Arc<Notify>,
+) {
+    println!("[SSH] I/O task started for {}", session_id);
+
+    let SshIo {
+        session,
+        mut channel,
+        socket_fd,
+        keepalive,
+        mut forwards,
+    } = io;
+
+    let mut decoder = Utf8Decoder::new();
+    let exit = match AsyncFd::with_interest(socket_fd, Interest::READABLE | Interest::WRITABLE) {
+        Ok(socket) => {
+            let mut buf = [0u8; SSH_READ_BUFFER_SIZE];
+            // Input the channel has not accepted yet; output keeps flowing meanwhile
+            let mut pending: Vec<u8> = Vec::new();
+            // Window size libssh2 is part-way through sending, and the latest
+            // size to send once it is done
+            let mut resizing: Option<(u32, u32)> = None;
+            let mut next_size: Option<(u32, u32)> = None;
+            let mut next_keepalive = keepalive
+                .map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs.into()));
+
+            'io: loop {
+                // Drain everything libssh2 can hand out without blocking
+                loop {
+                    match channel.read(&mut buf) {
+                        Ok(0) if channel.eof() => break 'io SshExit::Eof,
+                        Ok(0) => break,
+                        Ok(n) => {
+                            // Multibyte characters may be split across reads
+                            let data = decoder.decode(&buf[..n]);
+                            if data.is_empty() {
+                                continue;
+                            }
+
+                            // Write to log files and scrollback for this session
+                            record_output(&session_id, &data);
+                            output.send(data);
+                        }
+                        Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => break 'io SshExit::Error(format!("Connection error: {}", e)),
                     }
-                    thread::sleep(Duration::from_millis(10));
                 }
-                Ok(n) => {
-                    let data = String::from_utf8_lossy(&buf[..n]).to_string();
-
-                    // Write to any active log files for this session
-                    write_to_logs(&session_id_read, &data);
-
-                    let _ = app.emit(
-                        "ssh-output",
-                        SshOutput {
-                            session_id: session_id_read.clone(),
-                            data,
-                        },
-                    );
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(10));
+
+                // Push queued input until the channel stops accepting it
+                while !pending.is_empty() {
+                    match channel.write(&pending) {
+                        Ok(n) => {
+                            pending.drain(..n);
+                        }
+                        Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => break 'io SshExit::Error(format!("Write error: {}", e)),
+                    }
                 }
-                Err(e) => {
-                    // Check if it's just a temporary error
-                    if e.kind() == std::io::ErrorKind::Interrupted {
-                        continue;
+
+                // Report the window size; a failed window change is not fatal
+                while let Some((cols, rows)) = resizing.take().or_else(|| next_size.take()) {
+                    match channel
+                        .request_pty_size(cols, rows, None, None)
+                        .map_err(std::io::Error::from)
+                    {
+                        Ok(()) => {}
+                        Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                            resizing = Some((cols, rows));
+                            break;
+                        }
+                        Err(e) => eprintln!("[SSH] Failed to resize {}: {}", session_id, e),
                     }
+                }
+
+                // Forwarded channels may leave data for the shell in libssh2's
+                // buffers, so go round again until nothing moves
+                if forwards.pump(&session) {
+                    continue;
+                }
 
-                    eprintln!("[SSH] Read error for {}: {}", session_id_read, e);
-                    let _ = app.emit(
-                        "ssh-status",
-                        SshConnectionStatus {
-                            session_id: session_id_read.clone(),
-                            status: "error".to_string(),
-                            message: format!("Connection error: {}", e),
-                        },
-                    );
-                    break;
+                // Wait for whatever libssh2 is blocked on, or for new input
+                let interest = match session.block_directions() {
+                    BlockDirections::Outbound => Interest::WRITABLE,
+                    BlockDirections::Both => Interest::READABLE | Interest::WRITABLE,
+                    _ => Interest::READABLE,
+                };
+                tokio::select! {
+                    ready = socket.ready(interest) => match ready {
+                        Ok(mut guard) => guard.clear_ready(),
+                        Err(e) => break 'io SshExit::Error(format!("Connection error: {}", e)),
+                    },
+                    queued = input.recv() => match queued {
+                        Some(SshInput::Data(data)) => pending.extend_from_slice(&data),
+                        Some(SshInput::Resize { cols, rows }) => next_size = Some((cols, rows)),
+                        Some(SshInput::Forward(request)) => forwards.request(request),
+                        None => break 'io SshExit::Closed,
+                    },
+                    _ = forwards.wait() => {}
+                    _ = shutdown.notified() => break 'io SshExit::Closed,
+                    _ = async {
+                        match next_keepalive {
+                            Some(at) => tokio::time::sleep_until(at).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        // libssh2 says how long until the next one is due
+                        let wait = match session.keepalive_send().map_err(std::io::Error::from) {
+                            Ok(secs) => secs.max(1),
+                            Err(ref e) if e.kind() == ErrorKind::WouldBlock => 1,
+                            Err(e) => break 'io SshExit::Error(format!("Keepalive failed: {}", e)),
+                        };
+                        next_keepalive = Some(
+                            tokio::time::Instant::now() + Duration::from_secs(wait.into()),
+                        );
+                    }
                 }
             }
         }
-
-        // Clean up session
-        cleanup_session_logs(&session_id_read);
-        let mut sessions = sessions_for_cleanup.lock();
-        if let Some(_removed) = sessions.remove(&session_id_read) {
-            println!(
-                "[SSH] Session {} ({}@{}:{}) cleaned up. Remaining: {}",
-                session_id_read, username_display, host_display, port,
-                sessions.len()
+        Err(e) => SshExit::Error(format!("Failed to watch socket: {}", e)),
+    };
+
+    // Deliver the last output before reporting why the session ended
+    record_final_output(&session_id, &mut decoder, &output);
+    output.finish().await;
+
+    match exit {
+        SshExit::Closed => {
+            println!("[SSH] I/O task stopping for {}", session_id);
+            // Best effort; the socket closes when the session is dropped
+            let _ = channel.send_eof();
+            let _ = channel.close();
+        }
+        SshExit::Eof => {
+            println!("[SSH] Connection closed for {}", session_id);
+            sink.emit(
+                "ssh-status",
+                &session_id,
+                SshConnectionStatus {
+                    session_id: session_id.clone(),
+                    status: "disconnected".to_string(),
+                    message: "Connection closed by remote host".to_string(),
+                },
+            );
+        }
+        SshExit::Error(message) => {
+            eprintln!("[SSH] {} for {}", message, session_id);
+            sink.emit(
+                "ssh-status",
+                &session_id,
+                SshConnectionStatus {
+                    session_id: session_id.clone(),
+                    status: "error".to_string(),
+                    message,
+                },
             );
         }
-    });
+    }
 
-    println!("[SSH] connect_ssh returning id: {}", session_id);
-    Ok(session_id)
+    // Clean up session
+    *running.lock() = false;
+    sessions().unregister(&session_id);
 }
 
 /// Writes data to an SSH session
 #[tauri::command]
 pub fn write_ssh(session_id: String, data: String) -> Result<(), String> {
-    let sessions_ptr = SSH_SESSIONS.clone();
-    let sessions = sessions_ptr.lock();
-
-    if let Some(session) = sessions.get(&session_id) {
-        let mut channel = session.channel.lock();
-        channel
-            .0
-            .write_all(data.as_bytes())
-            .map_err(|e| format!("Failed to write to SSH: {}", e))?;
-        channel
-            .0
-            .flush()
-            .map_err(|e| format!("Failed to flush SSH: {}", e))?;
-        Ok(())
-    } else {
-        Err(format!("SSH session not found: {}", session_id))
-    }
+    sessions().write(&session_id, data.as_bytes())
 }
 
 /// Resizes an SSH session's PTY
@@ -370,57 +856,29 @@ pub fn resize_ssh(session_id: String, cols: u32, rows: u32) -> Result<(), String
         "[SSH] resize_ssh called: session_id={}, cols={}, rows={}",
         session_id, cols, rows
     );
-
-    // Note: ssh2 crate doesn't support PTY resize after shell is started
-    // This is a limitation of libssh2. The window size was set at PTY request time.
-    // For full resize support, consider using russh crate instead.
-    
-    Ok(())
+    sessions().resize(
+        &session_id,
+        cols.try_into().unwrap_or(u16::MAX),
+        rows.try_into().unwrap_or(u16::MAX),
+    )
 }
 
 /// Disconnects an SSH session
 #[tauri::command]
 pub fn disconnect_ssh(session_id: String) -> Result<(), String> {
     println!("[SSH] disconnect_ssh called: session_id={}", session_id);
-
-    let sessions_ptr = SSH_SESSIONS.clone();
-    let mut sessions = sessions_ptr.lock();
-
-    if let Some(session) = sessions.remove(&session_id) {
-        // Signal reader thread to stop
-        *session.running.lock() = false;
-
-        // Close the channel gracefully
-        {
-            let mut channel = session.channel.lock();
-            let _ = channel.0.send_eof();
-            let _ = channel.0.wait_close();
-        }
-
-        // Clean up logs
-        cleanup_session_logs(&session_id);
-
-        println!(
-            "[SSH] Session {} disconnected. Remaining: {}",
-            session_id,
-            sessions.len()
-        );
-        Ok(())
-    } else {
-        Err(format!("SSH session not found: {}", session_id))
-    }
+    sessions().close(&session_id)
 }
 
 /// Lists active SSH sessions
 #[tauri::command]
 pub fn list_ssh_sessions() -> Vec<SshSessionInfo> {
-    let sessions_ptr = SSH_SESSIONS.clone();
-    let sessions = sessions_ptr.lock();
-
-    sessions
+    sessions()
+        .all()
         .iter()
-        .map(|(id, session)| SshSessionInfo {
-            session_id: id.clone(),
+        .filter_map(|session| session.as_any().downcast_ref::<SshSession>())
+        .map(|session| SshSessionInfo {
+            session_id: session.id.clone(),
             host: session.host.clone(),
             port: session.port,
             username: session.username.clone(),
@@ -437,3 +895,20 @@ pub struct SshSessionInfo {
     pub port: u16,
     pub username: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_key_type() {
+        let mut blob = vec![0, 0, 0, 11];
+        blob.extend_from_slice(b"ssh-ed25519");
+        blob.extend_from_slice(&[0, 0, 0, 32]);
+        blob.extend_from_slice(&[7; 32]);
+        assert_eq!(blob_key_type(&blob).as_deref(), Some("ssh-ed25519"));
+
+        assert_eq!(blob_key_type(&[0, 0, 0, 40, b's']), None);
+        assert_eq!(blob_key_type(&[0, 0]), None);
+    }
+}
@@ -0,0 +1,528 @@
+//! OpenSSH Client Config Module for Packet
+//!
+//! Reads `~/.ssh/config` so hosts can be reached by their alias with the
+//! settings the team already keeps there.
+//!
+//! # Supported Directives
+//!
+//! - `Host` patterns with `*`, `?` and `!` negation (`Match all` is treated
+//!   as `Host *`; other `Match` blocks never apply)
+//! - `HostName` (with `%h`), `User`, `Port`
+//! - `IdentityFile` (with `~`, `%d`, `%h`, `%r`, `%u`) and `IdentitiesOnly`
+//! - `ProxyJump`, `ServerAliveInterval`
+//! - `Include`, with globs, relative to `~/.ssh`; inside a `Host` block the
+//!   included blocks only apply to hosts that block matches
+//!
+//! As in OpenSSH the first value found for a setting wins, so specific
+//! `Host` blocks go before general ones, and `IdentityFile` accumulates.
+//! Other directives are ignored.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Include nesting limit, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A `Host` (or `Match`) block and the directives under it
+struct HostBlock {
+    /// Patterns from the `Host` line; empty for blocks that never match
+    patterns: Vec<String>,
+    /// Patterns of the blocks around the `Include` this block came from;
+    /// each must match as well
+    conditions: Vec<Vec<String>>,
+    /// Lowercased keyword and its arguments, in file order
+    options: Vec<(String, Vec<String>)>,
+}
+
+impl HostBlock {
+    /// Whether the block's settings are used for `host`
+    fn applies_to(&self, host: &str) -> bool {
+        host_matches(&self.patterns, host)
+            && self
+                .conditions
+                .iter()
+                .all(|patterns| host_matches(patterns, host))
+    }
+}
+
+/// A parsed client config
+pub struct SshConfig {
+    blocks: Vec<HostBlock>,
+}
+
+/// Effective settings for one host alias
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SshHostConfig {
+    /// The name that was looked up
+    pub alias: String,
+    /// Address to connect to (`HostName`, or the alias itself)
+    pub hostname: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Expanded key paths, in the order OpenSSH tries them
+    #[serde(rename = "identityFiles")]
+    pub identity_files: Vec<String>,
+    /// Only use the keys in `identity_files`, even if the agent has others
+    #[serde(rename = "identitiesOnly")]
+    pub identities_only: bool,
+    /// Comma-separated jump hosts (`ProxyJump none` leaves it unset)
+    #[serde(rename = "proxyJump")]
+    pub proxy_jump: Option<String>,
+    /// Seconds between keepalives (`0` leaves it unset)
+    #[serde(rename = "serverAliveInterval")]
+    pub server_alive_interval: Option<u32>,
+}
+
+impl SshConfig {
+    /// Reads `~/.ssh/config`; a missing file gives an empty config
+    pub fn load() -> Self {
+        let ssh_dir = ssh_dir();
+        let content = fs::read_to_string(ssh_dir.join("config")).unwrap_or_default();
+        Self::parse(&content, &ssh_dir)
+    }
+
+    /// Parses config text; `Include` paths are resolved against `ssh_dir`
+    pub fn parse(content: &str, ssh_dir: &Path) -> Self {
+        // Directives before the first Host line apply to every host
+        let mut config = SshConfig {
+            blocks: vec![HostBlock {
+                patterns: vec!["*".to_string()],
+                conditions: Vec::new(),
+                options: Vec::new(),
+            }],
+        };
+        config.read(content, ssh_dir, 0, &[]);
+        config
+    }
+
+    fn read(&mut self, content: &str, ssh_dir: &Path, depth: usize, conditions: &[Vec<String>]) {
+        for line in content.lines() {
+            let Some((keyword, args)) = split_line(line) else {
+                continue;
+            };
+
+            match keyword.as_str() {
+                "host" => self.blocks.push(HostBlock {
+                    patterns: args,
+                    conditions: conditions.to_vec(),
+                    options: Vec::new(),
+                }),
+                "match" => self.blocks.push(HostBlock {
+                    patterns: if args.len() == 1 && args[0].eq_ignore_ascii_case("all") {
+                        vec!["*".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    conditions: conditions.to_vec(),
+                    options: Vec::new(),
+                }),
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        eprintln!("[SSH] Include nested too deeply, skipping {:?}", args);
+                        continue;
+                    }
+                    let enclosing = self.blocks.len() - 1;
+                    // As in OpenSSH, what the file adds only counts where the
+                    // block around the Include applies
+                    let mut inner = self.blocks[enclosing].conditions.clone();
+                    inner.push(self.blocks[enclosing].patterns.clone());
+                    for arg in &args {
+                        for path in expand_include(arg, ssh_dir) {
+                            if let Ok(included) = fs::read_to_string(&path) {
+                                self.read(&included, ssh_dir, depth + 1, &inner);
+                            }
+                        }
+                    }
+                    // Lines after the Include belong to the block around it again
+                    if self.blocks.len() - 1 != enclosing {
+                        let block = &self.blocks[enclosing];
+                        self.blocks.push(HostBlock {
+                            patterns: block.patterns.clone(),
+                            conditions: block.conditions.clone(),
+                            options: Vec::new(),
+                        });
+                    }
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.options.push((keyword, args));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves the settings for `alias`
+    pub fn resolve(&self, alias: &str) -> SshHostConfig {
+        let mut hostname = None;
+        let mut user = None;
+        let mut port = None;
+        let mut identity_files = Vec::new();
+        let mut identities_only = None;
+        let mut proxy_jump = None;
+        let mut server_alive_interval = None;
+
+        let matching = self.blocks.iter().filter(|block| block.applies_to(alias));
+        for block in matching {
+            for (keyword, args) in &block.options {
+                let Some(value) = args.first() else {
+                    continue;
+                };
+                match keyword.as_str() {
+                    "hostname" => {
+                        hostname.get_or_insert_with(|| value.clone());
+                    }
+                    "user" => {
+                        user.get_or_insert_with(|| value.clone());
+                    }
+                    "port" => {
+                        port.get_or_insert_with(|| value.parse::<u16>().ok());
+                    }
+                    "identityfile" => identity_files.push(value.clone()),
+                    "identitiesonly" => {
+                        identities_only.get_or_insert_with(|| value.eq_ignore_ascii_case("yes"));
+                    }
+                    "proxyjump" => {
+                        proxy_jump.get_or_insert_with(|| {
+                            Some(value.clone()).filter(|jump| !jump.eq_ignore_ascii_case("none"))
+                        });
+                    }
+                    "serveraliveinterval" => {
+                        server_alive_interval.get_or_insert_with(|| {
+                            value.parse::<u32>().ok().filter(|secs| *secs > 0)
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let hostname = hostname
+            .map(|name| expand_tokens(&name, alias, alias, None))
+            .unwrap_or_else(|| alias.to_string());
+        let identity_files = identity_files
+            .iter()
+            .map(|path| expand_tokens(path, alias, &hostname, user.as_deref()))
+            .collect();
+
+        SshHostConfig {
+            alias: alias.to_string(),
+            hostname,
+            user,
+            port: port.flatten(),
+            identity_files,
+            identities_only: identities_only.unwrap_or(false),
+            proxy_jump: proxy_jump.flatten(),
+            server_alive_interval: server_alive_interval.flatten(),
+        }
+    }
+
+    /// Host aliases without wildcards, for completion in the connect dialog
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = self
+            .blocks
+            .iter()
+            .flat_map(|block| {
+                block
+                    .patterns
+                    .iter()
+                    .filter(|pattern| !pattern.contains(['*', '?', '!']))
+                    .filter(|alias| block.applies_to(alias))
+            })
+            .cloned()
+            .collect();
+        aliases.sort();
+        aliases.dedup();
+        aliases
+    }
+}
+
+/// `~/.ssh`
+fn ssh_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".ssh")
+}
+
+/// Splits a config line into its lowercased keyword and arguments.
+/// The keyword may be followed by `=`; arguments may be double-quoted.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let keyword = line[..end].to_ascii_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    Some((keyword, args))
+}
+
+/// Whether a `Host` line's patterns select `host`: at least one pattern
+/// must match and no negated one may
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.to_ascii_lowercase();
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, &host) {
+                return false;
+            }
+        } else if wildcard_match(&pattern, &host) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Glob match supporting `*` (any run of characters) and `?` (one character)
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Last `*` seen and the text position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Expands `~` and the `%` tokens OpenSSH allows in HostName and IdentityFile
+fn expand_tokens(value: &str, alias: &str, hostname: &str, user: Option<&str>) -> String {
+    let home = dirs::home_dir().unwrap_or_default();
+    let home = home.to_string_lossy();
+    let local_user = std::env::var("USER").unwrap_or_default();
+
+    let value = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", home, rest),
+        _ => value.to_string(),
+    };
+
+    let mut expanded = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('d') => expanded.push_str(&home),
+            Some('h') => expanded.push_str(hostname),
+            Some('n') => expanded.push_str(alias),
+            Some('r') => expanded.push_str(user.unwrap_or(&local_user)),
+            Some('u') => expanded.push_str(&local_user),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+/// Files named by an `Include` argument, sorted; the file name may be a glob
+fn expand_include(arg: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = PathBuf::from(expand_tokens(arg, "", "", None));
+    let path = if path.is_absolute() {
+        path
+    } else {
+        ssh_dir.join(path)
+    };
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| wildcard_match(&name, &entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Returns the effective `~/.ssh/config` settings for a host alias
+#[tauri::command]
+pub fn resolve_ssh_config(host: String) -> SshHostConfig {
+    SshConfig::load().resolve(&host)
+}
+
+/// Lists the host aliases defined in `~/.ssh/config`
+#[tauri::command]
+pub fn list_ssh_config_hosts() -> Vec<String> {
+    SshConfig::load().aliases()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Shared lab config
+ServerAliveInterval 30
+
+Host core-r? !core-r9
+    HostName %h.lab.example.net
+    User netops
+    Port 2222
+    IdentityFile ~/.ssh/lab_ed25519
+    IdentitiesOnly yes
+
+Host core-r9
+    HostName=10.0.0.9
+    ProxyJump bastion
+
+Host bastion
+    HostName bastion.example.net
+    ProxyJump none
+
+Host *
+    User admin
+    Port 22
+    IdentityFile "~/.ssh/id %r"
+    ServerAliveInterval 60
+"#;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("r-*", "r-core-1"));
+        assert!(wildcard_match("r-?", "r-1"));
+        assert!(!wildcard_match("r-?", "r-10"));
+        assert!(wildcard_match("*.lab", "sw1.lab"));
+        assert!(wildcard_match("*a*b", "xxaxxb"));
+        assert!(!wildcard_match("*a*b", "xxaxxbc"));
+        assert!(wildcard_match("*", ""));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().into_owned();
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+
+        let r1 = config.resolve("core-r1");
+        assert_eq!(r1.hostname, "core-r1.lab.example.net");
+        assert_eq!(r1.user.as_deref(), Some("netops"));
+        assert_eq!(r1.port, Some(2222));
+        assert_eq!(
+            r1.identity_files,
+            vec![
+                format!("{}/.ssh/lab_ed25519", home),
+                format!("{}/.ssh/id netops", home)
+            ]
+        );
+        assert!(r1.identities_only);
+        assert_eq!(r1.proxy_jump, None);
+        // The global default comes first, as in OpenSSH
+        assert_eq!(r1.server_alive_interval, Some(30));
+
+        // Excluded from the core-r? block by the negated pattern
+        let r9 = config.resolve("CORE-R9");
+        assert_eq!(r9.hostname, "10.0.0.9");
+        assert_eq!(r9.user.as_deref(), Some("admin"));
+        assert_eq!(r9.port, Some(22));
+        assert_eq!(r9.proxy_jump.as_deref(), Some("bastion"));
+        assert!(!r9.identities_only);
+
+        let bastion = config.resolve("bastion");
+        assert_eq!(bastion.proxy_jump, None);
+
+        let unknown = config.resolve("10.1.1.1");
+        assert_eq!(unknown.hostname, "10.1.1.1");
+        assert_eq!(unknown.user.as_deref(), Some("admin"));
+
+        assert_eq!(config.aliases(), vec!["bastion", "core-r9"]);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = std::env::temp_dir().join(format!("packet-ssh-config-{}", std::process::id()));
+        fs::create_dir_all(dir.join("config.d")).unwrap();
+        fs::write(
+            dir.join("config.d/10-lab"),
+            "Host lab-*\n    User labuser\n",
+        )
+        .unwrap();
+        fs::write(dir.join("config.d/20-edge"), "Host edge\n    Port 830\n").unwrap();
+        fs::write(dir.join("common"), "Host edge\n    HostName 192.0.2.1\n").unwrap();
+
+        let config = SshConfig::parse(
+            "Host lab-sw1\n    Include config.d/*\n    HostName 192.0.2.10\n\
+             Host *\n    Include common\n    User root\n",
+            &dir,
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        let sw1 = config.resolve("lab-sw1");
+        assert_eq!(sw1.user.as_deref(), Some("labuser"));
+        // Still inside the Host lab-sw1 block after the Include
+        assert_eq!(sw1.hostname, "192.0.2.10");
+        // Included under Host lab-sw1, so only lab-sw1 gets the lab-* settings
+        let sw2 = config.resolve("lab-sw2");
+        assert_eq!(sw2.hostname, "lab-sw2");
+        assert_eq!(sw2.user.as_deref(), Some("root"));
+        // Under Host *, the included block applies as if written inline
+        let edge = config.resolve("edge");
+        assert_eq!(edge.port, None);
+        assert_eq!(edge.hostname, "192.0.2.1");
+        assert_eq!(edge.user.as_deref(), Some("root"));
+        assert_eq!(config.aliases(), vec!["edge", "lab-sw1"]);
+    }
+}
@@ -8,20 +8,68 @@
 //!
 //! Each telnet session maintains:
 //! - A TCP connection to the GNS3 device
-//! - A reader thread that emits output events to the frontend
-//! - A writer for sending commands to the device
+//! - A reader task that emits output events to the frontend
+//! - A writer task fed by a queue of commands for the device
+//!
+//! Both tasks run on the shared tokio runtime, so an idle console costs no
+//! OS thread and output is delivered as soon as it arrives.
+//!
+//! # Option Negotiation
+//!
+//! [`TelnetProtocol`] is the RFC 854 state machine. It keeps its parser state
+//! between reads, so commands split across reads are not mangled, and answers
+//! option requests using the RFC 1143 Q-method, which avoids negotiation
+//! loops. ECHO, SGA (suppress go-ahead), TTYPE (terminal type), NAWS
+//! (window size) and BINARY are supported; everything else is refused.
+//!
+//! NAWS is offered when the connection opens, so the server learns the
+//! pane's size up front, and every resize afterwards is reported to it.
+//!
+//! Input is encoded before it is sent: IAC bytes are doubled and, unless we
+//! are in BINARY mode, a bare CR becomes CR NUL or CR LF as configured for
+//! the session (RFC 854).
+//!
+//! # Serial Port Control
+//!
+//! Console servers such as ser2net, Opengear and Moxa accept RFC 2217
+//! COM-PORT-OPTION. Once the server agrees to it, a session can set the
+//! remote port's baud rate, framing and flow control and send a serial
+//! break. Modem line changes reported by the server are emitted as
+//! `telnet-com-port` events.
+//!
+//! # TLS
+//!
+//! Console servers that only expose telnets (RFC 4248 reserves port 992,
+//! but any port works) are reached by wrapping the socket in TLS before the
+//! telnet protocol starts. Certificates are verified against the system
+//! roots plus an optional CA file; sessions can opt out to accept
+//! self-signed certificates.
+//!
+//! # Jump Hosts
+//!
+//! Devices only reachable from a bastion are dialled through a chain of SSH
+//! jump hosts (see the `jump` module); the telnet protocol then runs over
+//! the tunnel's local socket, wrapped in TLS for telnets as over TCP.
 
-use crate::logging::{cleanup_session_logs, write_to_logs};
-use once_cell::sync::Lazy;
+use crate::daemon::{remote_command, SessionCommand};
+use crate::jump::{self, JumpHost};
+use crate::output::{spawn_output_pump, OutputSender};
+use crate::pty::expand_home;
+use crate::session::{record_final_output, record_output, sessions, EventSink, Session, SessionInfo, SessionKind, SessionStatus};
+use crate::utf8::Utf8Decoder;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::any::Any;
 use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
 use std::sync::Arc;
-use std::thread;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+use tokio_native_tls::TlsStream;
 use uuid::Uuid;
 
 /// Buffer size for reading telnet output (4KB)
@@ -30,22 +78,221 @@ const TELNET_READ_BUFFER_SIZE: usize = 4096;
 /// Connection timeout in seconds
 const CONNECTION_TIMEOUT_SECS: u64 = 10;
 
+/// Terminal type reported to the server, matching the local PTY's TERM
+const TERMINAL_TYPE: &str = "XTERM-256COLOR";
+
+/// Window size reported until the terminal's real size is known
+const DEFAULT_WINDOW_SIZE: (u16, u16) = (80, 24);
+
+/// Subnegotiations longer than this are truncated (nothing we handle comes close)
+const MAX_SUBNEGOTIATION_LEN: usize = 1024;
+
+// Telnet commands (RFC 854)
+const SE: u8 = 240;
+const BRK: u8 = 243;
+const IP: u8 = 244;
+const AO: u8 = 245;
+const AYT: u8 = 246;
+const SB: u8 = 250;
+const WILL: u8 = 251;
+const WONT: u8 = 252;
+const DO: u8 = 253;
+const DONT: u8 = 254;
+const IAC: u8 = 255;
+
+// Telnet options
+const OPT_BINARY: u8 = 0;
+const OPT_ECHO: u8 = 1;
+const OPT_SGA: u8 = 3;
+const OPT_TTYPE: u8 = 24;
+const OPT_NAWS: u8 = 31;
+
+// TTYPE subnegotiation commands (RFC 1091)
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+
+// COM-PORT-OPTION (RFC 2217)
+const OPT_COM_PORT: u8 = 44;
+const CPO_SET_BAUDRATE: u8 = 1;
+const CPO_SET_DATASIZE: u8 = 2;
+const CPO_SET_PARITY: u8 = 3;
+const CPO_SET_STOPSIZE: u8 = 4;
+const CPO_SET_CONTROL: u8 = 5;
+const CPO_NOTIFY_LINESTATE: u8 = 6;
+const CPO_NOTIFY_MODEMSTATE: u8 = 7;
+const CPO_SET_MODEMSTATE_MASK: u8 = 11;
+/// Added to a command code in the server's replies
+const CPO_SERVER_OFFSET: u8 = 100;
+
+// SET-CONTROL values
+const CONTROL_REQUEST_FLOW: u8 = 0;
+const CONTROL_FLOW_NONE: u8 = 1;
+const CONTROL_FLOW_XONXOFF: u8 = 2;
+const CONTROL_FLOW_HARDWARE: u8 = 3;
+const CONTROL_BREAK_ON: u8 = 5;
+const CONTROL_BREAK_OFF: u8 = 6;
+
+/// How long a serial break is held when no duration is given
+const DEFAULT_BREAK_MS: u64 = 500;
+
 /// Represents an active telnet session
 pub struct TelnetSession {
-    /// The TCP stream writer (wrapped for thread-safe access)
-    pub writer: Arc<Mutex<TcpStream>>,
-    /// Connection info for display
+    /// Session identifier
+    pub id: String,
+    /// Queue of outgoing bytes, drained by the session's writer task
+    pub writer: UnboundedSender<Vec<u8>>,
+    /// Connection info for display; for Unix sockets `host` is the socket
+    /// path and `port` is 0
     pub host: String,
     pub port: u16,
-    /// Flag to signal reader thread to stop
+    /// Whether the connection is wrapped in TLS
+    pub tls: bool,
+    /// Whether the connection is still open
     pub running: Arc<Mutex<bool>>,
+    /// Wakes the reader task when the session is closed locally
+    pub shutdown: Arc<Notify>,
+    /// Protocol state shared with the reader task
+    pub protocol: Arc<Mutex<TelnetProtocol>>,
+}
+
+/// Telnet commands the user can send to the remote end
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TelnetCommand {
+    /// Break (e.g. to enter ROMMON during a Cisco boot)
+    Brk,
+    /// Interrupt Process
+    Ip,
+    /// Abort Output
+    Ao,
+    /// Are You There
+    Ayt,
 }
 
-/// Global static state for all telnet sessions
-static TELNET_SESSIONS: Lazy<Arc<Mutex<HashMap<String, TelnetSession>>>> = Lazy::new(|| {
-    println!("[Telnet] Initializing global telnet session store");
-    Arc::new(Mutex::new(HashMap::new()))
-});
+impl TelnetCommand {
+    fn code(self) -> u8 {
+        match self {
+            TelnetCommand::Brk => BRK,
+            TelnetCommand::Ip => IP,
+            TelnetCommand::Ao => AO,
+            TelnetCommand::Ayt => AYT,
+        }
+    }
+}
+
+impl TelnetSession {
+    /// Sends IAC followed by `command`
+    pub fn send_command(&self, command: TelnetCommand) -> Result<(), String> {
+        self.writer
+            .send(vec![IAC, command.code()])
+            .map_err(|_| "Failed to send command: connection closed".to_string())
+    }
+
+    /// Updates the protocol state and sends whatever it queued
+    fn with_protocol<T>(
+        &self,
+        update: impl FnOnce(&mut TelnetProtocol) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let (result, outgoing) = {
+            let mut protocol = self.protocol.lock();
+            let result = update(&mut protocol)?;
+            (result, protocol.take_outgoing())
+        };
+        if !outgoing.is_empty() {
+            self.writer
+                .send(outgoing)
+                .map_err(|_| "Connection closed".to_string())?;
+        }
+        Ok(result)
+    }
+}
+
+/// How a bare CR typed by the user is sent outside BINARY mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelnetNewline {
+    /// CR NUL, the RFC 854 encoding of a carriage return
+    #[default]
+    CrNul,
+    /// CR LF, the NVT end of line (some servers expect this for Enter)
+    CrLf,
+}
+
+/// Per-session telnet settings chosen when connecting
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelnetOptions {
+    pub newline: TelnetNewline,
+    /// Offer RFC 2217 serial port control when connecting
+    #[serde(rename = "comPort")]
+    pub com_port: bool,
+    /// Wrap the connection in TLS (telnets)
+    pub tls: Option<TelnetTls>,
+}
+
+/// Certificate checks for a telnets connection
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelnetTls {
+    /// Accept self-signed, expired or mismatched certificates
+    pub insecure: bool,
+    /// PEM file with an extra CA certificate to trust
+    #[serde(rename = "caFile")]
+    pub ca_file: Option<String>,
+    /// Name to verify the certificate against, when it differs from the host
+    #[serde(rename = "serverName")]
+    pub server_name: Option<String>,
+}
+
+impl Session for TelnetSession {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        let encoded = self.protocol.lock().encode(data);
+        self.writer
+            .send(encoded)
+            .map_err(|_| "Failed to write: connection closed".to_string())
+    }
+
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        // Reported through NAWS once the server has agreed to it
+        self.with_protocol(|protocol| {
+            protocol.set_window_size(cols, rows);
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to resize: {}", e))
+    }
+
+    fn close(&self) -> Result<(), String> {
+        // Signal the reader task to stop; the socket closes once both tasks exit
+        *self.running.lock() = false;
+        self.shutdown.notify_one();
+        Ok(())
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            kind: SessionKind::Telnet,
+            label: if self.port == 0 {
+                self.host.clone()
+            } else {
+                format!("{}:{}", self.host, self.port)
+            },
+            status: self.status(),
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        if *self.running.lock() {
+            SessionStatus::Connected
+        } else {
+            SessionStatus::Disconnected
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
 /// Event payload emitted when telnet output is available
 #[derive(Clone, Serialize, Deserialize)]
@@ -55,6 +302,27 @@ pub struct TelnetOutput {
     pub data: String,
 }
 
+/// Negotiated state of one telnet option
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TelnetOptionStatus {
+    pub option: String,
+    /// Enabled on our side (we sent or accepted WILL)
+    pub local: bool,
+    /// Enabled on the server's side (it sent or accepted WILL)
+    pub remote: bool,
+}
+
+/// Telnet session details returned by `list_telnet_sessions`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TelnetSessionInfo {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub options: Vec<TelnetOptionStatus>,
+}
+
 /// Event payload for connection status updates
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ConnectionStatus {
@@ -63,16 +331,81 @@ pub struct ConnectionStatus {
     pub status: String,
     pub message: String,
 }
+
 /// Establishes a new telnet connection to a GNS3 device
+///
+/// `cols` and `rows` are the terminal's initial size, reported through NAWS.
+/// `jump_hosts` are SSH bastions to tunnel through, in order.
+/// Output is delivered on `on_output` when given, otherwise as `telnet-output` events.
+///
+/// Runs on the blocking pool, since logging into jump hosts may wait for the
+/// user to answer host key or login prompts.
 #[tauri::command]
-pub fn connect_telnet(
+#[allow(clippy::too_many_arguments)]
+pub async fn connect_telnet(
     app: AppHandle,
     host: String,
     port: u16,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    options: Option<TelnetOptions>,
+    jump_hosts: Option<Vec<JumpHost>>,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    let window_size = cols.zip(rows);
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        open_telnet(
+            EventSink::App(app),
+            host,
+            port,
+            window_size,
+            options,
+            jump_hosts.unwrap_or_default(),
+            on_output,
+        )
+    })
+    .await
+    .map_err(|e| format!("Telnet connection task failed: {}", e))?
+}
+
+/// Opens a telnet connection whose events are delivered to `sink`.
+pub fn open_telnet(
+    sink: EventSink,
+    host: String,
+    port: u16,
+    window_size: Option<(u16, u16)>,
+    options: TelnetOptions,
+    jump_hosts: Vec<JumpHost>,
+    on_output: Option<Channel<String>>,
 ) -> Result<String, String> {
     println!("[Telnet] Connecting to {}:{}", host, port);
 
-    let sessions_ptr = TELNET_SESSIONS.clone();
+    if !jump_hosts.is_empty() {
+        // Identifies the jump hosts' prompts; the session ID comes later
+        let request_id = Uuid::new_v4().to_string();
+        let stream = jump::tunnel(&sink, &request_id, &jump_hosts, &host, port)?;
+        stream
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+        let stream = match &options.tls {
+            Some(tls) => TelnetStream::TunnelTls(start_tls(
+                || tokio::net::UnixStream::from_std(stream),
+                &host,
+                tls,
+            )?),
+            None => TelnetStream::Tunnel(stream),
+        };
+        return Ok(start_telnet(
+            sink,
+            stream,
+            host,
+            port,
+            window_size,
+            options,
+            on_output,
+        ));
+    }
 
     // Resolve hostname to socket address
     let addr_str = format!("{}:{}", host, port);
@@ -91,136 +424,373 @@ pub fn connect_telnet(
     )
     .map_err(|e| format!("Connection failed: {}", e))?;
 
-    // Configure the stream
-    stream
-        .set_read_timeout(Some(Duration::from_millis(100)))
-        .map_err(|e| format!("Failed to set read timeout: {}", e))?;
+    // Configure the stream for the async runtime
     stream
         .set_nodelay(true)
         .map_err(|e| format!("Failed to set nodelay: {}", e))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
 
-    // Clone stream for reader thread
-    let reader_stream = stream
-        .try_clone()
-        .map_err(|e| format!("Failed to clone stream: {}", e))?;
+    let stream = match &options.tls {
+        Some(tls) => TelnetStream::Tls(start_tls(
+            || tokio::net::TcpStream::from_std(stream),
+            &host,
+            tls,
+        )?),
+        None => TelnetStream::Tcp(stream),
+    };
+    Ok(start_telnet(
+        sink,
+        stream,
+        host,
+        port,
+        window_size,
+        options,
+        on_output,
+    ))
+}
+
+/// Opens a telnet session on a Unix domain socket (e.g. a QEMU serial
+/// console started with `-serial unix:...,server,telnet`).
+pub fn open_telnet_unix(
+    sink: EventSink,
+    path: String,
+    window_size: Option<(u16, u16)>,
+    options: TelnetOptions,
+    on_output: Option<Channel<String>>,
+) -> Result<String, String> {
+    println!("[Telnet] Connecting to Unix socket {}", path);
+
+    if options.tls.is_some() {
+        return Err("TLS is not supported on Unix sockets".to_string());
+    }
+    let stream =
+        UnixStream::connect(expand_home(&path)).map_err(|e| format!("Connection failed: {}", e))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+    Ok(start_telnet(
+        sink,
+        TelnetStream::Unix(stream),
+        path,
+        0,
+        window_size,
+        options,
+        on_output,
+    ))
+}
+
+/// Registers a session on a connected stream and starts its I/O tasks.
+///
+/// `port` is 0 for Unix sockets, where `host` holds the socket path.
+fn start_telnet(
+    sink: EventSink,
+    stream: TelnetStream,
+    host: String,
+    port: u16,
+    window_size: Option<(u16, u16)>,
+    options: TelnetOptions,
+    on_output: Option<Channel<String>>,
+) -> String {
+    let tls = matches!(stream, TelnetStream::Tls(_) | TelnetStream::TunnelTls(_));
+    let target = match stream {
+        TelnetStream::Tcp(_) => format!("telnet://{}:{}", host, port),
+        TelnetStream::Tls(_) => format!("telnets://{}:{}", host, port),
+        TelnetStream::Unix(_) => host.clone(),
+        TelnetStream::Tunnel(_) => format!("telnet://{}:{} (via jump host)", host, port),
+        TelnetStream::TunnelTls(_) => format!("telnets://{}:{} (via jump host)", host, port),
+    };
 
     // Generate session ID
     let session_id = Uuid::new_v4().to_string();
     println!("[Telnet] Generated session ID: {}", session_id);
 
     let running = Arc::new(Mutex::new(true));
-    let running_clone = running.clone();
-
-    // Store session
-    {
-        let mut sessions = sessions_ptr.lock();
-        sessions.insert(
-            session_id.clone(),
-            TelnetSession {
-                writer: Arc::new(Mutex::new(stream)),
-                host: host.clone(),
-                port,
-                running,
-            },
-        );
-        println!(
-            "[Telnet] Session {} stored. Total sessions: {}",
-            session_id,
-            sessions.len()
-        );
+    let shutdown = Arc::new(Notify::new());
+    let (writer, outgoing) = mpsc::unbounded_channel();
+
+    // Offer NAWS right away; the size is sent once the server agrees
+    let mut protocol = TelnetProtocol::new();
+    protocol.set_newline(options.newline);
+    if let Some((cols, rows)) = window_size {
+        protocol.set_window_size(cols, rows);
     }
+    protocol.request_local(OPT_NAWS);
+    if options.com_port {
+        protocol.request_local(OPT_COM_PORT);
+    }
+    let _ = writer.send(protocol.take_outgoing());
+    let protocol = Arc::new(Mutex::new(protocol));
+
+    // Register session with the global session manager
+    sessions().insert(
+        &session_id,
+        Arc::new(TelnetSession {
+            id: session_id.clone(),
+            writer: writer.clone(),
+            host: host.clone(),
+            port,
+            tls,
+            running: running.clone(),
+            shutdown: shutdown.clone(),
+            protocol: protocol.clone(),
+        }),
+    );
 
     // Emit connection success
-    let _ = app.emit(
+    sink.emit(
         "telnet-status",
+        &session_id,
         ConnectionStatus {
             session_id: session_id.clone(),
             status: "connected".to_string(),
-            message: format!("Connected to {}:{}", host, port),
+            message: format!("Connected to {}", target),
         },
     );
 
-    // Spawn reader thread
-    let session_id_clone = session_id.clone();
-    let sessions_for_cleanup = sessions_ptr.clone();
+    let output = spawn_output_pump(
+        sink.clone(),
+        &session_id,
+        on_output,
+        "telnet-output",
+        |session_id, data| TelnetOutput {
+            session_id: session_id.to_string(),
+            data,
+        },
+    );
+    tauri::async_runtime::spawn(run_telnet(
+        sink,
+        session_id.clone(),
+        TelnetIo {
+            stream,
+            outgoing,
+            writer,
+            protocol,
+        },
+        output,
+        running,
+        shutdown,
+    ));
+
+    println!("[Telnet] connect_telnet returning id: {}", session_id);
+    session_id
+}
+
+/// Performs the TLS handshake on a connected socket, which `register`
+/// hands to the async runtime
+fn start_tls<S>(
+    register: impl FnOnce() -> std::io::Result<S>,
+    host: &str,
+    tls: &TelnetTls,
+) -> Result<TlsStream<S>, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut builder = native_tls::TlsConnector::builder();
+    if tls.insecure {
+        builder
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true);
+    }
+    if let Some(path) = tls.ca_file.as_deref().filter(|path| !path.is_empty()) {
+        let pem = std::fs::read(expand_home(path))
+            .map_err(|e| format!("Failed to read CA file '{}': {}", path, e))?;
+        let certificate = native_tls::Certificate::from_pem(&pem)
+            .map_err(|e| format!("Invalid CA certificate '{}': {}", path, e))?;
+        builder.add_root_certificate(certificate);
+    }
+    let connector = builder
+        .build()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+    let connector = tokio_native_tls::TlsConnector::from(connector);
+
+    let domain = tls
+        .server_name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(host);
+    println!("[Telnet] Starting TLS handshake with {}", domain);
 
-    thread::spawn(move || {
-        println!("[Telnet] Reader thread started for {}", session_id_clone);
-        let mut reader = reader_stream;
-        let mut buf = [0u8; TELNET_READ_BUFFER_SIZE];
+    tauri::async_runtime::block_on(async {
+        let stream = register().map_err(|e| format!("Failed to register stream: {}", e))?;
+        let handshake = connector.connect(domain, stream);
+        tokio::time::timeout(Duration::from_secs(CONNECTION_TIMEOUT_SECS), handshake)
+            .await
+            .map_err(|_| "TLS handshake timed out".to_string())?
+            .map_err(|e| format!("TLS handshake failed: {}", e))
+    })
+}
+
+/// A connected socket: TCP, TCP wrapped in TLS, or a Unix domain socket
+enum TelnetStream {
+    Tcp(TcpStream),
+    Tls(TlsStream<tokio::net::TcpStream>),
+    Unix(UnixStream),
+    /// Local end of a tunnel through SSH jump hosts
+    Tunnel(UnixStream),
+    /// A tunnel through jump hosts wrapped in TLS
+    TunnelTls(TlsStream<tokio::net::UnixStream>),
+}
 
-        loop {
-            // Check if we should stop
-            if !*running_clone.lock() {
-                println!("[Telnet] Reader thread stopping for {}", session_id_clone);
+type TelnetReader = Box<dyn AsyncRead + Send + Unpin>;
+type TelnetWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+impl TelnetStream {
+    /// Splits the socket into halves for the reader and writer tasks
+    fn split(self) -> std::io::Result<(TelnetReader, TelnetWriter)> {
+        match self {
+            TelnetStream::Tcp(stream) => {
+                let (reader, writer) = tokio::net::TcpStream::from_std(stream)?.into_split();
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            TelnetStream::Tls(stream) => {
+                let (reader, writer) = tokio::io::split(stream);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            TelnetStream::TunnelTls(stream) => {
+                let (reader, writer) = tokio::io::split(stream);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            TelnetStream::Unix(stream) | TelnetStream::Tunnel(stream) => {
+                let (reader, writer) = tokio::net::UnixStream::from_std(stream)?.into_split();
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+        }
+    }
+}
+
+/// The socket and protocol state owned by a session's I/O tasks
+struct TelnetIo {
+    stream: TelnetStream,
+    /// Bytes queued for the server, drained by the writer task
+    outgoing: UnboundedReceiver<Vec<u8>>,
+    /// Queue for negotiation replies from the reader task
+    writer: UnboundedSender<Vec<u8>>,
+    protocol: Arc<Mutex<TelnetProtocol>>,
+}
+
+/// Drives a telnet connection until either side closes it
+async fn run_telnet(
+    sink: EventSink,
+    session_id: String,
+    io: TelnetIo,
+    output: OutputSender,
+    running: Arc<Mutex<bool>>,
+    shutdown: Arc<Notify>,
+) {
+    println!("[Telnet] Reader task started for {}", session_id);
+    let TelnetIo {
+        stream,
+        mut outgoing,
+        writer: replies,
+        protocol,
+    } = io;
+
+    let (mut reader, mut writer) = match stream.split() {
+        Ok(halves) => halves,
+        Err(e) => {
+            eprintln!("[Telnet] Failed to register stream for {}: {}", session_id, e);
+            *running.lock() = false;
+            sessions().unregister(&session_id);
+            return;
+        }
+    };
+
+    // Writer task: forwards queued input until the session is dropped
+    let writer_session_id = session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(data) = outgoing.recv().await {
+            if let Err(e) = writer.write_all(&data).await {
+                eprintln!("[Telnet] Write error for {}: {}", writer_session_id, e);
                 break;
             }
+        }
+        let _ = writer.shutdown().await;
+    });
 
-            match reader.read(&mut buf) {
-                Ok(0) => {
-                    println!("[Telnet] Connection closed for {}", session_id_clone);
-                    let _ = app.emit(
-                        "telnet-status",
-                        ConnectionStatus {
-                            session_id: session_id_clone.clone(),
-                            status: "disconnected".to_string(),
-                            message: "Connection closed by remote host".to_string(),
-                        },
-                    );
-                    break;
-                }
-                Ok(n) => {
-                    // Process telnet protocol bytes and extract printable data
-                    let data = process_telnet_data(&buf[..n]);
-                    if !data.is_empty() {
-                        // Write to any active log files for this session
-                        write_to_logs(&session_id_clone, &data);
-                        
-                        let _ = app.emit(
-                            "telnet-output",
-                            TelnetOutput {
-                                session_id: session_id_clone.clone(),
-                                data,
-                            },
-                        );
-                    }
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    // No data available, continue loop
-                    thread::sleep(Duration::from_millis(10));
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
-                    // Read timeout, continue loop
-                    continue;
+    let mut buf = [0u8; TELNET_READ_BUFFER_SIZE];
+    let mut decoder = Utf8Decoder::new();
+
+    loop {
+        let read = tokio::select! {
+            read = reader.read(&mut buf) => read,
+            _ = shutdown.notified() => {
+                println!("[Telnet] Reader task stopping for {}", session_id);
+                break;
+            }
+        };
+
+        match read {
+            Ok(0) => {
+                println!("[Telnet] Connection closed for {}", session_id);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "telnet-status",
+                    &session_id,
+                    ConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "disconnected".to_string(),
+                        message: "Connection closed by remote host".to_string(),
+                    },
+                );
+                break;
+            }
+            Ok(n) => {
+                // Answer negotiations and extract the data bytes
+                let (data, reply, com_port) = {
+                    let mut protocol = protocol.lock();
+                    let data = protocol.receive(&buf[..n]);
+                    (
+                        data,
+                        protocol.take_outgoing(),
+                        protocol.take_com_port_update(),
+                    )
+                };
+                if !reply.is_empty() {
+                    let _ = replies.send(reply);
                 }
-                Err(e) => {
-                    eprintln!("[Telnet] Read error for {}: {}", session_id_clone, e);
-                    let _ = app.emit(
-                        "telnet-status",
-                        ConnectionStatus {
-                            session_id: session_id_clone.clone(),
-                            status: "error".to_string(),
-                            message: format!("Read error: {}", e),
+                if let Some(status) = com_port {
+                    sink.emit(
+                        "telnet-com-port",
+                        &session_id,
+                        ComPortEvent {
+                            session_id: session_id.clone(),
+                            status,
                         },
                     );
-                    break;
+                }
+
+                let data = decoder.decode(&data);
+                if !data.is_empty() {
+                    // Write to log files and scrollback for this session
+                    record_output(&session_id, &data);
+                    output.send(data);
                 }
             }
+            Err(e) => {
+                eprintln!("[Telnet] Read error for {}: {}", session_id, e);
+                record_final_output(&session_id, &mut decoder, &output);
+                output.finish().await;
+                sink.emit(
+                    "telnet-status",
+                    &session_id,
+                    ConnectionStatus {
+                        session_id: session_id.clone(),
+                        status: "error".to_string(),
+                        message: format!("Read error: {}", e),
+                    },
+                );
+                break;
+            }
         }
+    }
 
-        // Clean up session
-        cleanup_session_logs(&session_id_clone);
-        let mut sessions = sessions_for_cleanup.lock();
-        sessions.remove(&session_id_clone);
-        println!(
-            "[Telnet] Session {} cleaned up. Remaining: {}",
-            session_id_clone,
-            sessions.len()
-        );
-    });
-
-    println!("[Telnet] connect_telnet returning id: {}", session_id);
-    Ok(session_id)
+    // Clean up session; dropping it closes the writer queue and the socket
+    *running.lock() = false;
+    sessions().unregister(&session_id);
 }
 
 /// Writes data to a telnet session
@@ -231,110 +801,1033 @@ pub fn write_telnet(session_id: String, data: String) -> Result<(), String> {
         session_id,
         data.len()
     );
+    sessions().write(&session_id, data.as_bytes())
+}
 
-    let sessions_ptr = TELNET_SESSIONS.clone();
-    let sessions = sessions_ptr.lock();
-
-    if let Some(session) = sessions.get(&session_id) {
-        let mut writer = session.writer.lock();
-        writer
-            .write_all(data.as_bytes())
-            .map_err(|e| format!("Failed to write: {}", e))?;
-        writer
-            .flush()
-            .map_err(|e| format!("Failed to flush: {}", e))?;
-        println!("[Telnet] Write successful to {}", session_id);
-        Ok(())
-    } else {
-        let err = format!("Telnet session not found: {}", session_id);
-        eprintln!("[Telnet] {}", err);
-        Err(err)
+/// Resizes a telnet session's window (sent to the server via NAWS)
+#[tauri::command]
+pub fn resize_telnet(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    println!(
+        "[Telnet] resize_telnet called: session_id={}, cols={}, rows={}",
+        session_id, cols, rows
+    );
+    sessions().resize(&session_id, cols, rows)
+}
+
+/// Runs `f` on the telnet session `session_id`
+fn with_telnet_session<T>(
+    session_id: &str,
+    f: impl FnOnce(&TelnetSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let session = sessions().get(session_id)?;
+    let telnet = session
+        .as_any()
+        .downcast_ref::<TelnetSession>()
+        .ok_or_else(|| format!("Not a telnet session: {}", session_id))?;
+    f(telnet)
+}
+
+/// Sends a telnet command to the session `session_id`
+pub fn send_command_to(session_id: &str, command: TelnetCommand) -> Result<(), String> {
+    if let Some(result) = remote_command(session_id, || SessionCommand::Telnet {
+        session_id: session_id.to_string(),
+        command,
+    }) {
+        return result;
     }
+    with_telnet_session(session_id, |telnet| telnet.send_command(command))
+}
+
+/// Sends a telnet command (BRK, IP, AO or AYT) to a session
+///
+/// # Arguments
+/// * `session_id` - The telnet session ID
+/// * `command` - The command to send after IAC
+#[tauri::command]
+pub fn send_telnet_command(session_id: String, command: TelnetCommand) -> Result<(), String> {
+    println!(
+        "[Telnet] send_telnet_command called: session_id={}, command={:?}",
+        session_id, command
+    );
+    send_command_to(&session_id, command)
 }
 
 /// Disconnects a telnet session
 #[tauri::command]
 pub fn disconnect_telnet(session_id: String) -> Result<(), String> {
     println!("[Telnet] disconnect_telnet called: session_id={}", session_id);
+    sessions().close(&session_id)
+}
 
-    let sessions_ptr = TELNET_SESSIONS.clone();
-    let mut sessions = sessions_ptr.lock();
-
-    if let Some(session) = sessions.remove(&session_id) {
-        // Signal reader thread to stop
-        *session.running.lock() = false;
-        // Close the connection
-        drop(session.writer);
-        println!(
-            "[Telnet] Session {} disconnected. Remaining: {}",
-            session_id,
-            sessions.len()
-        );
-        Ok(())
-    } else {
-        Err(format!("Telnet session not found: {}", session_id))
-    }
-}
-
-/// Process raw telnet data, handling telnet protocol commands
-/// 
-/// Telnet protocol uses bytes 255 (IAC) followed by command bytes.
-/// We filter these out and return only printable data.
-fn process_telnet_data(raw: &[u8]) -> String {
-    let mut result = Vec::with_capacity(raw.len());
-    let mut i = 0;
-
-    while i < raw.len() {
-        let byte = raw[i];
-
-        // IAC (Interpret As Command) - telnet protocol escape
-        if byte == 255 && i + 1 < raw.len() {
-            let cmd = raw[i + 1];
-            match cmd {
-                // IAC IAC = literal 255
-                255 => {
-                    result.push(255);
-                    i += 2;
-                }
-                // WILL, WONT, DO, DONT - 3 byte sequences
-                251..=254 => {
-                    // Skip the 3-byte telnet negotiation
-                    i += 3;
+/// Get list of active telnet sessions with their negotiated options
+#[tauri::command]
+pub fn list_telnet_sessions() -> Vec<TelnetSessionInfo> {
+    sessions()
+        .all()
+        .iter()
+        .filter_map(|session| session.as_any().downcast_ref::<TelnetSession>())
+        .map(|session| TelnetSessionInfo {
+            session_id: session.id.clone(),
+            host: session.host.clone(),
+            port: session.port,
+            tls: session.tls,
+            options: session.protocol.lock().option_status(),
+        })
+        .collect()
+}
+
+// ============================================================================
+// RFC 2217 COM Port Control
+// ============================================================================
+
+/// Serial parity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    Mark,
+    Space,
+}
+
+/// Serial stop bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopBits {
+    #[serde(rename = "1")]
+    One,
+    #[serde(rename = "1.5")]
+    OnePointFive,
+    #[serde(rename = "2")]
+    Two,
+}
+
+/// Serial flow control
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowControl {
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+/// Serial port settings; fields left as `None` are not changed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComPortSettings {
+    #[serde(rename = "baudRate")]
+    pub baud_rate: Option<u32>,
+    #[serde(rename = "dataBits")]
+    pub data_bits: Option<u8>,
+    pub parity: Option<Parity>,
+    #[serde(rename = "stopBits")]
+    pub stop_bits: Option<StopBits>,
+    #[serde(rename = "flowControl")]
+    pub flow_control: Option<FlowControl>,
+}
+
+/// Modem status lines reported by the server
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModemState {
+    /// Carrier detect
+    pub cd: bool,
+    /// Ring indicator
+    pub ri: bool,
+    /// Data set ready
+    pub dsr: bool,
+    /// Clear to send
+    pub cts: bool,
+}
+
+impl ModemState {
+    fn from_byte(state: u8) -> Self {
+        ModemState {
+            cd: state & 0x80 != 0,
+            ri: state & 0x40 != 0,
+            dsr: state & 0x20 != 0,
+            cts: state & 0x10 != 0,
+        }
+    }
+}
+
+/// Serial port state of a telnet session as last reported by the server
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ComPortStatus {
+    /// Whether the server agreed to COM-PORT-OPTION
+    pub enabled: bool,
+    pub settings: ComPortSettings,
+    pub modem: Option<ModemState>,
+    /// Raw RFC 2217 line state byte (overrun, framing errors, break, ...)
+    #[serde(rename = "lineState")]
+    pub line_state: Option<u8>,
+}
+
+/// Event payload emitted when the server reports serial port changes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ComPortEvent {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    #[serde(flatten)]
+    pub status: ComPortStatus,
+}
+
+/// Changes serial port settings of a telnet session (RFC 2217).
+///
+/// Settings requested before the server has agreed to COM-PORT-OPTION are
+/// sent as soon as it does.
+#[tauri::command]
+pub fn telnet_com_port_configure(
+    session_id: String,
+    settings: ComPortSettings,
+) -> Result<(), String> {
+    println!(
+        "[Telnet] telnet_com_port_configure called: session_id={}, settings={:?}",
+        session_id, settings
+    );
+    validate_com_port_settings(&settings)?;
+    if let Some(result) = remote_command(&session_id, || SessionCommand::ComPortConfigure {
+        session_id: session_id.clone(),
+        settings: settings.clone(),
+    }) {
+        return result;
+    }
+    with_telnet_session(&session_id, |telnet| {
+        telnet.with_protocol(|protocol| {
+            protocol.com_port_configure(settings);
+            Ok(())
+        })
+    })
+}
+
+/// Sends a serial break of `duration_ms` (default 500ms) over RFC 2217
+#[tauri::command]
+pub fn telnet_com_port_break(session_id: String, duration_ms: Option<u64>) -> Result<(), String> {
+    println!(
+        "[Telnet] telnet_com_port_break called: session_id={}, duration_ms={:?}",
+        session_id, duration_ms
+    );
+    if let Some(result) = remote_command(&session_id, || SessionCommand::ComPortBreak {
+        session_id: session_id.clone(),
+        duration_ms,
+    }) {
+        return result;
+    }
+    with_telnet_session(&session_id, |telnet| {
+        telnet.with_protocol(|protocol| protocol.com_port_break(true))
+    })?;
+
+    // Release the break after the duration, unless the session is gone by then
+    let duration = Duration::from_millis(duration_ms.unwrap_or(DEFAULT_BREAK_MS));
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(duration).await;
+        let _ = with_telnet_session(&session_id, |telnet| {
+            telnet.with_protocol(|protocol| protocol.com_port_break(false))
+        });
+    });
+    Ok(())
+}
+
+/// Returns the serial port state of a telnet session as reported by the server
+#[tauri::command]
+pub fn telnet_com_port_status(session_id: String) -> Result<ComPortStatus, String> {
+    if let Some(result) = remote_command(&session_id, || SessionCommand::ComPortStatus {
+        session_id: session_id.clone(),
+    }) {
+        return result;
+    }
+    with_telnet_session(&session_id, |telnet| {
+        Ok(telnet.protocol.lock().com_port_status())
+    })
+}
+
+/// Rejects settings RFC 2217 cannot express
+fn validate_com_port_settings(settings: &ComPortSettings) -> Result<(), String> {
+    if settings.baud_rate == Some(0) {
+        return Err("Baud rate must be greater than 0".to_string());
+    }
+    if let Some(bits) = settings.data_bits {
+        if !(5..=8).contains(&bits) {
+            return Err(format!("Invalid data bits: {} (expected 5-8)", bits));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Protocol State Machine
+// ============================================================================
+
+/// Negotiation state of one side of an option (RFC 1143)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum QState {
+    #[default]
+    No,
+    Yes,
+    /// We asked to disable it and await the answer
+    WantNo,
+    /// We asked to enable it and await the answer
+    WantYes,
+}
+
+/// One side of an option: its state plus the RFC 1143 "opposite" queue bit
+#[derive(Clone, Copy, Debug, Default)]
+struct OptionSide {
+    state: QState,
+    /// A request for the opposite state is queued behind the pending one
+    opposite: bool,
+}
+
+impl OptionSide {
+    /// Handles WILL (remote side) or DO (local side).
+    ///
+    /// Returns `Some(true)` to acknowledge, `Some(false)` to refuse, or
+    /// `None` when no reply is due.
+    fn receive_enable(&mut self, acceptable: bool) -> Option<bool> {
+        match (self.state, self.opposite) {
+            (QState::No, _) => {
+                if acceptable {
+                    self.state = QState::Yes;
+                    Some(true)
+                } else {
+                    Some(false)
                 }
-                // SB (subnegotiation) - skip until SE
-                250 => {
-                    // Find the end of subnegotiation (IAC SE = 255 240)
-                    while i < raw.len() {
-                        if raw[i] == 255 && i + 1 < raw.len() && raw[i + 1] == 240 {
-                            i += 2;
-                            break;
+            }
+            (QState::Yes, _) => None,
+            // Our disable request was answered with an enable; accept it
+            (QState::WantNo, false) => {
+                self.state = QState::No;
+                None
+            }
+            (QState::WantNo, true) => {
+                self.state = QState::Yes;
+                self.opposite = false;
+                None
+            }
+            (QState::WantYes, false) => {
+                self.state = QState::Yes;
+                None
+            }
+            (QState::WantYes, true) => {
+                self.state = QState::WantNo;
+                self.opposite = false;
+                Some(false)
+            }
+        }
+    }
+
+    /// Handles WONT (remote side) or DONT (local side); same return as
+    /// [`OptionSide::receive_enable`].
+    fn receive_disable(&mut self) -> Option<bool> {
+        match (self.state, self.opposite) {
+            (QState::No, _) => None,
+            (QState::Yes, _) => {
+                self.state = QState::No;
+                Some(false)
+            }
+            (QState::WantNo, false) => {
+                self.state = QState::No;
+                None
+            }
+            (QState::WantNo, true) => {
+                self.state = QState::WantYes;
+                self.opposite = false;
+                Some(true)
+            }
+            (QState::WantYes, _) => {
+                self.state = QState::No;
+                self.opposite = false;
+                None
+            }
+        }
+    }
+
+    /// Asks for the option to be enabled; returns true if a request must be sent
+    fn request_enable(&mut self) -> bool {
+        match (self.state, self.opposite) {
+            (QState::No, _) => {
+                self.state = QState::WantYes;
+                true
+            }
+            // A disable request is pending; ask again once it is answered
+            (QState::WantNo, false) => {
+                self.opposite = true;
+                false
+            }
+            (QState::WantYes, true) => {
+                self.opposite = false;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.state == QState::Yes
+    }
+}
+
+/// Both sides of an option
+#[derive(Clone, Copy, Debug, Default)]
+struct OptionState {
+    /// Whether we perform the option (WILL/WONT from us, DO/DONT from the server)
+    local: OptionSide,
+    /// Whether the server performs the option (WILL/WONT from the server)
+    remote: OptionSide,
+}
+
+/// Where the parser is within the telnet byte stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ParseState {
+    Data,
+    /// Previous byte was CR; a following NUL is padding (RFC 854)
+    Cr,
+    Iac,
+    /// Waiting for the option byte of WILL, WONT, DO or DONT
+    Negotiate(u8),
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+/// Telnet protocol state for one connection.
+///
+/// Feed received bytes through [`TelnetProtocol::receive`] and send whatever
+/// [`TelnetProtocol::take_outgoing`] returns to the server.
+pub struct TelnetProtocol {
+    parse: ParseState,
+    options: [OptionState; 256],
+    subnegotiation: Vec<u8>,
+    /// Window size reported through NAWS as (cols, rows)
+    window_size: (u16, u16),
+    /// Protocol bytes waiting to be sent
+    outgoing: Vec<u8>,
+    /// Encoding of a bare CR in user input
+    newline: TelnetNewline,
+    /// The last input ended with a CR sent as a line ending, so an LF that
+    /// starts the next one (a CR LF split across writes) is not sent again
+    sent_cr: bool,
+    /// Serial port state reported by the server (RFC 2217)
+    com_port: ComPortStatus,
+    /// Settings waiting for the server to agree to COM-PORT-OPTION
+    com_port_pending: Option<ComPortSettings>,
+    /// The server reported a serial port change since the last check
+    com_port_updated: bool,
+}
+
+impl Default for TelnetProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelnetProtocol {
+    pub fn new() -> Self {
+        TelnetProtocol {
+            parse: ParseState::Data,
+            options: [OptionState::default(); 256],
+            subnegotiation: Vec::new(),
+            window_size: DEFAULT_WINDOW_SIZE,
+            outgoing: Vec::new(),
+            newline: TelnetNewline::default(),
+            sent_cr: false,
+            com_port: ComPortStatus::default(),
+            com_port_pending: None,
+            com_port_updated: false,
+        }
+    }
+
+    /// Processes bytes read from the connection and returns the data bytes
+    pub fn receive(&mut self, raw: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(raw.len());
+
+        for &byte in raw {
+            match self.parse {
+                ParseState::Data | ParseState::Cr => {
+                    let after_cr = self.parse == ParseState::Cr;
+                    self.parse = ParseState::Data;
+                    match byte {
+                        IAC => self.parse = ParseState::Iac,
+                        // CR NUL means a bare CR outside binary mode
+                        0 if after_cr && !self.remote_enabled(OPT_BINARY) => {}
+                        b'\r' => {
+                            data.push(byte);
+                            self.parse = ParseState::Cr;
                         }
-                        i += 1;
+                        _ => data.push(byte),
                     }
                 }
-                // Other commands - 2 byte sequences
-                _ => {
-                    i += 2;
+                ParseState::Iac => self.receive_command(byte, &mut data),
+                ParseState::Negotiate(command) => {
+                    self.parse = ParseState::Data;
+                    self.negotiate(command, byte);
                 }
+                ParseState::Subnegotiation => {
+                    if byte == IAC {
+                        self.parse = ParseState::SubnegotiationIac;
+                    } else if self.subnegotiation.len() < MAX_SUBNEGOTIATION_LEN {
+                        self.subnegotiation.push(byte);
+                    }
+                }
+                ParseState::SubnegotiationIac => match byte {
+                    SE => {
+                        self.parse = ParseState::Data;
+                        self.handle_subnegotiation();
+                    }
+                    IAC => {
+                        if self.subnegotiation.len() < MAX_SUBNEGOTIATION_LEN {
+                            self.subnegotiation.push(IAC);
+                        }
+                        self.parse = ParseState::Subnegotiation;
+                    }
+                    // Unterminated subnegotiation; drop it and treat this as a command
+                    _ => {
+                        self.subnegotiation.clear();
+                        self.receive_command(byte, &mut data);
+                    }
+                },
             }
-        } else {
-            // Regular data byte
-            result.push(byte);
-            i += 1;
         }
+
+        data
     }
 
-    String::from_utf8_lossy(&result).to_string()
-}
+    /// Handles the byte following IAC
+    fn receive_command(&mut self, command: u8, data: &mut Vec<u8>) {
+        self.parse = ParseState::Data;
+        match command {
+            // IAC IAC = literal 255
+            IAC => data.push(IAC),
+            WILL | WONT | DO | DONT => self.parse = ParseState::Negotiate(command),
+            SB => {
+                self.subnegotiation.clear();
+                self.parse = ParseState::Subnegotiation;
+            }
+            // NOP, GA, DM, AYT etc. carry nothing for us
+            _ => {}
+        }
+    }
 
-/// Get list of active telnet sessions
-#[tauri::command]
-pub fn list_telnet_sessions() -> Vec<(String, String, u16)> {
-    let sessions_ptr = TELNET_SESSIONS.clone();
-    let sessions = sessions_ptr.lock();
+    /// Encodes user input for the wire.
+    ///
+    /// IAC is always doubled. Outside BINARY mode a CR not followed by LF is
+    /// sent as CR NUL or CR LF, depending on the session's newline setting;
+    /// when a paste splits CR LF across two writes, the LF is dropped.
+    pub fn encode(&mut self, data: &[u8]) -> Vec<u8> {
+        let binary = self.local_enabled(OPT_BINARY);
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let data = match data.strip_prefix(b"\n") {
+            Some(rest) if self.sent_cr && !binary => rest,
+            _ => data,
+        };
+        self.sent_cr = !binary && data.last() == Some(&b'\r');
+
+        let mut encoded = Vec::with_capacity(data.len() + data.len() / 8);
 
-    sessions
+        for (i, &byte) in data.iter().enumerate() {
+            encoded.push(byte);
+            match byte {
+                IAC => encoded.push(IAC),
+                b'\r' if !binary && data.get(i + 1) != Some(&b'\n') => {
+                    encoded.push(match self.newline {
+                        TelnetNewline::CrNul => 0,
+                        TelnetNewline::CrLf => b'\n',
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        encoded
+    }
+
+    /// Sets how a bare CR in user input is encoded
+    pub fn set_newline(&mut self, newline: TelnetNewline) {
+        self.newline = newline;
+    }
+
+    /// Takes the protocol bytes that should be sent to the server
+    pub fn take_outgoing(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Whether we have agreed to perform `option`
+    pub fn local_enabled(&self, option: u8) -> bool {
+        self.options[option as usize].local.enabled()
+    }
+
+    /// Whether the server has agreed to perform `option`
+    pub fn remote_enabled(&self, option: u8) -> bool {
+        self.options[option as usize].remote.enabled()
+    }
+
+    /// Negotiated state of the supported options, for display
+    pub fn option_status(&self) -> Vec<TelnetOptionStatus> {
+        [
+            OPT_BINARY,
+            OPT_ECHO,
+            OPT_SGA,
+            OPT_TTYPE,
+            OPT_NAWS,
+            OPT_COM_PORT,
+        ]
         .iter()
-        .map(|(id, session)| (id.clone(), session.host.clone(), session.port))
+        .map(|&option| TelnetOptionStatus {
+            option: option_name(option).to_string(),
+            local: self.local_enabled(option),
+            remote: self.remote_enabled(option),
+        })
         .collect()
+    }
+
+    /// Offers to perform `option` (sends WILL unless already agreed or pending)
+    pub fn request_local(&mut self, option: u8) {
+        if self.options[option as usize].local.request_enable() {
+            self.send_command(WILL, option);
+        }
+    }
+
+    /// Updates the window size, reporting it if NAWS is in effect
+    pub fn set_window_size(&mut self, cols: u16, rows: u16) {
+        if self.window_size == (cols, rows) {
+            return;
+        }
+        self.window_size = (cols, rows);
+        if self.local_enabled(OPT_NAWS) {
+            self.send_window_size();
+        }
+    }
+
+    /// Sends serial port settings, or queues them until COM-PORT-OPTION is agreed
+    pub fn com_port_configure(&mut self, settings: ComPortSettings) {
+        if self.local_enabled(OPT_COM_PORT) {
+            self.send_com_port_settings(&settings);
+        } else {
+            let pending = self.com_port_pending.get_or_insert_with(Default::default);
+            merge_com_port_settings(pending, &settings);
+            self.request_local(OPT_COM_PORT);
+        }
+    }
+
+    /// Sets or clears the break state on the remote serial port
+    pub fn com_port_break(&mut self, on: bool) -> Result<(), String> {
+        if !self.local_enabled(OPT_COM_PORT) {
+            return Err("Server has not agreed to RFC 2217 serial port control".to_string());
+        }
+        let control = if on {
+            CONTROL_BREAK_ON
+        } else {
+            CONTROL_BREAK_OFF
+        };
+        self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_CONTROL, control]);
+        Ok(())
+    }
+
+    /// Serial port state as last reported by the server
+    pub fn com_port_status(&self) -> ComPortStatus {
+        ComPortStatus {
+            enabled: self.local_enabled(OPT_COM_PORT),
+            ..self.com_port.clone()
+        }
+    }
+
+    /// Returns the serial port state if the server reported a change since the last call
+    pub fn take_com_port_update(&mut self) -> Option<ComPortStatus> {
+        std::mem::take(&mut self.com_port_updated).then(|| self.com_port_status())
+    }
+
+    /// Options we are willing to perform
+    fn accept_local(option: u8) -> bool {
+        matches!(
+            option,
+            OPT_BINARY | OPT_SGA | OPT_TTYPE | OPT_NAWS | OPT_COM_PORT
+        )
+    }
+
+    /// Options we let the server perform
+    fn accept_remote(option: u8) -> bool {
+        matches!(option, OPT_BINARY | OPT_ECHO | OPT_SGA)
+    }
+
+    fn negotiate(&mut self, command: u8, option: u8) {
+        let state = &mut self.options[option as usize];
+        match command {
+            WILL | WONT => {
+                let reply = if command == WILL {
+                    state.remote.receive_enable(Self::accept_remote(option))
+                } else {
+                    state.remote.receive_disable()
+                };
+                if let Some(agree) = reply {
+                    self.send_command(if agree { DO } else { DONT }, option);
+                }
+            }
+            _ => {
+                let was_enabled = state.local.enabled();
+                let reply = if command == DO {
+                    state.local.receive_enable(Self::accept_local(option))
+                } else {
+                    state.local.receive_disable()
+                };
+                if let Some(agree) = reply {
+                    self.send_command(if agree { WILL } else { WONT }, option);
+                }
+                if !was_enabled && self.local_enabled(option) {
+                    self.local_option_enabled(option);
+                }
+            }
+        }
+    }
+
+    /// Sends the initial subnegotiation for options that need one
+    fn local_option_enabled(&mut self, option: u8) {
+        match option {
+            OPT_NAWS => self.send_window_size(),
+            OPT_COM_PORT => {
+                // Apply queued settings, then ask for the current ones
+                if let Some(settings) = self.com_port_pending.take() {
+                    self.send_com_port_settings(&settings);
+                }
+                // A zero value asks the server for the current setting
+                self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_BAUDRATE, 0, 0, 0, 0]);
+                for command in [CPO_SET_DATASIZE, CPO_SET_PARITY, CPO_SET_STOPSIZE] {
+                    self.send_subnegotiation(OPT_COM_PORT, &[command, 0]);
+                }
+                self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_CONTROL, CONTROL_REQUEST_FLOW]);
+                self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_MODEMSTATE_MASK, 0xFF]);
+                self.com_port_updated = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_subnegotiation(&mut self) {
+        match self.subnegotiation.as_slice() {
+            [OPT_TTYPE, TTYPE_SEND, ..] if self.local_enabled(OPT_TTYPE) => {
+                let mut payload = vec![TTYPE_IS];
+                payload.extend_from_slice(TERMINAL_TYPE.as_bytes());
+                self.send_subnegotiation(OPT_TTYPE, &payload);
+            }
+            [OPT_COM_PORT, command, value @ ..] if *command > CPO_SERVER_OFFSET => {
+                let command = command - CPO_SERVER_OFFSET;
+                let value = value.to_vec();
+                self.handle_com_port_reply(command, &value);
+            }
+            _ => {}
+        }
+    }
+
+    /// Records a serial port setting or notification from the server
+    fn handle_com_port_reply(&mut self, command: u8, value: &[u8]) {
+        let settings = &mut self.com_port.settings;
+        match (command, value) {
+            (CPO_SET_BAUDRATE, &[a, b, c, d]) => {
+                settings.baud_rate = Some(u32::from_be_bytes([a, b, c, d]));
+            }
+            (CPO_SET_DATASIZE, &[bits]) => settings.data_bits = Some(bits),
+            (CPO_SET_PARITY, &[parity]) => {
+                settings.parity = match parity {
+                    1 => Some(Parity::None),
+                    2 => Some(Parity::Odd),
+                    3 => Some(Parity::Even),
+                    4 => Some(Parity::Mark),
+                    5 => Some(Parity::Space),
+                    _ => settings.parity,
+                };
+            }
+            (CPO_SET_STOPSIZE, &[stop]) => {
+                settings.stop_bits = match stop {
+                    1 => Some(StopBits::One),
+                    2 => Some(StopBits::Two),
+                    3 => Some(StopBits::OnePointFive),
+                    _ => settings.stop_bits,
+                };
+            }
+            (CPO_SET_CONTROL, &[control]) => {
+                settings.flow_control = match control {
+                    CONTROL_FLOW_NONE => Some(FlowControl::None),
+                    CONTROL_FLOW_XONXOFF => Some(FlowControl::Software),
+                    CONTROL_FLOW_HARDWARE => Some(FlowControl::Hardware),
+                    // Break, DTR and RTS states are not tracked
+                    _ => return,
+                };
+            }
+            (CPO_NOTIFY_LINESTATE, &[state]) => self.com_port.line_state = Some(state),
+            (CPO_NOTIFY_MODEMSTATE, &[state]) => {
+                self.com_port.modem = Some(ModemState::from_byte(state));
+            }
+            _ => return,
+        }
+        self.com_port_updated = true;
+    }
+
+    fn send_com_port_settings(&mut self, settings: &ComPortSettings) {
+        if let Some(baud) = settings.baud_rate {
+            let mut payload = vec![CPO_SET_BAUDRATE];
+            payload.extend_from_slice(&baud.to_be_bytes());
+            self.send_subnegotiation(OPT_COM_PORT, &payload);
+        }
+        if let Some(bits) = settings.data_bits {
+            self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_DATASIZE, bits]);
+        }
+        if let Some(parity) = settings.parity {
+            let value = match parity {
+                Parity::None => 1,
+                Parity::Odd => 2,
+                Parity::Even => 3,
+                Parity::Mark => 4,
+                Parity::Space => 5,
+            };
+            self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_PARITY, value]);
+        }
+        if let Some(stop) = settings.stop_bits {
+            let value = match stop {
+                StopBits::One => 1,
+                StopBits::Two => 2,
+                StopBits::OnePointFive => 3,
+            };
+            self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_STOPSIZE, value]);
+        }
+        if let Some(flow) = settings.flow_control {
+            let value = match flow {
+                FlowControl::None => CONTROL_FLOW_NONE,
+                FlowControl::Software => CONTROL_FLOW_XONXOFF,
+                FlowControl::Hardware => CONTROL_FLOW_HARDWARE,
+            };
+            self.send_subnegotiation(OPT_COM_PORT, &[CPO_SET_CONTROL, value]);
+        }
+    }
+
+    fn send_window_size(&mut self) {
+        let (cols, rows) = self.window_size;
+        let mut payload = Vec::with_capacity(4);
+        payload.extend_from_slice(&cols.to_be_bytes());
+        payload.extend_from_slice(&rows.to_be_bytes());
+        self.send_subnegotiation(OPT_NAWS, &payload);
+    }
+
+    fn send_command(&mut self, command: u8, option: u8) {
+        self.outgoing.extend_from_slice(&[IAC, command, option]);
+    }
+
+    /// Queues IAC SB option <payload> IAC SE, escaping IAC bytes in the payload
+    fn send_subnegotiation(&mut self, option: u8, payload: &[u8]) {
+        self.outgoing.extend_from_slice(&[IAC, SB, option]);
+        for &byte in payload {
+            if byte == IAC {
+                self.outgoing.push(IAC);
+            }
+            self.outgoing.push(byte);
+        }
+        self.outgoing.extend_from_slice(&[IAC, SE]);
+    }
+}
+
+/// Copies the fields set in `update` into `settings`
+fn merge_com_port_settings(settings: &mut ComPortSettings, update: &ComPortSettings) {
+    settings.baud_rate = update.baud_rate.or(settings.baud_rate);
+    settings.data_bits = update.data_bits.or(settings.data_bits);
+    settings.parity = update.parity.or(settings.parity);
+    settings.stop_bits = update.stop_bits.or(settings.stop_bits);
+    settings.flow_control = update.flow_control.or(settings.flow_control);
+}
+
+/// Display name of a telnet option
+fn option_name(option: u8) -> &'static str {
+    match option {
+        OPT_BINARY => "BINARY",
+        OPT_ECHO => "ECHO",
+        OPT_SGA => "SGA",
+        OPT_TTYPE => "TTYPE",
+        OPT_NAWS => "NAWS",
+        OPT_COM_PORT => "COM-PORT-OPTION",
+        _ => "UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiation_replies() {
+        let mut protocol = TelnetProtocol::new();
+        let data = protocol.receive(&[
+            IAC, WILL, OPT_ECHO, IAC, WILL, OPT_SGA, IAC, DO, OPT_TTYPE, IAC, DO, 42, b'o', b'k',
+        ]);
+        assert_eq!(data, b"ok");
+        assert_eq!(
+            protocol.take_outgoing(),
+            vec![IAC, DO, OPT_ECHO, IAC, DO, OPT_SGA, IAC, WILL, OPT_TTYPE, IAC, WONT, 42]
+        );
+        assert!(protocol.remote_enabled(OPT_ECHO));
+        assert!(protocol.local_enabled(OPT_TTYPE));
+
+        // Repeated requests for an enabled option are not answered again
+        protocol.receive(&[IAC, WILL, OPT_ECHO]);
+        assert!(protocol.take_outgoing().is_empty());
+
+        protocol.receive(&[IAC, WONT, OPT_ECHO]);
+        assert_eq!(protocol.take_outgoing(), vec![IAC, DONT, OPT_ECHO]);
+        assert!(!protocol.remote_enabled(OPT_ECHO));
+    }
+
+    #[test]
+    fn test_split_sequences() {
+        let mut protocol = TelnetProtocol::new();
+        let mut data = protocol.receive(&[b'a', IAC]);
+        data.extend(protocol.receive(&[DO]));
+        data.extend(protocol.receive(&[OPT_NAWS, IAC]));
+        data.extend(protocol.receive(&[IAC, b'b']));
+        assert_eq!(data, vec![b'a', IAC, b'b']);
+        assert_eq!(
+            protocol.take_outgoing(),
+            vec![IAC, WILL, OPT_NAWS, IAC, SB, OPT_NAWS, 0, 80, 0, 24, IAC, SE]
+        );
+    }
+
+    #[test]
+    fn test_window_size() {
+        let mut protocol = TelnetProtocol::new();
+        protocol.set_window_size(132, 40);
+        protocol.request_local(OPT_NAWS);
+        assert_eq!(protocol.take_outgoing(), vec![IAC, WILL, OPT_NAWS]);
+
+        // The server's DO answers our WILL; only the size follows
+        protocol.receive(&[IAC, DO, OPT_NAWS]);
+        assert_eq!(
+            protocol.take_outgoing(),
+            vec![IAC, SB, OPT_NAWS, 0, 132, 0, 40, IAC, SE]
+        );
+
+        // Sizes containing 255 are escaped
+        protocol.set_window_size(255, 40);
+        assert_eq!(
+            protocol.take_outgoing(),
+            vec![IAC, SB, OPT_NAWS, 0, IAC, IAC, 0, 40, IAC, SE]
+        );
+
+        // Nothing is sent if the server refused
+        let mut protocol = TelnetProtocol::new();
+        protocol.request_local(OPT_NAWS);
+        protocol.receive(&[IAC, DONT, OPT_NAWS]);
+        protocol.take_outgoing();
+        protocol.set_window_size(100, 30);
+        assert!(protocol.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn test_terminal_type() {
+        let mut protocol = TelnetProtocol::new();
+        protocol.receive(&[IAC, DO, OPT_TTYPE]);
+        protocol.take_outgoing();
+
+        protocol.receive(&[IAC, SB, OPT_TTYPE, TTYPE_SEND]);
+        protocol.receive(&[IAC, SE]);
+        let mut expected = vec![IAC, SB, OPT_TTYPE, TTYPE_IS];
+        expected.extend_from_slice(TERMINAL_TYPE.as_bytes());
+        expected.extend_from_slice(&[IAC, SE]);
+        assert_eq!(protocol.take_outgoing(), expected);
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut protocol = TelnetProtocol::new();
+        assert_eq!(protocol.encode(b"show ver\r"), b"show ver\r\0");
+        assert_eq!(protocol.encode(b"a\r\nb"), b"a\r\nb");
+        assert_eq!(
+            protocol.encode(&[b'x', IAC, b'y']),
+            vec![b'x', IAC, IAC, b'y']
+        );
+
+        // The LF of a CR LF split across writes belongs to the CR
+        assert_eq!(protocol.encode(b"a\r"), b"a\r\0");
+        assert_eq!(protocol.encode(b"\nb"), b"b");
+        assert_eq!(protocol.encode(b"\n"), b"\n");
+
+        protocol.set_newline(TelnetNewline::CrLf);
+        assert_eq!(protocol.encode(b"\r"), b"\r\n");
+        assert_eq!(protocol.encode(b"\n"), b"");
+
+        // BINARY mode only escapes IAC
+        protocol.receive(&[IAC, DO, OPT_BINARY]);
+        assert_eq!(protocol.encode(&[b'\r', IAC]), vec![b'\r', IAC, IAC]);
+    }
+
+    #[test]
+    fn test_com_port() {
+        let mut protocol = TelnetProtocol::new();
+        assert!(protocol.com_port_break(true).is_err());
+
+        // Settings wait for the server to agree
+        protocol.com_port_configure(ComPortSettings {
+            baud_rate: Some(9600),
+            parity: Some(Parity::Even),
+            ..Default::default()
+        });
+        assert_eq!(protocol.take_outgoing(), vec![IAC, WILL, OPT_COM_PORT]);
+
+        protocol.receive(&[IAC, DO, OPT_COM_PORT]);
+        let sent = protocol.take_outgoing();
+        let baud = [
+            IAC,
+            SB,
+            OPT_COM_PORT,
+            CPO_SET_BAUDRATE,
+            0,
+            0,
+            0x25,
+            0x80,
+            IAC,
+            SE,
+        ];
+        let parity = [IAC, SB, OPT_COM_PORT, CPO_SET_PARITY, 3, IAC, SE];
+        assert_eq!(sent[..baud.len()], baud);
+        assert_eq!(sent[baud.len()..baud.len() + parity.len()], parity);
+        assert!(protocol.take_com_port_update().is_some());
+
+        // Server replies and notifications are recorded
+        protocol.receive(&[IAC, SB, OPT_COM_PORT, 101, 0, 0, 0x25, 0x80, IAC, SE]);
+        protocol.receive(&[IAC, SB, OPT_COM_PORT, 107, 0xB0, IAC, SE]);
+        let status = protocol.take_com_port_update().unwrap();
+        assert!(status.enabled);
+        assert_eq!(status.settings.baud_rate, Some(9600));
+        let modem = status.modem.unwrap();
+        assert!(modem.cd && modem.dsr && modem.cts && !modem.ri);
+        assert!(protocol.take_com_port_update().is_none());
+
+        protocol.com_port_break(true).unwrap();
+        assert_eq!(
+            protocol.take_outgoing(),
+            vec![
+                IAC,
+                SB,
+                OPT_COM_PORT,
+                CPO_SET_CONTROL,
+                CONTROL_BREAK_ON,
+                IAC,
+                SE
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_names() {
+        let command: TelnetCommand = serde_json::from_str("\"BRK\"").unwrap();
+        assert_eq!(command.code(), BRK);
+        let command: TelnetCommand = serde_json::from_str("\"AYT\"").unwrap();
+        assert_eq!(command.code(), AYT);
+        assert!(serde_json::from_str::<TelnetCommand>("\"DM\"").is_err());
+    }
+
+    #[test]
+    fn test_cr_nul() {
+        let mut protocol = TelnetProtocol::new();
+        assert_eq!(protocol.receive(b"a\r\0b\r\n"), b"a\rb\r\n");
+
+        // NUL is data in binary mode
+        protocol.receive(&[IAC, WILL, OPT_BINARY]);
+        assert_eq!(protocol.receive(b"\r\0"), b"\r\0");
+    }
 }
@@ -0,0 +1,112 @@
+//! Incremental UTF-8 Decoding
+//!
+//! Terminal output arrives in arbitrary chunks, so a multibyte character can
+//! be split across two reads. Decoding every chunk on its own turns both
+//! halves into replacement characters. [`Utf8Decoder`] carries an incomplete
+//! trailing sequence over to the next chunk instead, and readers call
+//! [`Utf8Decoder::finish`] when the stream ends so a cut-off one still shows.
+
+/// Streaming UTF-8 decoder, one per session
+#[derive(Default)]
+pub struct Utf8Decoder {
+    /// Bytes of an incomplete sequence left over from the previous chunk
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the next chunk of bytes.
+    ///
+    /// Invalid sequences become U+FFFD. A truncated sequence at the end of
+    /// the chunk is held back and completed by the next call.
+    pub fn decode(&mut self, input: &[u8]) -> String {
+        let joined;
+        let mut rest: &[u8] = if self.pending.is_empty() {
+            input
+        } else {
+            self.pending.extend_from_slice(input);
+            joined = std::mem::take(&mut self.pending);
+            &joined
+        };
+
+        let mut output = String::with_capacity(rest.len());
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    output.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // Cannot fail: `valid_up_to` marks the end of the valid prefix
+                    output.push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match e.error_len() {
+                        Some(len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            // Sequence is cut off at the end of the chunk
+                            self.pending.extend_from_slice(after);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Ends the stream. A sequence it stopped in the middle of becomes U+FFFD,
+    /// as it would have if more bytes had followed.
+    pub fn finish(&mut self) -> String {
+        if self.pending.is_empty() {
+            return String::new();
+        }
+        self.pending.clear();
+        char::REPLACEMENT_CHARACTER.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_multibyte_character() {
+        // "─" (U+2500) is E2 94 80
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"ab\xe2\x94"), "ab");
+        assert_eq!(decoder.decode(b"\x80cd"), "─cd");
+    }
+
+    #[test]
+    fn test_sequence_split_over_three_reads() {
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"\xf0\x9f"), "");
+        assert_eq!(decoder.decode(b"\x98"), "");
+        assert_eq!(decoder.decode(b"\x80!"), "😀!");
+    }
+
+    #[test]
+    fn test_invalid_bytes_are_replaced() {
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{FFFD}b");
+        assert_eq!(decoder.decode(b"\xe2\x94a"), "\u{FFFD}a");
+    }
+
+    #[test]
+    fn test_finish_flushes_truncated_sequence() {
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"ok\xf0\x9f"), "ok");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+        // Nothing is left over afterwards
+        assert_eq!(decoder.finish(), "");
+        assert_eq!(decoder.decode(b"a"), "a");
+    }
+}
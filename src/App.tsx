@@ -12,6 +12,8 @@ import { TerminalProvider } from "./context/TerminalContext";
 import { TerminalGrid } from "./components/TerminalGrid";
 import { BroadcastInput } from "./components/BroadcastInput";
 import { TitleBar } from "./components/TitleBar";
+import { HostKeyDialog } from "./components/HostKeyDialog";
+import { AuthPromptDialog } from "./components/AuthPromptDialog";
 import "./App.css";
 
 /**
@@ -20,6 +22,7 @@ import "./App.css";
  * Provides the root layout structure with:
  * - Main: Tabbed terminal workspace
  * - Footer: Broadcast command input panel
+ * - SSH host key and login prompts, shown over everything
  */
 function App() {
   return (
@@ -34,6 +37,9 @@ function App() {
         <footer className="app-footer">
           <BroadcastInput />
         </footer>
+
+        <HostKeyDialog />
+        <AuthPromptDialog />
       </div>
     </TerminalProvider>
   );
@@ -0,0 +1,122 @@
+/**
+ * AuthPromptDialog Component
+ *
+ * Answers SSH keyboard-interactive prompts (AAA passwords, OTP tokens,
+ * password changes). The backend emits ssh-auth-prompt for each round and
+ * blocks the login until respond_auth_prompt is called; answers are
+ * cleared as soon as they are sent. Rounds without prompts are server
+ * notices (e.g. an expired password) that the login does not wait for.
+ */
+
+import { useState, useEffect, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { AuthPrompt } from "../types/terminal";
+
+/**
+ * Lock icon for dialog header
+ */
+function LockIcon() {
+    return (
+        <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <rect x="3" y="11" width="18" height="11" rx="2" />
+            <path d="M7 11V7a5 5 0 0110 0v4" />
+        </svg>
+    );
+}
+
+export function AuthPromptDialog() {
+    const [prompts, setPrompts] = useState<AuthPrompt[]>([]);
+    const [answers, setAnswers] = useState<string[]>([]);
+    const firstInputRef = useRef<HTMLInputElement>(null);
+
+    useEffect(() => {
+        const unlistenPromise = listen<AuthPrompt>("ssh-auth-prompt", (event) => {
+            setPrompts((prev) => [...prev, event.payload]);
+        });
+
+        return () => {
+            unlistenPromise.then((unlisten) => unlisten());
+        };
+    }, []);
+
+    const prompt = prompts[0];
+
+    // Start each round with empty answers
+    useEffect(() => {
+        if (prompt) {
+            setAnswers(prompt.prompts.map(() => ""));
+            setTimeout(() => firstInputRef.current?.focus(), 50);
+        }
+    }, [prompt]);
+
+    const respond = useCallback((current: AuthPrompt, response: string[] | null) => {
+        setPrompts((prev) => prev.filter((p) => p.requestId !== current.requestId));
+        setAnswers([]);
+        // Notices were answered by the backend already
+        if (current.prompts.length === 0) return;
+        invoke("respond_auth_prompt", { requestId: current.requestId, answers: response })
+            .catch((err) => console.error("[SSH] Failed to answer authentication prompt:", err));
+    }, []);
+
+    if (!prompt) return null;
+
+    const target = prompt.port === 22 ? prompt.host : `${prompt.host}:${prompt.port}`;
+
+    const handleSubmit = (e: React.FormEvent) => {
+        e.preventDefault();
+        respond(prompt, answers);
+    };
+
+    return (
+        <div className="dialog-overlay">
+            <div className="dialog-content">
+                <div className="dialog-header">
+                    <LockIcon />
+                    <h2>{prompt.name || "Authentication"}</h2>
+                </div>
+
+                <form onSubmit={handleSubmit}>
+                    <div className="dialog-body">
+                        <p className="dialog-description">
+                            {prompt.username}@{target}
+                            {prompt.round > 1 && ` (step ${prompt.round})`}
+                        </p>
+
+                        {prompt.instructions && (
+                            <pre className="auth-prompt-instructions">{prompt.instructions}</pre>
+                        )}
+
+                        {prompt.prompts.map((field, index) => (
+                            <div className="form-group" key={index}>
+                                <label htmlFor={`authPrompt${index}`}>{field.text.trim()}</label>
+                                <input
+                                    ref={index === 0 ? firstInputRef : undefined}
+                                    id={`authPrompt${index}`}
+                                    type={field.echo ? "text" : "password"}
+                                    value={answers[index] ?? ""}
+                                    onChange={(e) => {
+                                        const value = e.target.value;
+                                        setAnswers((prev) => prev.map((answer, i) => (i === index ? value : answer)));
+                                    }}
+                                    autoComplete="off"
+                                />
+                            </div>
+                        ))}
+                    </div>
+
+                    <div className="dialog-footer">
+                        {prompt.prompts.length > 0 && (
+                            <button type="button" className="btn btn-secondary" onClick={() => respond(prompt, null)}>
+                                Cancel
+                            </button>
+                        )}
+                        <button type="submit" className="btn btn-primary">
+                            {prompt.prompts.length > 0 ? "Continue" : "OK"}
+                        </button>
+                    </div>
+                </form>
+            </div>
+        </div>
+    );
+}
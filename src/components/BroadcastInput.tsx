@@ -16,6 +16,17 @@
  *   :m <pattern> <group> - move terminals matching pattern to group
  *   :m <pattern>   - remove terminals from group
  *   :s <group>     - switch to viewing a group (:s all for all)
+ *   :break [all]   - send telnet BREAK to the active terminal (or all targets)
+ *   :iac <brk|ip|ao|ayt> [all] - send a telnet command
+ *   :serial [baud] [8N1] [none|xonxoff|rtscts] - show or set the remote serial port (RFC 2217)
+ *   :sbreak [ms]   - send a serial break over RFC 2217
+ *   :hosts         - list trusted SSH host keys
+ *   :forget <host> [port] - remove a host key trusted in Packet
+ *   :forward <-L|-R|-D spec> - add a port forward to the active SSH session
+ *   :forwards      - list the active SSH session's port forwards
+ *   :unforward <id> - remove a port forward (ID prefix from :forwards)
+ *   :scrollback <size> - set how much output the backend keeps per session (e.g. 512k, 4m)
+ *   :detach        - detach the active terminal from its background session, leaving it running
  * 
  * Wildcard patterns for :m command:
  *   * matches any characters (e.g., R-* matches R-1, R-2, R-CID1)
@@ -25,11 +36,53 @@
 import { useState, useRef, useCallback, useMemo } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTerminals } from "../context/TerminalContext";
-import { CiscoKeywords } from "../types/terminal";
+import { CiscoKeywords, BroadcastResult, ComPortSettings, ComPortStatus, ForwardInfo, KnownHostEntry, TerminalSession } from "../types/terminal";
+import { formatBytes, formatForward, parseForward } from "../utils/portForward";
+import { parseByteSize, setScrollbackLimit } from "../utils/scrollback";
 
 /** Broadcast target modes */
 type BroadcastMode = "all" | "group" | "custom";
 
+/** Telnet commands accepted by send_telnet_command */
+const TELNET_COMMANDS = ["BRK", "IP", "AO", "AYT"];
+
+const PARITY_LETTERS: Record<string, ComPortSettings["parity"]> = {
+    n: "none", o: "odd", e: "even", m: "mark", s: "space",
+};
+
+const FLOW_CONTROL_NAMES: Record<string, ComPortSettings["flowControl"]> = {
+    none: "none", xonxoff: "software", rtscts: "hardware",
+};
+
+/**
+ * Whether a session speaks telnet (telnet connections and telnet-framed sockets)
+ */
+function isTelnetSession(session: TerminalSession): boolean {
+    return session.connectionType === "telnet" || (session.connectionType === "unix" && !!session.unixInfo?.telnet);
+}
+
+/**
+ * Parses ":serial" arguments such as "9600 8N1 rtscts" into port settings
+ */
+function parseSerialArgs(args: string[]): ComPortSettings | null {
+    const settings: ComPortSettings = {};
+    for (const arg of args) {
+        const framing = /^([5-8])([noems])(1|1\.5|2)$/.exec(arg);
+        if (/^\d+$/.test(arg)) {
+            settings.baudRate = parseInt(arg, 10);
+        } else if (framing) {
+            settings.dataBits = parseInt(framing[1], 10);
+            settings.parity = PARITY_LETTERS[framing[2]];
+            settings.stopBits = framing[3] as ComPortSettings["stopBits"];
+        } else if (arg in FLOW_CONTROL_NAMES) {
+            settings.flowControl = FLOW_CONTROL_NAMES[arg];
+        } else {
+            return null;
+        }
+    }
+    return settings;
+}
+
 /**
  * Broadcast status icon
  */
@@ -52,7 +105,7 @@ export function BroadcastInput() {
     const [broadcastMode, setBroadcastMode] = useState<BroadcastMode>("all");
     const [customGroupId, setCustomGroupId] = useState<string | null>(null);
     const inputRef = useRef<HTMLInputElement>(null);
-    const { broadcastKeystroke, sessions, groups, activeGroupId, activeSessionId, moveToGroup, setActiveGroup } = useTerminals();
+    const { broadcastKeystroke, sessions, groups, activeGroupId, activeSessionId, moveToGroup, setActiveGroup, detachSession } = useTerminals();
 
     // Calculate which sessions will receive broadcasts based on mode
     const targetSessions = useMemo(() => {
@@ -269,6 +322,181 @@ export function BroadcastInput() {
             }
         }
 
+        // :break [all] - send telnet BREAK, or a line break on serial sessions (e.g. to reach ROMMON)
+        // :iac <brk|ip|ao|ayt> [all] - send a telnet command
+        if (trimmed === ":break" || trimmed === ":break all" || trimmed.startsWith(":iac ")) {
+            const args = trimmed.split(/\s+/).slice(1);
+            const command = trimmed.startsWith(":iac ") ? args.shift()?.toUpperCase() : "BRK";
+            const toAll = args[0] === "all";
+
+            if (command && TELNET_COMMANDS.includes(command)) {
+                if (toAll) {
+                    const targets = targetSessions.filter(s => isTelnetSession(s) && s.sessionId);
+                    invoke<BroadcastResult[]>("broadcast_telnet_command", {
+                        sessionIds: targets.map(s => s.sessionId),
+                        command,
+                    }).then(results => {
+                        results
+                            .filter(result => !result.success)
+                            .forEach(result => console.error(`[Telnet] Failed to send ${command} to ${result.sessionId}:`, result.error));
+                    }).catch(err => {
+                        console.error(`[Telnet] Failed to broadcast ${command}:`, err);
+                    });
+                    if (command === "BRK") {
+                        targetSessions
+                            .filter(s => s.connectionType === "serial" && s.sessionId)
+                            .forEach(s => invoke("serial_break", { sessionId: s.sessionId, durationMs: null })
+                                .catch(err => console.error(`[Serial] Failed to send break to ${s.sessionId}:`, err)));
+                    }
+                } else {
+                    const session = sessions.find(s => s.id === activeSessionId);
+                    if (session && isTelnetSession(session) && session.sessionId) {
+                        invoke("send_telnet_command", { sessionId: session.sessionId, command })
+                            .catch(err => console.error(`[Telnet] Failed to send ${command}:`, err));
+                    } else if (session?.connectionType === "serial" && session.sessionId && command === "BRK") {
+                        invoke("serial_break", { sessionId: session.sessionId, durationMs: null })
+                            .catch(err => console.error(`[Serial] Failed to send break:`, err));
+                    }
+                }
+                setCurrentLine("");
+                return true;
+            }
+        }
+
+        // :serial [baud] [8N1] [flow] - show or set the serial port (local, or remote over RFC 2217)
+        // :sbreak [ms] - send a serial break
+        if (trimmed === ":serial" || trimmed.startsWith(":serial ") || trimmed === ":sbreak" || trimmed.startsWith(":sbreak ")) {
+            const session = sessions.find(s => s.id === activeSessionId);
+            if ((session?.connectionType === "telnet" || session?.connectionType === "serial") && session.sessionId) {
+                const sessionId = session.sessionId;
+                const args = trimmed.split(/\s+/).slice(1);
+                const commands = session.connectionType === "serial"
+                    ? { brk: "serial_break", status: "serial_status", configure: "serial_configure" }
+                    : { brk: "telnet_com_port_break", status: "telnet_com_port_status", configure: "telnet_com_port_configure" };
+
+                if (trimmed.startsWith(":sbreak")) {
+                    const durationMs = args[0] ? parseInt(args[0], 10) : null;
+                    invoke(commands.brk, { sessionId, durationMs })
+                        .catch(err => console.error(`[Serial] Failed to send break:`, err));
+                } else if (args.length === 0) {
+                    invoke<ComPortStatus>(commands.status, { sessionId })
+                        .then(status => console.log(`[Serial] ${session.name}:`, status))
+                        .catch(err => console.error(`[Serial] Failed to get status:`, err));
+                } else {
+                    const settings = parseSerialArgs(args);
+                    if (!settings) {
+                        console.warn(`[Serial] Usage: :serial [baud] [8N1] [none|xonxoff|rtscts]`);
+                        return false;
+                    }
+                    invoke(commands.configure, { sessionId, settings })
+                        .catch(err => console.error(`[Serial] Failed to configure port:`, err));
+                }
+                setCurrentLine("");
+                return true;
+            }
+        }
+
+        // :hosts - list trusted SSH host keys
+        if (trimmed === ":hosts") {
+            invoke<KnownHostEntry[]>("list_known_hosts").then(entries => {
+                if (entries.length === 0) {
+                    console.log(`[SSH] No trusted host keys`);
+                } else {
+                    console.log(`[SSH] Trusted host keys:`);
+                    entries.forEach(entry => {
+                        console.log(`  - ${entry.hosts.join(",")} ${entry.keyType} ${entry.fingerprint} (${entry.source})`);
+                    });
+                }
+            }).catch(err => {
+                console.error(`[SSH] Failed to list host keys:`, err);
+            });
+            setCurrentLine("");
+            return true;
+        }
+
+        // :forget <host> [port] - remove a host key trusted in Packet
+        if (trimmed.startsWith(":forget ")) {
+            const [host, portArg] = trimmed.split(/\s+/).slice(1);
+            const port = portArg ? parseInt(portArg, 10) : 22;
+            if (host && !isNaN(port)) {
+                invoke<number>("remove_known_host", { host, port, fingerprint: null })
+                    .then(removed => console.log(`[SSH] Removed ${removed} host key(s) for ${host}`))
+                    .catch(err => console.error(`[SSH] Failed to remove host key:`, err));
+                setCurrentLine("");
+                return true;
+            }
+        }
+
+        // :forward <spec>, :forwards, :unforward <id> - manage the active SSH session's port forwards
+        if (trimmed.startsWith(":forward ") || trimmed === ":forwards" || trimmed.startsWith(":unforward ")) {
+            const session = sessions.find(s => s.id === activeSessionId);
+            if (session?.connectionType === "ssh" && session.sessionId) {
+                const sessionId = session.sessionId;
+                const arg = trimmed.split(/\s+/).slice(1).join(" ");
+
+                if (trimmed === ":forwards") {
+                    invoke<ForwardInfo[]>("list_ssh_forwards", { sessionId }).then(forwards => {
+                        if (forwards.length === 0) {
+                            console.log(`[SSH] No port forwards on ${session.name}`);
+                        } else {
+                            console.log(`[SSH] Port forwards on ${session.name}:`);
+                            forwards.forEach(forward => {
+                                console.log(`  - ${forward.id.slice(0, 8)} ${formatForward(forward)} (port ${forward.boundPort}) ` +
+                                    `${forward.activeConnections} open, ${forward.totalConnections} total, ` +
+                                    `${formatBytes(forward.bytesSent)} sent, ${formatBytes(forward.bytesReceived)} received`);
+                            });
+                        }
+                    }).catch(err => console.error(`[SSH] Failed to list port forwards:`, err));
+                } else if (trimmed.startsWith(":forward ")) {
+                    const forward = parseForward(arg);
+                    if (!forward) {
+                        console.warn(`[SSH] Usage: :forward -L [addr:]port:host:hostport | -R [addr:]port:host:hostport | -D [addr:]port`);
+                        return false;
+                    }
+                    invoke<ForwardInfo>("add_ssh_forward", { sessionId, forward })
+                        .then(info => console.log(`[SSH] Forwarding ${formatForward(info)} (port ${info.boundPort}) as ${info.id.slice(0, 8)}`))
+                        .catch(err => console.error(`[SSH] Failed to add port forward:`, err));
+                } else {
+                    invoke<ForwardInfo[]>("list_ssh_forwards", { sessionId }).then(forwards => {
+                        const matches = forwards.filter(forward => forward.id.startsWith(arg));
+                        if (matches.length !== 1) {
+                            console.warn(`[SSH] ${matches.length === 0 ? "No" : "More than one"} port forward matches '${arg}'`);
+                            return;
+                        }
+                        return invoke("remove_ssh_forward", { sessionId, forwardId: matches[0].id })
+                            .then(() => console.log(`[SSH] Removed port forward ${formatForward(matches[0])}`));
+                    }).catch(err => console.error(`[SSH] Failed to remove port forward:`, err));
+                }
+                setCurrentLine("");
+                return true;
+            }
+        }
+
+        // :scrollback <size> - set the backend scrollback cap for new and open sessions
+        if (trimmed.startsWith(":scrollback ")) {
+            const maxBytes = parseByteSize(trimmed.slice(":scrollback ".length));
+            if (!maxBytes) {
+                console.warn(`[Scrollback] Usage: :scrollback <size>, e.g. 512k or 4m`);
+                return false;
+            }
+            const sessionIds = sessions.map(s => s.sessionId).filter((id): id is string => !!id);
+            setScrollbackLimit(maxBytes, sessionIds)
+                .then(() => console.log(`[Scrollback] Keeping ${formatBytes(maxBytes)} of output per session`))
+                .catch(err => console.error(`[Scrollback] Failed to set limit:`, err));
+            setCurrentLine("");
+            return true;
+        }
+
+        // :detach - close the active pane but keep its daemon session running
+        if (trimmed === ":detach" && activeSessionId) {
+            const session = sessions.find(s => s.id === activeSessionId);
+            detachSession(activeSessionId)
+                .then(() => console.log(`[Daemon] Detached from ${session?.name}; attach again from Sessions`))
+                .catch(err => console.error(`[Daemon] Failed to detach:`, err));
+            setCurrentLine("");
+            return true;
+        }
+
         // :? or :help - show help
         if (trimmed === ":?" || trimmed === ":help") {
             // Just clear - help is shown in placeholder
@@ -277,7 +505,7 @@ export function BroadcastInput() {
         }
 
         return false;
-    }, [activeGroupId, groups, sessions, moveToGroup, setActiveGroup]);
+    }, [activeGroupId, activeSessionId, groups, sessions, targetSessions, moveToGroup, setActiveGroup, detachSession]);
 
     /**
      * Handle keyboard events and broadcast keystrokes in real-time
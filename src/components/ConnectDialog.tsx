@@ -1,26 +1,119 @@
 /**
  * ConnectDialog Component
  * 
- * Modal dialog for connecting to network devices via Telnet or SSH.
+ * Modal dialog for connecting to network devices via Telnet, SSH, a serial cable or a socket.
  * Supports:
  * - Saved connection profiles for quick access
  * - Telnet connections (for GNS3/EVE-NG devices)
- * - SSH connections with password or public key authentication
+ * - SSH connections with password, public key, ssh-agent or keyboard-interactive authentication,
+ *   by address or by ~/.ssh/config alias
+ * - Local programs (virsh console, docker exec, ...) with custom args, cwd and env
+ * - Serial consoles on local ports (USB console cables) with line settings
+ * - VM consoles on Unix domain sockets (QEMU, libvirt), raw or telnet framed
+ * - Raw TCP consoles and services, with line ending translation
  */
 
 import { useState, useRef, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTerminals } from "../context/TerminalContext";
-import { SshAuthType, ConnectionProfile } from "../types/terminal";
+import { SshAuthType, AgentIdentity, SshHostConfig, JumpHost, ForwardSpec, ConnectionProfile, LocalCommand, TelnetNewline, TelnetOptions, ComPortSettings, SerialDevice, RawNewline, RawOptions } from "../types/terminal";
+import { formatForward, parseForwardList } from "../utils/portForward";
 
 interface ConnectDialogProps {
     isOpen: boolean;
     onClose: () => void;
 }
 
-type ConnectionMode = "telnet" | "ssh";
+type ConnectionMode = "telnet" | "ssh" | "local" | "serial" | "unix" | "raw";
+
+/** Common console baud rates, fastest last */
+const BAUD_RATES = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200];
 type DialogView = "connect" | "save-profile";
 
+/**
+ * Splits an argument string on whitespace, honouring single and double quotes
+ */
+function splitArgs(input: string): string[] {
+    const args: string[] = [];
+    let current = "";
+    let quote: string | null = null;
+    let inArg = false;
+
+    for (const ch of input) {
+        if (quote) {
+            if (ch === quote) {
+                quote = null;
+            } else {
+                current += ch;
+            }
+        } else if (ch === "'" || ch === '"') {
+            quote = ch;
+            inArg = true;
+        } else if (/\s/.test(ch)) {
+            if (inArg) {
+                args.push(current);
+                current = "";
+                inArg = false;
+            }
+        } else {
+            current += ch;
+            inArg = true;
+        }
+    }
+    if (inArg) {
+        args.push(current);
+    }
+    return args;
+}
+
+/**
+ * Parses KEY=VALUE lines into an environment map
+ */
+function parseEnv(input: string): Record<string, string> {
+    const env: Record<string, string> = {};
+    for (const line of input.split("\n")) {
+        const trimmed = line.trim();
+        const eq = trimmed.indexOf("=");
+        if (eq > 0) {
+            env[trimmed.slice(0, eq).trim()] = trimmed.slice(eq + 1);
+        }
+    }
+    return env;
+}
+
+/**
+ * Quotes arguments containing whitespace so they survive splitArgs
+ */
+function joinArgs(args: string[]): string {
+    return args.map(arg => (/\s/.test(arg) || arg === "" ? `"${arg}"` : arg)).join(" ");
+}
+
+/**
+ * Parses a ProxyJump-style list (`user@bastion:2222, core-gw`) into jump hosts
+ */
+function parseJumpHosts(input: string): JumpHost[] {
+    return input.split(",").map(hop => hop.trim()).filter(Boolean).map(hop => {
+        const at = hop.lastIndexOf("@");
+        const username = at >= 0 ? hop.slice(0, at) : null;
+        const address = hop.slice(at + 1);
+        // [2001:db8::1]:22, or host:port when there is a single colon
+        const match = address.match(/^\[(.+)\](?::(\d+))?$/) ?? address.match(/^([^:]+)(?::(\d+))?$/);
+        const host = match ? match[1] : address;
+        const port = match?.[2] ? parseInt(match[2], 10) : null;
+        return { host, port, username };
+    });
+}
+
+/**
+ * Formats jump hosts back into the ProxyJump syntax parseJumpHosts reads
+ */
+function formatJumpHosts(hosts: JumpHost[]): string {
+    return hosts.map(hop => {
+        const host = hop.host.includes(":") ? `[${hop.host}]` : hop.host;
+        return `${hop.username ? `${hop.username}@` : ""}${host}${hop.port ? `:${hop.port}` : ""}`;
+    }).join(", ");
+}
+
 /**
  * Network icon for dialog header
  */
@@ -72,7 +165,7 @@ function DeleteIcon() {
 }
 
 export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
-    const { addTelnetSession, addSshSession } = useTerminals();
+    const { addTelnetSession, addSshSession, addCommandSession, addSerialSession, addUnixSession, addRawSession } = useTerminals();
     
     // Profiles state
     const [profiles, setProfiles] = useState<ConnectionProfile[]>([]);
@@ -95,8 +188,49 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
     const [password, setPassword] = useState("");
     const [keyPath, setKeyPath] = useState("~/.ssh/id_rsa");
     const [passphrase, setPassphrase] = useState("");
+    const [agentIdentities, setAgentIdentities] = useState<AgentIdentity[]>([]);
+    const [agentError, setAgentError] = useState("");
+    const [sshConfigHosts, setSshConfigHosts] = useState<string[]>([]);
+    const [sshHostConfig, setSshHostConfig] = useState<SshHostConfig | null>(null);
+    const [forwardsText, setForwardsText] = useState("");
+
+    // Telnet-specific fields
+    const [newline, setNewline] = useState<TelnetNewline>("crnul");
+    const [comPort, setComPort] = useState(false);
+    const [tls, setTls] = useState(false);
+    const [tlsInsecure, setTlsInsecure] = useState(false);
+    const [tlsCaFile, setTlsCaFile] = useState("");
+
+    // Jump hosts (telnet and SSH), in ProxyJump syntax
+    const [jumpHostsText, setJumpHostsText] = useState("");
+
+    // Local command fields
+    const [program, setProgram] = useState("");
+    const [argsText, setArgsText] = useState("");
+    const [cwd, setCwd] = useState("");
+    const [envText, setEnvText] = useState("");
+
+    // Serial fields
+    const [serialPath, setSerialPath] = useState("");
+    const [serialDevices, setSerialDevices] = useState<SerialDevice[]>([]);
+    const [baudRate, setBaudRate] = useState("9600");
+    const [dataBits, setDataBits] = useState("8");
+    const [parity, setParity] = useState<NonNullable<ComPortSettings["parity"]>>("none");
+    const [stopBits, setStopBits] = useState<NonNullable<ComPortSettings["stopBits"]>>("1");
+    const [flowControl, setFlowControl] = useState<NonNullable<ComPortSettings["flowControl"]>>("none");
+
+    // Unix socket fields
+    const [socketPath, setSocketPath] = useState("");
+    const [socketTelnet, setSocketTelnet] = useState(false);
+
+    // Raw TCP fields
+    const [rawNewline, setRawNewline] = useState<RawNewline>("cr");
+    const [lfToCrlf, setLfToCrlf] = useState(false);
     
     const hostInputRef = useRef<HTMLInputElement>(null);
+    const programInputRef = useRef<HTMLInputElement>(null);
+    const serialInputRef = useRef<HTMLInputElement>(null);
+    const socketInputRef = useRef<HTMLInputElement>(null);
 
     // Load profiles when dialog opens
     const loadProfiles = useCallback(async () => {
@@ -108,16 +242,71 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
         }
     }, []);
 
+    // List serial devices when the serial tab is shown
+    const loadSerialDevices = useCallback(async () => {
+        try {
+            setSerialDevices(await invoke<SerialDevice[]>("list_serial_ports"));
+        } catch (err) {
+            console.error("Failed to list serial ports:", err);
+        }
+    }, []);
+
+    useEffect(() => {
+        if (isOpen && mode === "serial") {
+            loadSerialDevices();
+        }
+    }, [isOpen, mode, loadSerialDevices]);
+
+    // Offer ~/.ssh/config aliases on the SSH tab
+    useEffect(() => {
+        if (isOpen && mode === "ssh") {
+            invoke<string[]>("list_ssh_config_hosts")
+                .then(setSshConfigHosts)
+                .catch((err) => console.error("Failed to read ~/.ssh/config:", err));
+        }
+    }, [isOpen, mode]);
+
+    // Show what ~/.ssh/config makes of the host as it is typed
+    useEffect(() => {
+        if (!isOpen || mode !== "ssh" || !host.trim()) {
+            setSshHostConfig(null);
+            return;
+        }
+        const timer = setTimeout(() => {
+            invoke<SshHostConfig>("resolve_ssh_config", { host: host.trim() })
+                .then(setSshHostConfig)
+                .catch(() => setSshHostConfig(null));
+        }, 200);
+        return () => clearTimeout(timer);
+    }, [isOpen, mode, host]);
+
+    // List agent keys when agent authentication is selected
+    const loadAgentIdentities = useCallback(async () => {
+        try {
+            setAgentIdentities(await invoke<AgentIdentity[]>("list_agent_identities"));
+            setAgentError("");
+        } catch (err) {
+            setAgentIdentities([]);
+            setAgentError(String(err));
+        }
+    }, []);
+
+    useEffect(() => {
+        if (isOpen && mode === "ssh" && authType === "agent") {
+            loadAgentIdentities();
+        }
+    }, [isOpen, mode, authType, loadAgentIdentities]);
+
     useEffect(() => {
         if (isOpen) {
             loadProfiles();
-            setTimeout(() => hostInputRef.current?.focus(), 100);
-            // Reset default port based on mode
-            if (mode === "telnet") {
-                setPort("");
-            } else {
-                setPort("22");
-            }
+            const focusRef = mode === "local" ? programInputRef
+                : mode === "serial" ? serialInputRef
+                : mode === "unix" ? socketInputRef
+                : hostInputRef;
+            setTimeout(() => focusRef.current?.focus(), 100);
+            // Reset the port; SSH leaves it to ~/.ssh/config (or 22)
+            setPort("");
         }
     }, [isOpen, mode, loadProfiles]);
 
@@ -137,9 +326,7 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
         setMode(newMode);
         setError("");
         setSelectedProfileId(null);
-        if (newMode === "ssh" && (!port || port === "5000")) {
-            setPort("22");
-        } else if (newMode === "telnet" && port === "22") {
+        if ((newMode === "ssh" && port === "5000") || ((newMode === "telnet" || newMode === "raw") && port === "22")) {
             setPort("");
         }
     };
@@ -149,17 +336,81 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
         setSelectedProfileId(profile.id);
         setMode(profile.connection_type as ConnectionMode);
         setHost(profile.host);
-        setPort(profile.port.toString());
+        setPort(profile.port ? profile.port.toString() : "");
         setDeviceName(profile.name);
         setUsername(profile.username || "");
         setAuthType((profile.auth_method as SshAuthType) || "password");
         setKeyPath(profile.key_path || "~/.ssh/id_rsa");
+        setForwardsText((profile.forwards ?? []).map(formatForward).join("\n"));
+        setNewline(profile.telnet_options?.newline || "crnul");
+        setComPort(profile.telnet_options?.comPort ?? false);
+        setTls(!!profile.telnet_options?.tls);
+        setTlsInsecure(profile.telnet_options?.tls?.insecure ?? false);
+        setTlsCaFile(profile.telnet_options?.tls?.caFile || "");
+        setJumpHostsText(formatJumpHosts(profile.jump_hosts ?? []));
+        setProgram(profile.command?.program || "");
+        setArgsText(joinArgs(profile.command?.args ?? []));
+        setCwd(profile.command?.cwd || "");
+        setEnvText(Object.entries(profile.command?.env ?? {}).map(([key, value]) => `${key}=${value}`).join("\n"));
+        setSerialPath(profile.connection_type === "serial" ? profile.host : "");
+        setBaudRate(String(profile.serial_settings?.baudRate ?? 9600));
+        setDataBits(String(profile.serial_settings?.dataBits ?? 8));
+        setParity(profile.serial_settings?.parity ?? "none");
+        setStopBits(profile.serial_settings?.stopBits ?? "1");
+        setFlowControl(profile.serial_settings?.flowControl ?? "none");
+        setSocketPath(profile.connection_type === "unix" ? profile.host : "");
+        setSocketTelnet(profile.connection_type === "unix" && !!profile.telnet_options);
+        setRawNewline(profile.raw_options?.newline ?? "cr");
+        setLfToCrlf(profile.raw_options?.lfToCrlf ?? false);
         // Password is never stored - user must enter it
         setPassword("");
         setPassphrase("");
         setError("");
     };
 
+    // Build the local command from the form
+    const buildCommand = (): LocalCommand => ({
+        program: program.trim(),
+        args: splitArgs(argsText),
+        cwd: cwd.trim() || undefined,
+        env: parseEnv(envText),
+    });
+
+    // Build the serial line settings from the form
+    const buildSerialSettings = (): ComPortSettings => ({
+        baudRate: parseInt(baudRate, 10),
+        dataBits: parseInt(dataBits, 10),
+        parity,
+        stopBits,
+        flowControl,
+    });
+
+    // Build the telnet options from the form
+    const buildTelnetOptions = (): TelnetOptions => ({
+        newline,
+        comPort,
+        tls: tls ? { insecure: tlsInsecure, caFile: tlsCaFile.trim() || undefined } : null,
+    });
+
+    // Build the options for a telnet-framed socket (no TLS or RFC 2217 on a local socket)
+    const buildSocketTelnetOptions = (): TelnetOptions => ({ newline });
+
+    // Build the raw TCP line ending settings from the form
+    const buildRawOptions = (): RawOptions => ({
+        newline: rawNewline,
+        lfToCrlf,
+    });
+
+    // Toggle TLS, moving between the telnet and telnets default ports
+    const handleTlsChange = (enabled: boolean) => {
+        setTls(enabled);
+        if (enabled && (!port || port === "23")) {
+            setPort("992");
+        } else if (!enabled && port === "992") {
+            setPort("");
+        }
+    };
+
     // Save current form as a profile
     const saveProfile = async () => {
         if (!profileName.trim()) {
@@ -167,11 +418,32 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
             return;
         }
 
-        const portNum = parseInt(port, 10);
-        if (isNaN(portNum) || portNum < 1 || portNum > 65535) {
+        // Local, serial and socket profiles have no port; serial and socket ones keep the path as host
+        const hasPort = mode === "telnet" || mode === "ssh" || mode === "raw";
+        // SSH profiles without a port take it from ~/.ssh/config
+        const portFromConfig = mode === "ssh" && !port.trim();
+        const portNum = hasPort && !portFromConfig ? parseInt(port, 10) : 0;
+        const profileHost = mode === "local" ? ""
+            : mode === "serial" ? serialPath.trim()
+            : mode === "unix" ? socketPath.trim()
+            : host.trim();
+        const telnetOptions = mode === "telnet" ? buildTelnetOptions()
+            : mode === "unix" && socketTelnet ? buildSocketTelnetOptions()
+            : null;
+        const jumpHosts = mode === "telnet" || mode === "ssh" ? parseJumpHosts(jumpHostsText) : [];
+        if (hasPort && !portFromConfig && (isNaN(portNum) || portNum < 1 || portNum > 65535)) {
             setError("Please enter a valid port number");
             return;
         }
+        let forwards: ForwardSpec[] = [];
+        if (mode === "ssh") {
+            try {
+                forwards = parseForwardList(forwardsText);
+            } catch (err) {
+                setError(String((err as Error).message));
+                return;
+            }
+        }
 
         try {
             if (selectedProfileId) {
@@ -180,22 +452,34 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                     id: selectedProfileId,
                     name: profileName.trim(),
                     connectionType: mode,
-                    host: host.trim(),
+                    host: profileHost,
                     port: portNum,
                     username: mode === "ssh" ? username.trim() : null,
                     authMethod: mode === "ssh" ? authType : null,
                     keyPath: mode === "ssh" && authType === "publickey" ? keyPath.trim() : null,
+                    telnetOptions,
+                    command: mode === "local" ? buildCommand() : null,
+                    serialSettings: mode === "serial" ? buildSerialSettings() : null,
+                    rawOptions: mode === "raw" ? buildRawOptions() : null,
+                    jumpHosts: jumpHosts.length > 0 ? jumpHosts : null,
+                    forwards: forwards.length > 0 ? forwards : null,
                 });
             } else {
                 // Create new profile
                 await invoke("create_profile", {
                     name: profileName.trim(),
                     connectionType: mode,
-                    host: host.trim(),
+                    host: profileHost,
                     port: portNum,
                     username: mode === "ssh" ? username.trim() : null,
                     authMethod: mode === "ssh" ? authType : null,
                     keyPath: mode === "ssh" && authType === "publickey" ? keyPath.trim() : null,
+                    telnetOptions,
+                    command: mode === "local" ? buildCommand() : null,
+                    serialSettings: mode === "serial" ? buildSerialSettings() : null,
+                    rawOptions: mode === "raw" ? buildRawOptions() : null,
+                    jumpHosts: jumpHosts.length > 0 ? jumpHosts : null,
+                    forwards: forwards.length > 0 ? forwards : null,
                 });
             }
             
@@ -226,9 +510,59 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
         e.preventDefault();
         setError("");
 
-        // Validate port
+        if (mode === "local") {
+            if (!program.trim()) {
+                setError("Please enter a program to run");
+                return;
+            }
+
+            // Create local session running the program
+            addCommandSession(buildCommand(), deviceName.trim() || undefined);
+            resetForm();
+            onClose();
+            return;
+        }
+
+        if (mode === "serial") {
+            if (!serialPath.trim()) {
+                setError("Please enter a serial device");
+                return;
+            }
+
+            const baud = parseInt(baudRate, 10);
+            if (isNaN(baud) || baud < 1) {
+                setError("Please enter a valid baud rate");
+                return;
+            }
+
+            // Create serial session on the local port
+            addSerialSession({ path: serialPath.trim(), settings: buildSerialSettings() }, deviceName.trim() || undefined);
+            resetForm();
+            onClose();
+            return;
+        }
+
+        if (mode === "unix") {
+            if (!socketPath.trim()) {
+                setError("Please enter a socket path");
+                return;
+            }
+
+            // Create session on the VM's console socket
+            addUnixSession({
+                path: socketPath.trim(),
+                telnet: socketTelnet,
+                options: socketTelnet ? buildSocketTelnetOptions() : undefined,
+            }, deviceName.trim() || undefined);
+            resetForm();
+            onClose();
+            return;
+        }
+
+        // Validate port (SSH may leave it to ~/.ssh/config)
+        const portFromConfig = mode === "ssh" && !port.trim();
         const portNum = parseInt(port, 10);
-        if (isNaN(portNum) || portNum < 1 || portNum > 65535) {
+        if (!portFromConfig && (isNaN(portNum) || portNum < 1 || portNum > 65535)) {
             setError("Please enter a valid port number (1-65535)");
             return;
         }
@@ -239,16 +573,16 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
             return;
         }
 
+        const jumpHosts = parseJumpHosts(jumpHostsText);
+
         if (mode === "telnet") {
             // Create telnet session
-            addTelnetSession(host.trim(), portNum, deviceName.trim() || undefined);
+            addTelnetSession(host.trim(), portNum, deviceName.trim() || undefined, buildTelnetOptions(), jumpHosts);
+        } else if (mode === "raw") {
+            // Create raw TCP session
+            addRawSession({ host: host.trim(), port: portNum, options: buildRawOptions() }, deviceName.trim() || undefined);
         } else {
-            // Validate SSH-specific fields
-            if (!username.trim()) {
-                setError("Please enter a username");
-                return;
-            }
-
+            // Validate SSH-specific fields; user and port may come from ~/.ssh/config
             if (authType === "password" && !password) {
                 setError("Please enter a password");
                 return;
@@ -259,15 +593,25 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                 return;
             }
 
+            let forwards: ForwardSpec[];
+            try {
+                forwards = parseForwardList(forwardsText);
+            } catch (err) {
+                setError(String((err as Error).message));
+                return;
+            }
+
             // Create SSH session
             addSshSession({
                 host: host.trim(),
-                port: portNum,
-                username: username.trim(),
+                port: portFromConfig ? undefined : portNum,
+                username: username.trim() || undefined,
                 authType,
                 password: authType === "password" ? password : undefined,
                 keyPath: authType === "publickey" ? keyPath.trim() : undefined,
                 passphrase: authType === "publickey" && passphrase ? passphrase : undefined,
+                jumpHosts: jumpHosts.length > 0 ? jumpHosts : undefined,
+                forwards: forwards.length > 0 ? forwards : undefined,
             }, deviceName.trim() || undefined);
         }
 
@@ -284,6 +628,27 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
         setPassword("");
         setKeyPath("~/.ssh/id_rsa");
         setPassphrase("");
+        setForwardsText("");
+        setNewline("crnul");
+        setComPort(false);
+        setTls(false);
+        setTlsInsecure(false);
+        setTlsCaFile("");
+        setJumpHostsText("");
+        setProgram("");
+        setArgsText("");
+        setCwd("");
+        setEnvText("");
+        setSerialPath("");
+        setBaudRate("9600");
+        setDataBits("8");
+        setParity("none");
+        setStopBits("1");
+        setFlowControl("none");
+        setSocketPath("");
+        setSocketTelnet(false);
+        setRawNewline("cr");
+        setLfToCrlf(false);
         setError("");
         setSelectedProfileId(null);
         setProfileName("");
@@ -291,7 +656,10 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
     };
 
     const openSaveDialog = () => {
-        setProfileName(deviceName || `${host}:${port}`);
+        setProfileName(deviceName || (mode === "local" ? program
+            : mode === "serial" ? serialPath
+            : mode === "unix" ? socketPath
+            : `${host}:${port}`));
         setView("save-profile");
     };
 
@@ -364,16 +732,45 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                                     <span className="preview-label">Type:</span>
                                     <span className="preview-value">{mode.toUpperCase()}</span>
                                 </div>
-                                <div className="preview-row">
-                                    <span className="preview-label">Host:</span>
-                                    <span className="preview-value">{host}:{port}</span>
-                                </div>
+                                {mode === "local" ? (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Command:</span>
+                                        <span className="preview-value">{program} {argsText}</span>
+                                    </div>
+                                ) : mode === "serial" ? (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Device:</span>
+                                        <span className="preview-value">{serialPath} @ {baudRate}</span>
+                                    </div>
+                                ) : mode === "unix" ? (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Socket:</span>
+                                        <span className="preview-value">{socketPath}{socketTelnet ? " (telnet)" : ""}</span>
+                                    </div>
+                                ) : (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Host:</span>
+                                        <span className="preview-value">{host}:{port}</span>
+                                    </div>
+                                )}
                                 {mode === "ssh" && username && (
                                     <div className="preview-row">
                                         <span className="preview-label">User:</span>
                                         <span className="preview-value">{username}</span>
                                     </div>
                                 )}
+                                {mode === "ssh" && forwardsText.trim() && (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Forwards:</span>
+                                        <span className="preview-value">{forwardsText.split("\n").filter(line => line.trim()).join(", ")}</span>
+                                    </div>
+                                )}
+                                {(mode === "telnet" || mode === "ssh") && jumpHostsText.trim() && (
+                                    <div className="preview-row">
+                                        <span className="preview-label">Via:</span>
+                                        <span className="preview-value">{jumpHostsText}</span>
+                                    </div>
+                                )}
                             </div>
 
                             {error && <div className="form-error">{error}</div>}
@@ -406,61 +803,466 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                                 >
                                     SSH
                                 </button>
+                                <button
+                                    type="button"
+                                    className={`dialog-tab ${mode === "local" ? "active" : ""}`}
+                                    onClick={() => handleModeChange("local")}
+                                >
+                                    Command
+                                </button>
+                                <button
+                                    type="button"
+                                    className={`dialog-tab ${mode === "serial" ? "active" : ""}`}
+                                    onClick={() => handleModeChange("serial")}
+                                >
+                                    Serial
+                                </button>
+                                <button
+                                    type="button"
+                                    className={`dialog-tab ${mode === "unix" ? "active" : ""}`}
+                                    onClick={() => handleModeChange("unix")}
+                                >
+                                    Socket
+                                </button>
+                                <button
+                                    type="button"
+                                    className={`dialog-tab ${mode === "raw" ? "active" : ""}`}
+                                    onClick={() => handleModeChange("raw")}
+                                >
+                                    Raw TCP
+                                </button>
                             </div>
 
                             <form onSubmit={handleSubmit}>
                                 <div className="dialog-body">
                                     <p className="dialog-description">
-                                        {mode === "telnet" 
+                                        {mode === "telnet"
                                             ? "Connect to a GNS3/EVE-NG device console via Telnet."
-                                            : "Connect to a network device or server via SSH."
+                                            : mode === "ssh"
+                                                ? "Connect to a network device or server via SSH."
+                                                : mode === "serial"
+                                                    ? "Open a console cable plugged into this machine."
+                                                    : mode === "unix"
+                                                        ? "Attach to a QEMU or libvirt console exposed as a Unix socket."
+                                                        : mode === "raw"
+                                                            ? "Open a plain TCP connection with no telnet negotiation."
+                                                            : "Run a local program such as virsh console or docker exec in a new tab."
                                         }
                                     </p>
 
-                                    {/* Common Fields */}
-                                    <div className="form-row">
-                                        <div className="form-group form-group-flex">
-                                            <label htmlFor="host">Host</label>
-                                            <input
-                                                ref={hostInputRef}
-                                                id="host"
-                                                type="text"
-                                                value={host}
-                                                onChange={(e) => setHost(e.target.value)}
-                                                placeholder={mode === "telnet" ? "localhost" : "192.168.1.1"}
-                                                autoComplete="off"
-                                            />
+                                    {/* Host fields (not used by local commands or serial ports) */}
+                                    {(mode === "telnet" || mode === "ssh" || mode === "raw") && (
+                                        <div className="form-row">
+                                            <div className="form-group form-group-flex">
+                                                <label htmlFor="host">Host</label>
+                                                <input
+                                                    ref={hostInputRef}
+                                                    id="host"
+                                                    type="text"
+                                                    value={host}
+                                                    onChange={(e) => setHost(e.target.value)}
+                                                    placeholder={mode === "telnet" ? "localhost" : "192.168.1.1"}
+                                                    list={mode === "ssh" ? "sshConfigHosts" : undefined}
+                                                    autoComplete="off"
+                                                />
+                                                {mode === "ssh" && (
+                                                    <datalist id="sshConfigHosts">
+                                                        {sshConfigHosts.map(alias => (
+                                                            <option key={alias} value={alias} />
+                                                        ))}
+                                                    </datalist>
+                                                )}
+                                            </div>
+
+                                            <div className="form-group form-group-sm">
+                                                <label htmlFor="port">Port</label>
+                                                <input
+                                                    id="port"
+                                                    type="number"
+                                                    value={port}
+                                                    onChange={(e) => setPort(e.target.value)}
+                                                    placeholder={mode === "ssh" ? "22" : "5000"}
+                                                    min="1"
+                                                    max="65535"
+                                                    autoComplete="off"
+                                                />
+                                            </div>
                                         </div>
+                                    )}
 
-                                        <div className="form-group form-group-sm">
-                                            <label htmlFor="port">Port</label>
+                                    {/* Jump hosts (SSH bastions in front of the device) */}
+                                    {(mode === "telnet" || mode === "ssh") && (
+                                        <div className="form-group">
+                                            <label htmlFor="jumpHosts">Jump Hosts</label>
                                             <input
-                                                id="port"
-                                                type="number"
-                                                value={port}
-                                                onChange={(e) => setPort(e.target.value)}
-                                                placeholder={mode === "telnet" ? "5000" : "22"}
-                                                min="1"
-                                                max="65535"
+                                                id="jumpHosts"
+                                                type="text"
+                                                value={jumpHostsText}
+                                                onChange={(e) => setJumpHostsText(e.target.value)}
+                                                placeholder={mode === "ssh" && sshHostConfig?.proxyJump ? sshHostConfig.proxyJump : "user@bastion:22, core-gw"}
+                                                list={mode === "ssh" ? "sshConfigHosts" : undefined}
                                                 autoComplete="off"
                                             />
+                                            <span className="form-hint">
+                                                {mode === "ssh" && !jumpHostsText.trim() && sshHostConfig?.proxyJump
+                                                    ? `Empty uses ProxyJump from ~/.ssh/config: ${sshHostConfig.proxyJump}`
+                                                    : "SSH bastions to tunnel through, in order; logins use ~/.ssh/config and the ssh-agent"}
+                                            </span>
                                         </div>
-                                    </div>
+                                    )}
+
+                                    {/* Serial port fields */}
+                                    {mode === "serial" && (
+                                        <>
+                                            <div className="form-group">
+                                                <label htmlFor="serialPath">Device *</label>
+                                                <input
+                                                    ref={serialInputRef}
+                                                    id="serialPath"
+                                                    type="text"
+                                                    list="serialDevices"
+                                                    value={serialPath}
+                                                    onChange={(e) => setSerialPath(e.target.value)}
+                                                    onFocus={loadSerialDevices}
+                                                    placeholder="/dev/ttyUSB0"
+                                                    autoComplete="off"
+                                                />
+                                                <datalist id="serialDevices">
+                                                    {serialDevices.map(device => (
+                                                        <option key={device.path} value={device.path}>
+                                                            {device.description || device.kind}
+                                                        </option>
+                                                    ))}
+                                                </datalist>
+                                                <span className="form-hint">
+                                                    {serialDevices.length > 0
+                                                        ? `${serialDevices.length} device(s) found`
+                                                        : "No serial devices found; check the cable and driver"}
+                                                </span>
+                                            </div>
+
+                                            <div className="form-row">
+                                                <div className="form-group form-group-flex">
+                                                    <label htmlFor="baudRate">Baud Rate</label>
+                                                    <input
+                                                        id="baudRate"
+                                                        type="number"
+                                                        list="baudRates"
+                                                        value={baudRate}
+                                                        onChange={(e) => setBaudRate(e.target.value)}
+                                                        min="1"
+                                                        autoComplete="off"
+                                                    />
+                                                    <datalist id="baudRates">
+                                                        {BAUD_RATES.map(rate => <option key={rate} value={rate} />)}
+                                                    </datalist>
+                                                </div>
+                                                <div className="form-group form-group-sm">
+                                                    <label htmlFor="dataBits">Data</label>
+                                                    <select id="dataBits" value={dataBits} onChange={(e) => setDataBits(e.target.value)}>
+                                                        <option value="8">8</option>
+                                                        <option value="7">7</option>
+                                                        <option value="6">6</option>
+                                                        <option value="5">5</option>
+                                                    </select>
+                                                </div>
+                                                <div className="form-group form-group-sm">
+                                                    <label htmlFor="parity">Parity</label>
+                                                    <select id="parity" value={parity} onChange={(e) => setParity(e.target.value as typeof parity)}>
+                                                        <option value="none">None</option>
+                                                        <option value="even">Even</option>
+                                                        <option value="odd">Odd</option>
+                                                    </select>
+                                                </div>
+                                                <div className="form-group form-group-sm">
+                                                    <label htmlFor="stopBits">Stop</label>
+                                                    <select id="stopBits" value={stopBits} onChange={(e) => setStopBits(e.target.value as typeof stopBits)}>
+                                                        <option value="1">1</option>
+                                                        <option value="2">2</option>
+                                                    </select>
+                                                </div>
+                                            </div>
+
+                                            <div className="form-group">
+                                                <label>Flow Control</label>
+                                                <div className="auth-type-selector">
+                                                    {(["none", "software", "hardware"] as const).map(flow => (
+                                                        <label key={flow} className="radio-label">
+                                                            <input
+                                                                type="radio"
+                                                                name="flowControl"
+                                                                value={flow}
+                                                                checked={flowControl === flow}
+                                                                onChange={() => setFlowControl(flow)}
+                                                            />
+                                                            {flow === "none" ? "None" : flow === "software" ? "XON/XOFF" : "RTS/CTS"}
+                                                        </label>
+                                                    ))}
+                                                </div>
+                                            </div>
+                                        </>
+                                    )}
+
+                                    {/* Raw TCP fields */}
+                                    {mode === "raw" && (
+                                        <>
+                                            <div className="form-group">
+                                                <label>Enter Key Sends</label>
+                                                <div className="auth-type-selector">
+                                                    {(["cr", "lf", "crlf"] as const).map(ending => (
+                                                        <label key={ending} className="radio-label">
+                                                            <input
+                                                                type="radio"
+                                                                name="rawNewline"
+                                                                value={ending}
+                                                                checked={rawNewline === ending}
+                                                                onChange={() => setRawNewline(ending)}
+                                                            />
+                                                            {ending === "cr" ? "CR" : ending === "lf" ? "LF" : "CR LF"}
+                                                        </label>
+                                                    ))}
+                                                </div>
+                                            </div>
+                                            <div className="form-group">
+                                                <label className="radio-label">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={lfToCrlf}
+                                                        onChange={(e) => setLfToCrlf(e.target.checked)}
+                                                    />
+                                                    Show LF as CR LF
+                                                </label>
+                                                <span className="form-hint">
+                                                    For services that end lines with LF only, so output doesn't staircase
+                                                </span>
+                                            </div>
+                                        </>
+                                    )}
+
+                                    {/* Unix socket fields */}
+                                    {mode === "unix" && (
+                                        <>
+                                            <div className="form-group">
+                                                <label htmlFor="socketPath">Socket Path *</label>
+                                                <input
+                                                    ref={socketInputRef}
+                                                    id="socketPath"
+                                                    type="text"
+                                                    value={socketPath}
+                                                    onChange={(e) => setSocketPath(e.target.value)}
+                                                    placeholder="/tmp/r1.sock"
+                                                    autoComplete="off"
+                                                />
+                                                <span className="form-hint">
+                                                    e.g. QEMU's -serial unix:/tmp/r1.sock,server
+                                                </span>
+                                            </div>
+                                            <div className="form-group">
+                                                <label className="radio-label">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={socketTelnet}
+                                                        onChange={(e) => setSocketTelnet(e.target.checked)}
+                                                    />
+                                                    Telnet framing
+                                                </label>
+                                                <span className="form-hint">
+                                                    Enable when the socket was created with QEMU's telnet flag
+                                                </span>
+                                            </div>
+                                            {socketTelnet && (
+                                                <div className="form-group">
+                                                    <label>Enter Key Sends</label>
+                                                    <div className="auth-type-selector">
+                                                        <label className="radio-label">
+                                                            <input
+                                                                type="radio"
+                                                                name="socketNewline"
+                                                                value="crnul"
+                                                                checked={newline === "crnul"}
+                                                                onChange={() => setNewline("crnul")}
+                                                            />
+                                                            CR NUL
+                                                        </label>
+                                                        <label className="radio-label">
+                                                            <input
+                                                                type="radio"
+                                                                name="socketNewline"
+                                                                value="crlf"
+                                                                checked={newline === "crlf"}
+                                                                onChange={() => setNewline("crlf")}
+                                                            />
+                                                            CR LF
+                                                        </label>
+                                                    </div>
+                                                </div>
+                                            )}
+                                        </>
+                                    )}
+
+                                    {/* Local command fields */}
+                                    {mode === "local" && (
+                                        <>
+                                            <div className="form-group">
+                                                <label htmlFor="program">Program *</label>
+                                                <input
+                                                    ref={programInputRef}
+                                                    id="program"
+                                                    type="text"
+                                                    value={program}
+                                                    onChange={(e) => setProgram(e.target.value)}
+                                                    placeholder="virsh"
+                                                    autoComplete="off"
+                                                />
+                                            </div>
+
+                                            <div className="form-group">
+                                                <label htmlFor="args">Arguments</label>
+                                                <input
+                                                    id="args"
+                                                    type="text"
+                                                    value={argsText}
+                                                    onChange={(e) => setArgsText(e.target.value)}
+                                                    placeholder="console R1"
+                                                    autoComplete="off"
+                                                />
+                                                <span className="form-hint">
+                                                    Separated by spaces; quote arguments that contain spaces
+                                                </span>
+                                            </div>
+
+                                            <div className="form-group">
+                                                <label htmlFor="cwd">Working Directory (optional)</label>
+                                                <input
+                                                    id="cwd"
+                                                    type="text"
+                                                    value={cwd}
+                                                    onChange={(e) => setCwd(e.target.value)}
+                                                    placeholder="~"
+                                                    autoComplete="off"
+                                                />
+                                            </div>
+
+                                            <div className="form-group">
+                                                <label htmlFor="env">Environment (optional)</label>
+                                                <textarea
+                                                    id="env"
+                                                    value={envText}
+                                                    onChange={(e) => setEnvText(e.target.value)}
+                                                    placeholder="LIBVIRT_DEFAULT_URI=qemu:///system"
+                                                    rows={3}
+                                                />
+                                                <span className="form-hint">
+                                                    One KEY=VALUE per line
+                                                </span>
+                                            </div>
+                                        </>
+                                    )}
+
+                                    {/* Telnet-specific fields */}
+                                    {mode === "telnet" && (
+                                        <>
+                                            <div className="form-group">
+                                                <label>Enter Key Sends</label>
+                                                <div className="auth-type-selector">
+                                                    <label className="radio-label">
+                                                        <input
+                                                            type="radio"
+                                                            name="newline"
+                                                            value="crnul"
+                                                            checked={newline === "crnul"}
+                                                            onChange={() => setNewline("crnul")}
+                                                        />
+                                                        CR NUL
+                                                    </label>
+                                                    <label className="radio-label">
+                                                        <input
+                                                            type="radio"
+                                                            name="newline"
+                                                            value="crlf"
+                                                            checked={newline === "crlf"}
+                                                            onChange={() => setNewline("crlf")}
+                                                        />
+                                                        CR LF
+                                                    </label>
+                                                </div>
+                                            </div>
+                                            <div className="form-group">
+                                                <label className="radio-label">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={comPort}
+                                                        onChange={(e) => setComPort(e.target.checked)}
+                                                    />
+                                                    RFC 2217 serial control
+                                                </label>
+                                                <span className="form-hint">
+                                                    For console servers (ser2net, Opengear, Moxa); use :serial and :sbreak to control the port
+                                                </span>
+                                            </div>
+                                            <div className="form-group">
+                                                <label className="radio-label">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={tls}
+                                                        onChange={(e) => handleTlsChange(e.target.checked)}
+                                                    />
+                                                    Use TLS (telnets)
+                                                </label>
+                                                <span className="form-hint">
+                                                    Usually port 992; the certificate is checked against the system CAs
+                                                </span>
+                                            </div>
+                                            {tls && (
+                                                <>
+                                                    <div className="form-group">
+                                                        <label htmlFor="tlsCaFile">CA Certificate</label>
+                                                        <input
+                                                            id="tlsCaFile"
+                                                            type="text"
+                                                            value={tlsCaFile}
+                                                            onChange={(e) => setTlsCaFile(e.target.value)}
+                                                            placeholder="Optional, e.g. ~/certs/console-ca.pem"
+                                                        />
+                                                    </div>
+                                                    <div className="form-group">
+                                                        <label className="radio-label">
+                                                            <input
+                                                                type="checkbox"
+                                                                checked={tlsInsecure}
+                                                                onChange={(e) => setTlsInsecure(e.target.checked)}
+                                                            />
+                                                            Accept self-signed certificates
+                                                        </label>
+                                                        <span className="form-hint">
+                                                            Skips certificate and hostname checks; only use on trusted networks
+                                                        </span>
+                                                    </div>
+                                                </>
+                                            )}
+                                        </>
+                                    )}
 
                                     {/* SSH-specific fields */}
                                     {mode === "ssh" && (
                                         <>
                                             <div className="form-group">
-                                                <label htmlFor="username">Username *</label>
+                                                <label htmlFor="username">Username</label>
                                                 <input
                                                     id="username"
                                                     type="text"
                                                     value={username}
                                                     onChange={(e) => setUsername(e.target.value)}
-                                                    placeholder="admin"
+                                                    placeholder={sshHostConfig?.user || "admin"}
                                                     autoComplete="off"
-                                                    required
                                                 />
+                                                {sshHostConfig && (
+                                                    <span className="form-hint">
+                                                        ~/.ssh/config: {sshHostConfig.user || "(local user)"}@{sshHostConfig.hostname}:{sshHostConfig.port ?? 22}
+                                                        {sshHostConfig.proxyJump && ` via ${sshHostConfig.proxyJump}`}
+                                                        {sshHostConfig.serverAliveInterval && `, keepalive ${sshHostConfig.serverAliveInterval}s`}
+                                                    </span>
+                                                )}
                                             </div>
 
                                             <div className="form-group">
@@ -486,6 +1288,36 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                                                         />
                                                         <KeyIcon /> SSH Key
                                                     </label>
+                                                    <label className="radio-label">
+                                                        <input
+                                                            type="radio"
+                                                            name="authType"
+                                                            value="agent"
+                                                            checked={authType === "agent"}
+                                                            onChange={() => setAuthType("agent")}
+                                                        />
+                                                        Agent
+                                                    </label>
+                                                    <label className="radio-label">
+                                                        <input
+                                                            type="radio"
+                                                            name="authType"
+                                                            value="keyboard-interactive"
+                                                            checked={authType === "keyboard-interactive"}
+                                                            onChange={() => setAuthType("keyboard-interactive")}
+                                                        />
+                                                        Interactive
+                                                    </label>
+                                                    <label className="radio-label">
+                                                        <input
+                                                            type="radio"
+                                                            name="authType"
+                                                            value="config"
+                                                            checked={authType === "config"}
+                                                            onChange={() => setAuthType("config")}
+                                                        />
+                                                        SSH Config
+                                                    </label>
                                                 </div>
                                             </div>
 
@@ -501,6 +1333,44 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                                                         autoComplete="off"
                                                     />
                                                 </div>
+                                            ) : authType === "config" ? (
+                                                <div className="form-group">
+                                                    <span className="form-hint">
+                                                        {sshHostConfig && sshHostConfig.identityFiles.length > 0
+                                                            ? `Keys: ${sshHostConfig.identityFiles.join(", ")}${sshHostConfig.identitiesOnly ? " (IdentitiesOnly)" : " and the ssh-agent's keys"}`
+                                                            : "The ssh-agent's keys, then ~/.ssh/id_rsa, id_ecdsa and id_ed25519"}
+                                                    </span>
+                                                </div>
+                                            ) : authType === "keyboard-interactive" ? (
+                                                <div className="form-group">
+                                                    <span className="form-hint">
+                                                        The device's prompts (password, token, new password) are asked for when connecting.
+                                                        Use this for AAA, RADIUS/TACACS+ and OTP logins.
+                                                    </span>
+                                                </div>
+                                            ) : authType === "agent" ? (
+                                                <div className="form-group">
+                                                    <label>Agent Keys</label>
+                                                    {agentError ? (
+                                                        <div className="form-error">{agentError}</div>
+                                                    ) : agentIdentities.length === 0 ? (
+                                                        <span className="form-hint">
+                                                            The agent holds no keys; add one with ssh-add
+                                                        </span>
+                                                    ) : (
+                                                        <ul className="agent-identities">
+                                                            {agentIdentities.map(identity => (
+                                                                <li key={identity.fingerprint}>
+                                                                    <span>{identity.comment || identity.keyType}</span>
+                                                                    <code>{identity.fingerprint}</code>
+                                                                </li>
+                                                            ))}
+                                                        </ul>
+                                                    )}
+                                                    <span className="form-hint">
+                                                        Each key is tried in turn; no passphrase is asked for
+                                                    </span>
+                                                </div>
                                             ) : (
                                                 <>
                                                     <div className="form-group">
@@ -530,6 +1400,20 @@ export function ConnectDialog({ isOpen, onClose }: ConnectDialogProps) {
                                                     </div>
                                                 </>
                                             )}
+
+                                            <div className="form-group">
+                                                <label htmlFor="forwards">Port Forwards (optional)</label>
+                                                <textarea
+                                                    id="forwards"
+                                                    value={forwardsText}
+                                                    onChange={(e) => setForwardsText(e.target.value)}
+                                                    placeholder={"-L 8443:10.0.0.1:443\n-R 5000:localhost:5000\n-D 1080"}
+                                                    rows={3}
+                                                />
+                                                <span className="form-hint">
+                                                    One per line, as for ssh: -L local, -R remote, -D SOCKS proxy
+                                                </span>
+                                            </div>
                                         </>
                                     )}
 
@@ -0,0 +1,88 @@
+/**
+ * HostKeyDialog Component
+ *
+ * Trust-on-first-use prompt for SSH host keys that are not in
+ * ~/.ssh/known_hosts or Packet's own known_hosts file.
+ * The backend emits ssh-host-key and blocks the connection until
+ * respond_host_key is called; prompts from parallel connections are queued.
+ */
+
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { HostKeyPrompt } from "../types/terminal";
+
+/**
+ * Shield icon for dialog header
+ */
+function ShieldIcon() {
+    return (
+        <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <path d="M12 22s8-4 8-10V5l-8-3-8 3v7c0 6 8 10 8 10z" />
+        </svg>
+    );
+}
+
+export function HostKeyDialog() {
+    const [prompts, setPrompts] = useState<HostKeyPrompt[]>([]);
+
+    useEffect(() => {
+        const unlistenPromise = listen<HostKeyPrompt>("ssh-host-key", (event) => {
+            setPrompts((prev) => [...prev, event.payload]);
+        });
+
+        return () => {
+            unlistenPromise.then((unlisten) => unlisten());
+        };
+    }, []);
+
+    const respond = useCallback((prompt: HostKeyPrompt, trust: boolean) => {
+        setPrompts((prev) => prev.filter((p) => p.requestId !== prompt.requestId));
+        invoke("respond_host_key", { requestId: prompt.requestId, trust })
+            .catch((err) => console.error("[SSH] Failed to answer host key prompt:", err));
+    }, []);
+
+    const prompt = prompts[0];
+    if (!prompt) return null;
+
+    const target = prompt.port === 22 ? prompt.host : `${prompt.host}:${prompt.port}`;
+
+    return (
+        <div className="dialog-overlay">
+            <div className="dialog-content dialog-content-lg">
+                <div className="dialog-header">
+                    <ShieldIcon />
+                    <h2>Unknown Host Key</h2>
+                </div>
+
+                <div className="dialog-body">
+                    <p className="dialog-description">
+                        The authenticity of <strong>{target}</strong> can't be established.
+                        Check the fingerprint before trusting this host.
+                    </p>
+
+                    {prompt.otherKeyKnown && (
+                        <div className="form-error host-key-warning">
+                            A key of a different type is already trusted for this host.
+                            The server may have been reinstalled, or someone may be intercepting the connection.
+                        </div>
+                    )}
+
+                    <div className="form-group">
+                        <label>{prompt.keyType} key fingerprint</label>
+                        <code className="host-key-fingerprint">{prompt.fingerprint}</code>
+                    </div>
+                </div>
+
+                <div className="dialog-footer">
+                    <button type="button" className="btn btn-secondary" onClick={() => respond(prompt, false)}>
+                        Reject
+                    </button>
+                    <button type="button" className="btn btn-primary" onClick={() => respond(prompt, true)}>
+                        Trust and Connect
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,136 @@
+/**
+ * SessionsDialog Component
+ *
+ * Lists the sessions kept in the session daemon, including orphaned ones
+ * left behind by a closed window, and attaches panes to them or detaches
+ * panes from them. Also switches keeping sessions on and off.
+ */
+
+import { useState, useEffect, useCallback } from "react";
+import { useTerminals } from "../context/TerminalContext";
+import { DaemonSessionInfo } from "../types/terminal";
+import { keepSessionsEnabled, listDaemonSessions, setKeepSessions } from "../utils/daemon";
+
+interface SessionsDialogProps {
+    isOpen: boolean;
+    onClose: () => void;
+}
+
+/**
+ * Stacked layers icon for dialog header
+ */
+function LayersIcon() {
+    return (
+        <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <path d="M12 2 2 7l10 5 10-5-10-5z" />
+            <path d="m2 17 10 5 10-5" />
+            <path d="m2 12 10 5 10-5" />
+        </svg>
+    );
+}
+
+export function SessionsDialog({ isOpen, onClose }: SessionsDialogProps) {
+    const { sessions, attachDaemonSession, detachSession } = useTerminals();
+    const [daemonSessions, setDaemonSessions] = useState<DaemonSessionInfo[]>([]);
+    const [keepSessions, setKeepSessionsState] = useState(keepSessionsEnabled);
+    const [error, setError] = useState("");
+
+    const refresh = useCallback(() => {
+        listDaemonSessions().then(setDaemonSessions);
+    }, []);
+
+    useEffect(() => {
+        if (isOpen) {
+            setError("");
+            refresh();
+        }
+    }, [isOpen, refresh]);
+
+    const toggleKeepSessions = useCallback((enabled: boolean) => {
+        setKeepSessionsState(enabled);
+        setKeepSessions(enabled)
+            .then(refresh)
+            .catch((err) => setError(String(err)));
+    }, [refresh]);
+
+    const detach = useCallback((paneId: string) => {
+        detachSession(paneId)
+            .then(refresh)
+            .catch((err) => setError(`Failed to detach: ${err}`));
+    }, [detachSession, refresh]);
+
+    if (!isOpen) return null;
+
+    return (
+        <div className="dialog-overlay" onClick={onClose}>
+            <div className="dialog-content dialog-content-lg" onClick={(e) => e.stopPropagation()}>
+                <div className="dialog-header">
+                    <LayersIcon />
+                    <h2>Sessions</h2>
+                </div>
+
+                <div className="dialog-body">
+                    <div className="form-group">
+                        <label className="radio-label">
+                            <input
+                                type="checkbox"
+                                checked={keepSessions}
+                                onChange={(e) => toggleKeepSessions(e.target.checked)}
+                            />
+                            Keep sessions running after Packet closes
+                        </label>
+                        <span className="form-hint">
+                            New sessions are opened in a background process and can be detached and attached again later
+                        </span>
+                    </div>
+
+                    {error && <div className="form-error">{error}</div>}
+
+                    {daemonSessions.length === 0 ? (
+                        <p className="dialog-description">No sessions are running in the background.</p>
+                    ) : (
+                        <ul className="daemon-sessions">
+                            {daemonSessions.map((info) => {
+                                const pane = sessions.find((s) => s.sessionId === info.sessionId);
+                                const state = pane ? "shown here" : info.attached ? "attached elsewhere" : "detached";
+                                return (
+                                    <li key={info.sessionId}>
+                                        <div className="profile-info">
+                                            <span className="profile-name">{pane?.name ?? info.label}</span>
+                                            <span className="profile-type">{info.kind} - {info.status} - {state}</span>
+                                        </div>
+                                        {pane ? (
+                                            <button type="button" className="btn btn-ghost" onClick={() => detach(pane.id)}>
+                                                Detach
+                                            </button>
+                                        ) : (
+                                            <button
+                                                type="button"
+                                                className="btn btn-ghost"
+                                                onClick={() => {
+                                                    attachDaemonSession(info);
+                                                    onClose();
+                                                }}
+                                            >
+                                                Attach
+                                            </button>
+                                        )}
+                                    </li>
+                                );
+                            })}
+                        </ul>
+                    )}
+                </div>
+
+                <div className="dialog-footer">
+                    <button type="button" className="btn btn-secondary" onClick={refresh}>
+                        Refresh
+                    </button>
+                    <button type="button" className="btn btn-primary" onClick={onClose}>
+                        Close
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -11,8 +11,10 @@ import { useState, useCallback, useMemo, useRef, useEffect } from "react";
 import { useTerminals } from "../context/TerminalContext";
 import { TerminalPanel, getSearchAddon } from "./TerminalPanel";
 import { ConnectDialog } from "./ConnectDialog";
+import { SessionsDialog } from "./SessionsDialog";
 import { useKeyboardShortcuts } from "../hooks/useKeyboardShortcuts";
 import { useResizableGrid } from "../hooks/useResizableGrid";
+import { ConnectionType } from "../types/terminal";
 
 /**
  * Icon component for adding new terminals
@@ -38,6 +40,19 @@ function ConnectIcon() {
     );
 }
 
+/**
+ * Icon for the kept sessions dialog
+ */
+function SessionsIcon() {
+    return (
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <path d="M12 2 2 7l10 5 10-5-10-5z" />
+            <path d="m2 17 10 5 10-5" />
+            <path d="m2 12 10 5 10-5" />
+        </svg>
+    );
+}
+
 /**
  * Close icon for tabs
  */
@@ -103,7 +118,7 @@ function FolderIcon() {
 /**
  * Connection type indicator for tabs
  */
-function ConnectionTypeIndicator({ type, enabled }: { type: "local" | "telnet" | "ssh"; enabled: boolean }) {
+function ConnectionTypeIndicator({ type, enabled }: { type: ConnectionType; enabled: boolean }) {
     if (type === "telnet") {
         return (
             <span className={`tab-type-indicator telnet ${enabled ? "broadcast" : ""}`} title="Telnet connection">
@@ -125,6 +140,36 @@ function ConnectionTypeIndicator({ type, enabled }: { type: "local" | "telnet" |
             </span>
         );
     }
+    if (type === "serial") {
+        return (
+            <span className={`tab-type-indicator serial ${enabled ? "broadcast" : ""}`} title="Serial console">
+                <svg width="10" height="10" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2.5">
+                    <rect x="3" y="7" width="18" height="10" rx="2" />
+                    <path d="M7 12h.01M10 12h.01M14 12h.01M17 12h.01" />
+                </svg>
+            </span>
+        );
+    }
+    if (type === "raw") {
+        return (
+            <span className={`tab-type-indicator raw ${enabled ? "broadcast" : ""}`} title="Raw TCP connection">
+                <svg width="10" height="10" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2.5">
+                    <path d="M8 6l-6 6 6 6" />
+                    <path d="M16 6l6 6-6 6" />
+                </svg>
+            </span>
+        );
+    }
+    if (type === "unix") {
+        return (
+            <span className={`tab-type-indicator unix ${enabled ? "broadcast" : ""}`} title="Socket console">
+                <svg width="10" height="10" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2.5">
+                    <rect x="2" y="3" width="20" height="14" rx="2" />
+                    <path d="M8 21h8M12 17v4" />
+                </svg>
+            </span>
+        );
+    }
     return (
         <span className={`tab-type-indicator local ${enabled ? "broadcast" : ""}`} title="Local terminal">
             <svg width="10" height="10" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2.5">
@@ -183,6 +228,7 @@ export function TerminalGrid() {
         reorderSessions,
     } = useTerminals();
     const [isConnectDialogOpen, setConnectDialogOpen] = useState(false);
+    const [isSessionsDialogOpen, setSessionsDialogOpen] = useState(false);
     const [isGroupMenuOpen, setGroupMenuOpen] = useState(false);
     const [newGroupName, setNewGroupName] = useState("");
     const [editingGroupId, setEditingGroupId] = useState<string | null>(null);
@@ -481,6 +527,14 @@ export function TerminalGrid() {
                             <span>{layoutMode === "tabs" ? "Grid" : "Tabs"}</span>
                         </button>
                     )}
+                    <button
+                        className="tab-action-btn"
+                        onClick={() => setSessionsDialogOpen(true)}
+                        title="Detach, list and attach background sessions"
+                    >
+                        <SessionsIcon />
+                        <span>Sessions</span>
+                    </button>
                     <button
                         className="tab-action-btn"
                         onClick={() => setConnectDialogOpen(true)}
@@ -638,6 +692,7 @@ export function TerminalGrid() {
 
             {/* Connect Dialog */}
             <ConnectDialog isOpen={isConnectDialogOpen} onClose={() => setConnectDialogOpen(false)} />
+            <SessionsDialog isOpen={isSessionsDialogOpen} onClose={() => setSessionsDialogOpen(false)} />
         </div>
     );
 }
\ No newline at end of file
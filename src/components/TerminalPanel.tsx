@@ -2,8 +2,9 @@
  * TerminalPanel Component
  * 
  * Renders an individual terminal session using xterm.js.
- * Supports local PTY sessions, telnet, and SSH connections.
+ * Supports local PTY sessions, telnet, SSH, serial, Unix socket, and raw TCP connections.
  * Handles communication, resize events, and user input.
+ * Sessions are opened in the session daemon instead when sessions are kept.
  */
 
 import { useEffect, useRef, useState, useCallback } from "react";
@@ -12,11 +13,13 @@ import { FitAddon } from "@xterm/addon-fit";
 import { WebLinksAddon } from "@xterm/addon-web-links";
 import { Unicode11Addon } from "@xterm/addon-unicode11";
 import { SearchAddon } from "@xterm/addon-search";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke, Channel } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useTerminals } from "../context/TerminalContext";
-import { TerminalSession, SshConnection } from "../types/terminal";
+import { TerminalSession, ConnectionType, SshConnection, SerialConnection, UnixSocketConnection, RawConnection, TelnetOptions, JumpHost, ForwardInfo } from "../types/terminal";
 import { highlightCiscoOutput } from "../utils/ciscoHighlight";
+import { formatForward } from "../utils/portForward";
+import { openSession } from "../utils/daemon";
 import "@xterm/xterm/css/xterm.css";
 
 // Track which sessions have been initialized to prevent double-spawning
@@ -31,6 +34,16 @@ const backendSessionIdMap = new Map<string, string>();
 // Store search addons globally for access from search UI
 const searchAddonMap = new Map<string, SearchAddon>();
 
+// Status event published by each kind of backend session
+const STATUS_EVENTS: Record<ConnectionType, string> = {
+  local: "pty-status",
+  telnet: "telnet-status",
+  ssh: "ssh-status",
+  serial: "serial-status",
+  unix: "socket-status",
+  raw: "socket-status",
+};
+
 // Store unlisten functions globally - these should NOT be called on React unmount
 // They should only be cleaned up when the session is actually closed
 const unlistenMap = new Map<string, { output?: () => void; status?: () => void }>();
@@ -208,23 +221,111 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
     searchAddonMap.set(session.id, searchAddon);
     setTerminal(session.id, terminal);
 
-    if (session.connectionType === "local") {
+    if (session.restoreSessionId) {
+      // Session that is already running in the backend
+      initRestoredSession(terminal, session.restoreSessionId);
+    } else if (session.connectionType === "local") {
       // Local PTY session
       initLocalSession(terminal);
     } else if (session.connectionType === "telnet" && session.telnetInfo) {
       // Telnet session to GNS3 device
-      initTelnetSession(terminal, session.telnetInfo.host, session.telnetInfo.port);
+      initTelnetSession(terminal, session.telnetInfo.host, session.telnetInfo.port, session.telnetInfo.options, session.telnetInfo.jumpHosts);
     } else if (session.connectionType === "ssh" && session.sshInfo) {
       // SSH session to device/server
       initSshSession(terminal, session.sshInfo);
+    } else if (session.connectionType === "serial" && session.serialInfo) {
+      // Serial console cable
+      initSerialSession(terminal, session.serialInfo);
+    } else if (session.connectionType === "unix" && session.unixInfo) {
+      // VM console on a Unix domain socket
+      initUnixSession(terminal, session.unixInfo);
+    } else if (session.connectionType === "raw" && session.rawInfo) {
+      // Raw TCP console, no telnet negotiation
+      initRawSession(terminal, session.rawInfo);
+    }
+
+    async function initRestoredSession(term: Terminal, backendId: string) {
+      try {
+        // Output now goes to this pane's channel instead of the one lost with the old page;
+        // it is held back until the history is painted
+        const onOutput = new Channel<string>();
+        const highlight = session.connectionType !== "local";
+        const show = (data: string) => term.write(highlight ? highlightCiscoOutput(data) : data);
+        let held: string[] | null = [];
+        onOutput.onmessage = (data) => (held ? held.push(data) : show(data));
+
+        // Repaint what the session printed before the pane was lost (or while detached)
+        let history: string;
+        if (session.attachToDaemon) {
+          history = await invoke<string>("daemon_attach", { sessionId: backendId, onOutput });
+        } else {
+          await invoke("subscribe_output", { sessionId: backendId, onOutput });
+          history = await invoke<string>("get_scrollback", { sessionId: backendId, maxBytes: null });
+        }
+        console.log(`[Terminal ${session.id}] Restored backend session ${backendId}`);
+        show(history);
+        held.forEach(show);
+        held = null;
+
+        sessionIdRef.current = backendId;
+        backendSessionIdMap.set(session.id, backendId);
+        setSessionId(session.id, backendId);
+
+        // Shells report "exited" with a ptyId, everything else uses sessionId
+        const unlistenStatus = await listen<{ sessionId?: string; ptyId?: string; status: string; message: string }>(
+          STATUS_EVENTS[session.connectionType],
+          (event) => {
+            if ((event.payload.sessionId ?? event.payload.ptyId) === backendId) {
+              if (event.payload.status === "disconnected" || event.payload.status === "exited") {
+                term.write(`\r\n\x1b[33m[Disconnected] ${event.payload.message}\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "disconnected");
+              } else if (event.payload.status === "error") {
+                term.write(`\r\n\x1b[31m[Error] ${event.payload.message}\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "error");
+              }
+            }
+          }
+        );
+
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
+
+        term.onData((data) => {
+          invoke("session_write", { sessionId: backendId, data }).catch(console.error);
+        });
+
+        term.onResize(({ cols, rows }) => {
+          invoke("session_resize", { sessionId: backendId, cols, rows }).catch(console.error);
+        });
+
+        // The new pane may not be the size the old one was
+        invoke("session_resize", { sessionId: backendId, cols: term.cols, rows: term.rows }).catch(console.error);
+        setIsDisconnected(false);
+        setConnectionState(session.id, "connected");
+      } catch (error) {
+        console.error(`[Terminal ${session.id}] Failed to restore session:`, error);
+        term.write(`\r\n\x1b[31mSession is no longer running: ${error}\x1b[0m\r\n`);
+        setIsDisconnected(true);
+        setConnectionState(session.id, "error");
+      }
     }
 
     async function initLocalSession(term: Terminal) {
       try {
-        const ptyId = await invoke<string>("spawn_pty", {
+        // Batched output arrives on a per-session channel
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(data);
+
+        const ptyId = await openSession("spawn_pty", {
           cols: term.cols,
           rows: term.rows,
-        });
+          command: session.localCommand ?? null,
+          onOutput,
+        }, { type: "pty", cols: term.cols, rows: term.rows, command: session.localCommand });
         console.log(`[Terminal ${session.id}] PTY spawned: ${ptyId}`);
 
         sessionIdRef.current = ptyId;
@@ -232,25 +333,33 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
         backendSessionIdMap.set(session.id, ptyId);
         setSessionId(session.id, ptyId);
 
+        // Report when the shell exits, with its exit code
         // Store unlisten globally - DO NOT clean up on React unmount
-        const unlistenOutput = await listen<{ ptyId: string; data: string }>(
-          "pty-output",
+        const unlistenStatus = await listen<{ ptyId: string; status: string; message: string; exitCode: number | null }>(
+          "pty-status",
           (event) => {
-            if (event.payload.ptyId === ptyId) {
-              term.write(event.payload.data);
+            if (event.payload.ptyId === ptyId && event.payload.status === "exited") {
+              const color = event.payload.exitCode === 0 ? "90" : "33";
+              term.write(`\r\n\x1b[${color}m[Process exited] ${event.payload.message}\x1b[0m\r\n`);
+              setIsDisconnected(true);
+              setConnectionState(session.id, "disconnected");
             }
           }
         );
-        unlistenMap.set(session.id, { output: unlistenOutput });
+
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
 
         // Use the ptyId directly (captured in closure) instead of ref
         // This ensures input works even after React remounts
         term.onData((data) => {
-          invoke("write_to_pty", { ptyId, data }).catch(console.error);
+          invoke("session_write", { sessionId: ptyId, data }).catch(console.error);
         });
 
         term.onResize(({ cols, rows }) => {
-          invoke("resize_pty", { ptyId, cols, rows }).catch(console.error);
+          invoke("session_resize", { sessionId: ptyId, cols, rows }).catch(console.error);
         });
       } catch (error) {
         console.error(`[Terminal ${session.id}] Failed to spawn PTY:`, error);
@@ -258,11 +367,24 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
       }
     }
 
-    async function initTelnetSession(term: Terminal, host: string, port: number) {
-      term.write(`\x1b[90mConnecting to ${host}:${port}...\x1b[0m\r\n`);
+    async function initTelnetSession(term: Terminal, host: string, port: number, options?: TelnetOptions, jumpHosts?: JumpHost[]) {
+      const via = jumpHosts?.length ? ` via ${jumpHosts.map((hop) => hop.host).join(", ")}` : "";
+      term.write(`\x1b[90mConnecting to ${host}:${port}${via}...\x1b[0m\r\n`);
 
       try {
-        const telnetSessionId = await invoke<string>("connect_telnet", { host, port });
+        // Batched output arrives on a per-session channel, with Cisco syntax highlighting
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(highlightCiscoOutput(data));
+
+        const telnetSessionId = await openSession("connect_telnet", {
+          host,
+          port,
+          cols: term.cols,
+          rows: term.rows,
+          options: options ?? null,
+          jumpHosts: jumpHosts?.length ? jumpHosts : null,
+          onOutput,
+        }, { type: "telnet", host, port, cols: term.cols, rows: term.rows, options, jumpHosts });
         console.log(`[Terminal ${session.id}] Telnet connected: ${telnetSessionId}`);
 
         sessionIdRef.current = telnetSessionId;
@@ -270,20 +392,8 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
         backendSessionIdMap.set(session.id, telnetSessionId);
         setSessionId(session.id, telnetSessionId);
 
-        // Listen for telnet output with Cisco syntax highlighting
-        // Store unlisten globally - DO NOT clean up on React unmount
-        const unlistenOutput = await listen<{ sessionId: string; data: string }>(
-          "telnet-output",
-          (event) => {
-            if (event.payload.sessionId === telnetSessionId) {
-              // Apply Cisco syntax highlighting to the output
-              const highlightedData = highlightCiscoOutput(event.payload.data);
-              term.write(highlightedData);
-            }
-          }
-        );
-
         // Listen for connection status updates
+        // Store unlisten globally - DO NOT clean up on React unmount
         const unlistenStatus = await listen<{ sessionId: string; status: string; message: string }>(
           "telnet-status",
           (event) => {
@@ -303,13 +413,21 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
         );
 
         // Store both unlisten functions globally
-        unlistenMap.set(session.id, { output: unlistenOutput, status: unlistenStatus });
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
 
         // Forward user input to telnet
         // Use telnetSessionId directly (captured in closure) instead of ref
         // This ensures input works even after React remounts
         term.onData((data) => {
-          invoke("write_telnet", { sessionId: telnetSessionId, data }).catch(console.error);
+          invoke("session_write", { sessionId: telnetSessionId, data }).catch(console.error);
+        });
+
+        // Report size changes to the device via NAWS
+        term.onResize(({ cols, rows }) => {
+          invoke("session_resize", { sessionId: telnetSessionId, cols, rows }).catch(console.error);
         });
 
         term.write(`\x1b[32mConnected to ${host}:${port}\x1b[0m\r\n\r\n`);
@@ -318,7 +436,7 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
 
         // Send initial Enter to get the prompt from the router
         setTimeout(() => {
-          invoke("write_telnet", { sessionId: telnetSessionId, data: "\r\n" }).catch(console.error);
+          invoke("session_write", { sessionId: telnetSessionId, data: "\r\n" }).catch(console.error);
         }, 500);
 
       } catch (error) {
@@ -332,23 +450,46 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
     }
 
     async function initSshSession(term: Terminal, sshInfo: SshConnection) {
-      term.write(`\x1b[90mConnecting via SSH to ${sshInfo.username}@${sshInfo.host}:${sshInfo.port}...\x1b[0m\r\n`);
+      // Missing user and port come from ~/.ssh/config
+      const target = `${sshInfo.username ? `${sshInfo.username}@` : ""}${sshInfo.host}${sshInfo.port ? `:${sshInfo.port}` : ""}`;
+      term.write(`\x1b[90mConnecting via SSH to ${target}...\x1b[0m\r\n`);
 
       try {
         // Build auth object based on auth type
         const auth = sshInfo.authType === "password"
           ? { type: "password" as const, password: sshInfo.password || "" }
+          : sshInfo.authType === "agent"
+          ? { type: "agent" as const }
+          : sshInfo.authType === "keyboard-interactive"
+          ? { type: "keyboard-interactive" as const }
+          : sshInfo.authType === "config"
+          ? { type: "config" as const }
           : { 
               type: "publickey" as const, 
               keyPath: sshInfo.keyPath || "~/.ssh/id_rsa",
               passphrase: sshInfo.passphrase || null 
             };
 
-        const sshSessionId = await invoke<string>("connect_ssh", {
+        // Batched output arrives on a per-session channel, with Cisco syntax highlighting
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(highlightCiscoOutput(data));
+
+        const sshSessionId = await openSession("connect_ssh", {
+          host: sshInfo.host,
+          port: sshInfo.port ?? null,
+          username: sshInfo.username || null,
+          auth,
+          jumpHosts: sshInfo.jumpHosts?.length ? sshInfo.jumpHosts : null,
+          cols: term.cols,
+          rows: term.rows,
+          onOutput,
+        }, {
+          type: "ssh",
           host: sshInfo.host,
           port: sshInfo.port,
-          username: sshInfo.username,
+          username: sshInfo.username || null,
           auth,
+          jumpHosts: sshInfo.jumpHosts,
           cols: term.cols,
           rows: term.rows,
         });
@@ -358,16 +499,15 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
         backendSessionIdMap.set(session.id, sshSessionId);
         setSessionId(session.id, sshSessionId);
 
-        // Listen for SSH output with Cisco syntax highlighting
-        const unlistenOutput = await listen<{ sessionId: string; data: string }>(
-          "ssh-output",
-          (event) => {
-            if (event.payload.sessionId === sshSessionId) {
-              const highlightedData = highlightCiscoOutput(event.payload.data);
-              term.write(highlightedData);
-            }
+        // Start the port forwards saved with the connection
+        for (const forward of sshInfo.forwards ?? []) {
+          try {
+            const info = await invoke<ForwardInfo>("add_ssh_forward", { sessionId: sshSessionId, forward });
+            term.write(`\x1b[90mForwarding ${formatForward(info)} (port ${info.boundPort})\x1b[0m\r\n`);
+          } catch (err) {
+            term.write(`\x1b[31mPort forward ${formatForward(forward)} failed: ${err}\x1b[0m\r\n`);
           }
-        );
+        }
 
         // Listen for connection status updates
         const unlistenStatus = await listen<{ sessionId: string; status: string; message: string }>(
@@ -388,19 +528,22 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
           }
         );
 
-        unlistenMap.set(session.id, { output: unlistenOutput, status: unlistenStatus });
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
 
         // Forward user input to SSH
         term.onData((data) => {
-          invoke("write_ssh", { sessionId: sshSessionId, data }).catch(console.error);
+          invoke("session_write", { sessionId: sshSessionId, data }).catch(console.error);
         });
 
         // Handle terminal resize
         term.onResize(({ cols, rows }) => {
-          invoke("resize_ssh", { sessionId: sshSessionId, cols, rows }).catch(console.error);
+          invoke("session_resize", { sessionId: sshSessionId, cols, rows }).catch(console.error);
         });
 
-        term.write(`\x1b[32mConnected to ${sshInfo.username}@${sshInfo.host}:${sshInfo.port}\x1b[0m\r\n\r\n`);
+        term.write(`\x1b[32mConnected to ${target}\x1b[0m\r\n\r\n`);
         setIsDisconnected(false);
         setConnectionState(session.id, "connected");
 
@@ -414,13 +557,217 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
       }
     }
 
+    async function initSerialSession(term: Terminal, serialInfo: SerialConnection) {
+      term.write(`\x1b[90mOpening ${serialInfo.path}...\x1b[0m\r\n`);
+
+      try {
+        // Batched output arrives on a per-session channel, with Cisco syntax highlighting
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(highlightCiscoOutput(data));
+
+        const serialSessionId = await openSession("connect_serial", {
+          path: serialInfo.path,
+          settings: serialInfo.settings ?? null,
+          onOutput,
+        }, { type: "serial", path: serialInfo.path, settings: serialInfo.settings });
+        console.log(`[Terminal ${session.id}] Serial opened: ${serialSessionId}`);
+
+        sessionIdRef.current = serialSessionId;
+        backendSessionIdMap.set(session.id, serialSessionId);
+        setSessionId(session.id, serialSessionId);
+
+        // Listen for the device going away (e.g. cable unplugged)
+        const unlistenStatus = await listen<{ sessionId: string; status: string; message: string }>(
+          "serial-status",
+          (event) => {
+            if (event.payload.sessionId === serialSessionId) {
+              if (event.payload.status === "disconnected") {
+                term.write(`\r\n\x1b[33m[Disconnected] ${event.payload.message}\x1b[0m\r\n`);
+                term.write(`\x1b[90mPress the reconnect button to reopen the port.\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "disconnected");
+              } else if (event.payload.status === "error") {
+                term.write(`\r\n\x1b[31m[Error] ${event.payload.message}\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "error");
+              }
+            }
+          }
+        );
+
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
+
+        // Forward user input to the port
+        term.onData((data) => {
+          invoke("session_write", { sessionId: serialSessionId, data }).catch(console.error);
+        });
+
+        const baudRate = serialInfo.settings?.baudRate ?? 9600;
+        term.write(`\x1b[32mOpened ${serialInfo.path} at ${baudRate} baud\x1b[0m\r\n\r\n`);
+        setIsDisconnected(false);
+        setConnectionState(session.id, "connected");
+
+        // Wake the console up so it prints a prompt
+        setTimeout(() => {
+          invoke("session_write", { sessionId: serialSessionId, data: "\r" }).catch(console.error);
+        }, 300);
+
+      } catch (error) {
+        console.error(`[Terminal ${session.id}] Failed to open serial port:`, error);
+        term.write(`\r\n\x1b[31mFailed to open serial port: ${error}\x1b[0m\r\n`);
+        term.write("\x1b[90mCheck the cable and that you have access to the device (e.g. the dialout group).\x1b[0m\r\n");
+        term.write(`\x1b[90mPress the reconnect button to try again.\x1b[0m\r\n`);
+        setIsDisconnected(true);
+        setConnectionState(session.id, "error");
+      }
+    }
+
+    async function initUnixSession(term: Terminal, unixInfo: UnixSocketConnection) {
+      term.write(`\x1b[90mConnecting to ${unixInfo.path}...\x1b[0m\r\n`);
+
+      try {
+        // Batched output arrives on a per-session channel, with Cisco syntax highlighting
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(highlightCiscoOutput(data));
+
+        const socketSessionId = await openSession("connect_unix_socket", {
+          path: unixInfo.path,
+          telnet: unixInfo.telnet,
+          cols: term.cols,
+          rows: term.rows,
+          options: unixInfo.options ?? null,
+          onOutput,
+        }, { type: "unix", path: unixInfo.path, telnet: unixInfo.telnet, cols: term.cols, rows: term.rows, options: unixInfo.options });
+        console.log(`[Terminal ${session.id}] Socket connected: ${socketSessionId}`);
+
+        sessionIdRef.current = socketSessionId;
+        backendSessionIdMap.set(session.id, socketSessionId);
+        setSessionId(session.id, socketSessionId);
+
+        // Telnet-framed sockets report status like any telnet session
+        const unlistenStatus = await listen<{ sessionId: string; status: string; message: string }>(
+          unixInfo.telnet ? "telnet-status" : "socket-status",
+          (event) => {
+            if (event.payload.sessionId === socketSessionId) {
+              if (event.payload.status === "disconnected") {
+                term.write(`\r\n\x1b[33m[Disconnected] ${event.payload.message}\x1b[0m\r\n`);
+                term.write(`\x1b[90mPress the reconnect button to reconnect.\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "disconnected");
+              } else if (event.payload.status === "error") {
+                term.write(`\r\n\x1b[31m[Error] ${event.payload.message}\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "error");
+              }
+            }
+          }
+        );
+
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
+
+        // Forward user input to the console
+        term.onData((data) => {
+          invoke("session_write", { sessionId: socketSessionId, data }).catch(console.error);
+        });
+
+        // Report size changes via NAWS (ignored on raw sockets)
+        term.onResize(({ cols, rows }) => {
+          invoke("session_resize", { sessionId: socketSessionId, cols, rows }).catch(console.error);
+        });
+
+        term.write(`\x1b[32mConnected to ${unixInfo.path}\x1b[0m\r\n\r\n`);
+        setIsDisconnected(false);
+        setConnectionState(session.id, "connected");
+
+        // Wake the console up so it prints a prompt
+        setTimeout(() => {
+          invoke("session_write", { sessionId: socketSessionId, data: "\r" }).catch(console.error);
+        }, 300);
+
+      } catch (error) {
+        console.error(`[Terminal ${session.id}] Socket connection failed:`, error);
+        term.write(`\r\n\x1b[31mConnection failed: ${error}\x1b[0m\r\n`);
+        term.write("\x1b[90mCheck that the VM is running and the socket path is correct.\x1b[0m\r\n");
+        term.write(`\x1b[90mPress the reconnect button to try again.\x1b[0m\r\n`);
+        setIsDisconnected(true);
+        setConnectionState(session.id, "error");
+      }
+    }
+
+    async function initRawSession(term: Terminal, rawInfo: RawConnection) {
+      term.write(`\x1b[90mConnecting to ${rawInfo.host}:${rawInfo.port} (raw TCP)...\x1b[0m\r\n`);
+
+      try {
+        // Batched output arrives on a per-session channel, with Cisco syntax highlighting
+        const onOutput = new Channel<string>();
+        onOutput.onmessage = (data) => term.write(highlightCiscoOutput(data));
+
+        const rawSessionId = await openSession("connect_raw", {
+          host: rawInfo.host,
+          port: rawInfo.port,
+          options: rawInfo.options ?? null,
+          onOutput,
+        }, { type: "raw", host: rawInfo.host, port: rawInfo.port, options: rawInfo.options });
+        console.log(`[Terminal ${session.id}] Raw TCP connected: ${rawSessionId}`);
+
+        sessionIdRef.current = rawSessionId;
+        backendSessionIdMap.set(session.id, rawSessionId);
+        setSessionId(session.id, rawSessionId);
+
+        const unlistenStatus = await listen<{ sessionId: string; status: string; message: string }>(
+          "socket-status",
+          (event) => {
+            if (event.payload.sessionId === rawSessionId) {
+              if (event.payload.status === "disconnected") {
+                term.write(`\r\n\x1b[33m[Disconnected] ${event.payload.message}\x1b[0m\r\n`);
+                term.write(`\x1b[90mPress the reconnect button to reconnect.\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "disconnected");
+              } else if (event.payload.status === "error") {
+                term.write(`\r\n\x1b[31m[Error] ${event.payload.message}\x1b[0m\r\n`);
+                setIsDisconnected(true);
+                setConnectionState(session.id, "error");
+              }
+            }
+          }
+        );
+
+        unlistenMap.set(session.id, {
+          output: () => { onOutput.onmessage = () => {}; },
+          status: unlistenStatus,
+        });
+
+        // Forward user input; line endings are translated by the backend
+        term.onData((data) => {
+          invoke("session_write", { sessionId: rawSessionId, data }).catch(console.error);
+        });
+
+        term.write(`\x1b[32mConnected to ${rawInfo.host}:${rawInfo.port}\x1b[0m\r\n\r\n`);
+        setIsDisconnected(false);
+        setConnectionState(session.id, "connected");
+
+      } catch (error) {
+        console.error(`[Terminal ${session.id}] Raw TCP connection failed:`, error);
+        term.write(`\r\n\x1b[31mConnection failed: ${error}\x1b[0m\r\n`);
+        term.write(`\x1b[90mPress the reconnect button to try again.\x1b[0m\r\n`);
+        setIsDisconnected(true);
+        setConnectionState(session.id, "error");
+      }
+    }
+
     // DO NOT clean up event listeners on React unmount!
     // The listeners need to persist across group changes.
     // They will be cleaned up when the session is removed from context.
     return () => {
       console.log(`[Terminal ${session.id}] Component unmounting (listeners preserved)`);
     };
-  }, [session.id, session.connectionType, session.telnetInfo, session.sshInfo]);
+  }, [session.id, session.connectionType, session.telnetInfo, session.sshInfo, session.serialInfo, session.unixInfo, session.rawInfo, session.localCommand, session.restoreSessionId, session.attachToDaemon]);
 
   // Handle window resize
   useEffect(() => {
@@ -449,7 +796,7 @@ export function TerminalPanel({ session, isActive }: TerminalPanelProps) {
   return (
     <div className="terminal-panel-simple">
       <div ref={terminalRef} className="terminal-container" />
-      {isDisconnected && session.connectionType !== "local" && (
+      {isDisconnected && session.connectionType !== "local" && !session.restoreSessionId && (
         <div className="terminal-reconnect-overlay">
           <button className="reconnect-btn" onClick={handleReconnect}>
             <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
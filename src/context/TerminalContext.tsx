@@ -3,6 +3,7 @@
  * 
  * Manages global state for all terminal sessions including:
  * - Session lifecycle (create, remove) for local and telnet connections
+ * - Detaching from and attaching to sessions kept in the session daemon
  * - Broadcast toggle state
  * - Session ID associations (PTY or telnet)
  * - Command broadcasting to multiple terminals
@@ -14,10 +15,12 @@
 import React, { createContext, useContext, useState, useCallback, useRef, useEffect } from "react";
 import { v4 as uuidv4 } from "uuid";
 import { Terminal } from "@xterm/xterm";
-import { TerminalSession, TerminalState, LayoutMode, TabGroup, SshConnection, ConnectionState } from "../types/terminal";
+import { TerminalSession, TerminalState, LayoutMode, TabGroup, SshConnection, SerialConnection, UnixSocketConnection, RawConnection, LocalCommand, TelnetOptions, JumpHost, ConnectionState, ConnectionType, BroadcastResult, SessionInfo, SessionKind } from "../types/terminal";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { cleanupTerminalSession } from "../components/TerminalPanel";
+import { applySavedScrollbackLimit } from "../utils/scrollback";
+import { initSessionDaemon } from "../utils/daemon";
 
 /** Predefined colors for groups */
 const GROUP_COLORS = [
@@ -31,6 +34,16 @@ const GROUP_COLORS = [
     "#e3b341", // Orange
 ];
 
+/** Tab connection type for each backend transport */
+const CONNECTION_TYPES: Record<SessionKind, ConnectionType> = {
+    pty: "local",
+    telnet: "telnet",
+    ssh: "ssh",
+    serial: "serial",
+    socket: "unix",
+    raw: "raw",
+};
+
 const TerminalContext = createContext<TerminalState | null>(null);
 
 /**
@@ -76,25 +89,27 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
 
         const checkCliConnection = async () => {
             try {
-                const connection = await invoke<{ name: string; host: string; port: number } | null>(
+                const connection = await invoke<{ name: string; host: string; port: number; raw?: boolean } | null>(
                     "get_cli_connection"
                 );
 
                 if (connection) {
                     console.log("[Startup] CLI connection requested:", connection);
-                    // Add telnet session from CLI args
+                    // Add telnet (or raw TCP) session from CLI args
                     const id = uuidv4();
+                    const target = { host: connection.host, port: connection.port };
                     const newSession: TerminalSession = {
                         id,
                         name: connection.name,
-                        connectionType: "telnet",
-                        telnetInfo: { host: connection.host, port: connection.port },
+                        ...(connection.raw
+                            ? { connectionType: "raw" as const, rawInfo: target }
+                            : { connectionType: "telnet" as const, telnetInfo: target }),
                         broadcastEnabled: true,
                         terminal: null,
                         sessionId: null,
                         groupId: null,
                     };
-                    setSessions([newSession]);
+                    setSessions((prev) => [...prev, newSession]);
                     setActiveSessionId(id);
                 }
             } catch (error) {
@@ -102,6 +117,33 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
             }
         };
 
+        // Sessions still running in the backend outlived a webview reload; give them panes again
+        const restoreRunningSessions = async () => {
+            try {
+                const running = await invoke<SessionInfo[]>("list_sessions");
+                if (running.length === 0) return;
+
+                console.log(`[Startup] Restoring ${running.length} running session(s)`);
+                const restored: TerminalSession[] = running.map((info) => ({
+                    id: uuidv4(),
+                    name: info.label,
+                    connectionType: CONNECTION_TYPES[info.kind],
+                    restoreSessionId: info.sessionId,
+                    broadcastEnabled: true,
+                    terminal: null,
+                    sessionId: null,
+                    groupId: null,
+                }));
+                setSessions((prev) => [...restored, ...prev]);
+                setActiveSessionId((current) => current ?? restored[0].id);
+            } catch (error) {
+                console.error("[Startup] Failed to list running sessions:", error);
+            }
+        };
+
+        applySavedScrollbackLimit();
+        initSessionDaemon();
+        restoreRunningSessions();
         checkCliConnection();
     }, []);
 
@@ -112,19 +154,20 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
      */
     useEffect(() => {
         const setupListener = async () => {
-            const unlisten = await listen<{ name: string; host: string; port: number }>(
+            const unlisten = await listen<{ name: string; host: string; port: number; raw?: boolean }>(
                 "new-connection",
                 (event) => {
                     console.log("[SingleInstance] New connection received:", event.payload);
-                    const { name, host, port } = event.payload;
+                    const { name, host, port, raw } = event.payload;
 
-                    // Create a new telnet session
+                    // Create a new telnet (or raw TCP) session
                     const id = uuidv4();
                     const newSession: TerminalSession = {
                         id,
                         name,
-                        connectionType: "telnet",
-                        telnetInfo: { host, port },
+                        ...(raw
+                            ? { connectionType: "raw" as const, rawInfo: { host, port } }
+                            : { connectionType: "telnet" as const, telnetInfo: { host, port } }),
                         broadcastEnabled: true,
                         terminal: null,
                         sessionId: null,
@@ -171,17 +214,38 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
         setActiveSessionId(id);
     }, [activeGroupId]);
 
+    /**
+     * Creates a new local session running a custom program
+     * (e.g. virsh console or docker exec) instead of the shell
+     */
+    const addCommandSession = useCallback((command: LocalCommand, name?: string) => {
+        const id = uuidv4();
+        const displayName = name || [command.program, ...(command.args ?? [])].join(" ");
+        const newSession: TerminalSession = {
+            id,
+            name: displayName,
+            connectionType: "local",
+            localCommand: command,
+            broadcastEnabled: true,
+            terminal: null,
+            sessionId: null,
+            groupId: activeGroupId,
+        };
+        setSessions((prev) => [...prev, newSession]);
+        setActiveSessionId(id);
+    }, [activeGroupId]);
+
     /**
      * Creates a new telnet session to a GNS3 device
      */
-    const addTelnetSession = useCallback((host: string, port: number, name?: string) => {
+    const addTelnetSession = useCallback((host: string, port: number, name?: string, options?: TelnetOptions, jumpHosts?: JumpHost[]) => {
         const id = uuidv4();
         const displayName = name || `${host}:${port}`;
         const newSession: TerminalSession = {
             id,
             name: displayName,
             connectionType: "telnet",
-            telnetInfo: { host, port },
+            telnetInfo: { host, port, options, jumpHosts },
             broadcastEnabled: true,
             terminal: null,
             sessionId: null,
@@ -196,7 +260,7 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
      */
     const addSshSession = useCallback((connection: SshConnection, name?: string) => {
         const id = uuidv4();
-        const displayName = name || `${connection.username}@${connection.host}`;
+        const displayName = name || (connection.username ? `${connection.username}@${connection.host}` : connection.host);
         const newSession: TerminalSession = {
             id,
             name: displayName,
@@ -212,20 +276,74 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
     }, [activeGroupId]);
 
     /**
-     * Removes a terminal session and cleans up its backend connection
+     * Creates a new session on a local serial port
      */
-    const removeSession = useCallback((id: string) => {
+    const addSerialSession = useCallback((connection: SerialConnection, name?: string) => {
+        const id = uuidv4();
+        const displayName = name || connection.path;
+        const newSession: TerminalSession = {
+            id,
+            name: displayName,
+            connectionType: "serial",
+            serialInfo: connection,
+            broadcastEnabled: true,
+            terminal: null,
+            sessionId: null,
+            groupId: activeGroupId,
+        };
+        setSessions((prev) => [...prev, newSession]);
+        setActiveSessionId(id);
+    }, [activeGroupId]);
+
+    /**
+     * Creates a new session on a console's Unix domain socket
+     */
+    const addUnixSession = useCallback((connection: UnixSocketConnection, name?: string) => {
+        const id = uuidv4();
+        const displayName = name || connection.path.split("/").pop() || connection.path;
+        const newSession: TerminalSession = {
+            id,
+            name: displayName,
+            connectionType: "unix",
+            unixInfo: connection,
+            broadcastEnabled: true,
+            terminal: null,
+            sessionId: null,
+            groupId: activeGroupId,
+        };
+        setSessions((prev) => [...prev, newSession]);
+        setActiveSessionId(id);
+    }, [activeGroupId]);
+
+    /**
+     * Creates a new raw TCP session
+     */
+    const addRawSession = useCallback((connection: RawConnection, name?: string) => {
+        const id = uuidv4();
+        const displayName = name || `${connection.host}:${connection.port}`;
+        const newSession: TerminalSession = {
+            id,
+            name: displayName,
+            connectionType: "raw",
+            rawInfo: connection,
+            broadcastEnabled: true,
+            terminal: null,
+            sessionId: null,
+            groupId: activeGroupId,
+        };
+        setSessions((prev) => [...prev, newSession]);
+        setActiveSessionId(id);
+    }, [activeGroupId]);
+
+    /**
+     * Removes a terminal pane, closing its backend session unless it was detached
+     */
+    const dropSession = useCallback((id: string, closeBackend: boolean) => {
         setSessions((prev) => {
             const session = prev.find((s) => s.id === id);
-            if (session?.sessionId) {
-                // Clean up backend session based on connection type
-                if (session.connectionType === "local") {
-                    invoke("kill_pty", { ptyId: session.sessionId }).catch(console.error);
-                } else if (session.connectionType === "telnet") {
-                    invoke("disconnect_telnet", { sessionId: session.sessionId }).catch(console.error);
-                } else if (session.connectionType === "ssh") {
-                    invoke("disconnect_ssh", { sessionId: session.sessionId }).catch(console.error);
-                }
+            if (session?.sessionId && closeBackend) {
+                // Clean up backend session (works for any connection type)
+                invoke("session_close", { sessionId: session.sessionId }).catch(console.error);
             }
 
             // Clean up frontend terminal state (event listeners, etc.)
@@ -246,6 +364,50 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
         });
     }, [activeSessionId]);
 
+    /**
+     * Removes a terminal session and cleans up its backend connection
+     */
+    const removeSession = useCallback((id: string) => dropSession(id, true), [dropSession]);
+
+    /**
+     * Detaches a pane from its daemon session; the session keeps running and
+     * can be attached to again from the Sessions dialog
+     */
+    const detachSession = useCallback(async (id: string) => {
+        const session = sessionsRef.current.find((s) => s.id === id);
+        if (!session?.sessionId) {
+            throw new Error("Terminal is not connected");
+        }
+        await invoke("daemon_detach", { sessionId: session.sessionId });
+        dropSession(id, false);
+    }, [dropSession]);
+
+    /**
+     * Opens a pane attached to a daemon session, or shows the pane that already is
+     */
+    const attachDaemonSession = useCallback((info: SessionInfo) => {
+        const existing = sessionsRef.current.find((s) => s.sessionId === info.sessionId || s.restoreSessionId === info.sessionId);
+        if (existing) {
+            setActiveSessionId(existing.id);
+            return;
+        }
+
+        const id = uuidv4();
+        const newSession: TerminalSession = {
+            id,
+            name: info.label,
+            connectionType: CONNECTION_TYPES[info.kind],
+            restoreSessionId: info.sessionId,
+            attachToDaemon: true,
+            broadcastEnabled: true,
+            terminal: null,
+            sessionId: null,
+            groupId: activeGroupId,
+        };
+        setSessions((prev) => [...prev, newSession]);
+        setActiveSessionId(id);
+    }, [activeGroupId]);
+
     /**
      * Toggles whether a terminal receives broadcast commands
      */
@@ -293,30 +455,29 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
     const broadcastKeystroke = useCallback((key: string, groupId?: string | null) => {
         const currentSessions = sessionsRef.current;
 
-        currentSessions.forEach((session) => {
+        const targets = currentSessions.filter((session) => {
             // Check if session is broadcast enabled
-            if (!session.broadcastEnabled || !session.sessionId) return;
+            if (!session.broadcastEnabled || !session.sessionId) return false;
 
             // If groupId is provided (not undefined), filter by group
-            if (groupId !== undefined && session.groupId !== groupId) return;
-
-            if (session.connectionType === "local") {
-                invoke("write_to_pty", {
-                    ptyId: session.sessionId,
-                    data: key,
-                }).catch((err) => console.error(`[Broadcast] Failed to send to ${session.name}:`, err));
-            } else if (session.connectionType === "telnet") {
-                invoke("write_telnet", {
-                    sessionId: session.sessionId,
-                    data: key,
-                }).catch((err) => console.error(`[Broadcast] Failed to send to ${session.name}:`, err));
-            } else if (session.connectionType === "ssh") {
-                invoke("write_ssh", {
-                    sessionId: session.sessionId,
-                    data: key,
-                }).catch((err) => console.error(`[Broadcast] Failed to send to ${session.name}:`, err));
-            }
+            return groupId === undefined || session.groupId === groupId;
         });
+        if (targets.length === 0) return;
+
+        // Fan out in the backend with a single IPC call
+        invoke<BroadcastResult[]>("broadcast_write", {
+            sessionIds: targets.map((session) => session.sessionId),
+            data: key,
+        })
+            .then((results) => {
+                results
+                    .filter((result) => !result.success)
+                    .forEach((result) => {
+                        const session = targets.find((s) => s.sessionId === result.sessionId);
+                        console.error(`[Broadcast] Failed to send to ${session?.name ?? result.sessionId}:`, result.error);
+                    });
+            })
+            .catch((err) => console.error("[Broadcast] Broadcast failed:", err));
     }, []);
 
     /**
@@ -426,11 +587,7 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
 
             // Clean up old backend session
             if (session.sessionId) {
-                if (session.connectionType === "telnet") {
-                    invoke("disconnect_telnet", { sessionId: session.sessionId }).catch(() => {});
-                } else if (session.connectionType === "ssh") {
-                    invoke("disconnect_ssh", { sessionId: session.sessionId }).catch(() => {});
-                }
+                invoke("session_close", { sessionId: session.sessionId }).catch(() => {});
             }
 
             // Clean up frontend terminal state to allow re-initialization
@@ -464,9 +621,15 @@ export function TerminalProvider({ children }: TerminalProviderProps) {
         activeGroupId,
         layoutMode,
         addSession,
+        addCommandSession,
         addTelnetSession,
         addSshSession,
+        addSerialSession,
+        addUnixSession,
+        addRawSession,
         removeSession,
+        attachDaemonSession,
+        detachSession,
         setActiveSession,
         toggleBroadcast,
         updateSessionName,
@@ -12,33 +12,144 @@ import { Terminal } from "@xterm/xterm";
 /**
  * Connection type for terminal sessions
  */
-export type ConnectionType = "local" | "telnet" | "ssh";
+export type ConnectionType = "local" | "telnet" | "ssh" | "serial" | "unix" | "raw";
 
 /**
  * Split direction for grid layouts
  */
 export type SplitDirection = "horizontal" | "vertical";
 
+/**
+ * How a bare CR (the Enter key) is sent to a telnet server
+ */
+export type TelnetNewline = "crnul" | "crlf";
+
+/**
+ * Per-session telnet settings
+ */
+export interface TelnetOptions {
+    /** Defaults to CR NUL */
+    newline?: TelnetNewline;
+    /** Offer RFC 2217 serial port control (ser2net, Opengear, Moxa) */
+    comPort?: boolean;
+    /** Wrap the connection in TLS (telnets); plain telnet when omitted */
+    tls?: TelnetTls | null;
+}
+
+/**
+ * Certificate checks for a telnets connection
+ */
+export interface TelnetTls {
+    /** Accept self-signed, expired or mismatched certificates */
+    insecure?: boolean;
+    /** PEM file with an extra CA certificate to trust */
+    caFile?: string;
+    /** Name to verify the certificate against, when it differs from the host */
+    serverName?: string;
+}
+
+/**
+ * Serial port settings; omitted fields are left unchanged
+ */
+export interface ComPortSettings {
+    baudRate?: number;
+    dataBits?: number;
+    parity?: "none" | "odd" | "even" | "mark" | "space";
+    stopBits?: "1" | "1.5" | "2";
+    flowControl?: "none" | "software" | "hardware";
+}
+
+/**
+ * Serial port state of a telnet session as reported by the server (RFC 2217)
+ */
+export interface ComPortStatus {
+    enabled: boolean;
+    settings: ComPortSettings;
+    modem?: { cd: boolean; ri: boolean; dsr: boolean; cts: boolean } | null;
+    lineState?: number | null;
+}
+
 /**
  * Telnet connection parameters for GNS3 devices
  */
 export interface TelnetConnection {
     host: string;
     port: number;
+    options?: TelnetOptions;
+    /** SSH bastions to tunnel through, first to last */
+    jumpHosts?: JumpHost[];
+}
+
+/**
+ * Local serial port parameters (e.g. a USB console cable)
+ */
+export interface SerialConnection {
+    /** Device path, e.g. /dev/ttyUSB0 */
+    path: string;
+    /** Line settings; unset fields default to 9600 8N1 */
+    settings?: ComPortSettings;
+}
+
+/**
+ * Console on a Unix domain socket (QEMU, libvirt, GNS3)
+ */
+export interface UnixSocketConnection {
+    /** Socket path, e.g. /tmp/r1.sock */
+    path: string;
+    /** The socket speaks telnet (QEMU's telnet flag) rather than raw bytes */
+    telnet: boolean;
+    /** Telnet settings, used only with telnet framing */
+    options?: TelnetOptions;
+}
+
+/**
+ * Line ending sent for the Enter key on a raw TCP connection
+ */
+export type RawNewline = "cr" | "lf" | "crlf";
+
+/**
+ * Line ending translation for raw TCP connections
+ */
+export interface RawOptions {
+    /** Defaults to CR (sent as typed) */
+    newline?: RawNewline;
+    /** Show a bare LF from the remote end as CR LF */
+    lfToCrlf?: boolean;
+}
+
+/**
+ * Raw TCP connection with no telnet processing
+ */
+export interface RawConnection {
+    host: string;
+    port: number;
+    options?: RawOptions;
+}
+
+/**
+ * A serial device found on this machine
+ */
+export interface SerialDevice {
+    path: string;
+    kind: "usb" | "pci" | "bluetooth" | "unknown";
+    description?: string | null;
 }
 
 /**
  * SSH authentication method
  */
-export type SshAuthType = "password" | "publickey";
+export type SshAuthType = "password" | "publickey" | "agent" | "keyboard-interactive" | "config";
 
 /**
  * SSH connection parameters
  */
 export interface SshConnection {
+    /** Hostname, address or ~/.ssh/config alias */
     host: string;
-    port: number;
-    username: string;
+    /** Taken from ~/.ssh/config (or 22) when missing */
+    port?: number;
+    /** Taken from ~/.ssh/config (or the local user) when missing */
+    username?: string;
     authType: SshAuthType;
     /** Password for password auth */
     password?: string;
@@ -46,6 +157,136 @@ export interface SshConnection {
     keyPath?: string;
     /** Passphrase for encrypted private keys */
     passphrase?: string;
+    /** SSH bastions to tunnel through; the ProxyJump from ~/.ssh/config when missing */
+    jumpHosts?: JumpHost[];
+    /** Port forwards to start once connected */
+    forwards?: ForwardSpec[];
+}
+
+/**
+ * An SSH bastion on the way to a host (ProxyJump)
+ */
+export interface JumpHost {
+    /** Hostname, address or ~/.ssh/config alias */
+    host: string;
+    /** Taken from ~/.ssh/config (or 22) when missing */
+    port?: number | null;
+    /** Taken from ~/.ssh/config (or the local user) when missing */
+    username?: string | null;
+    /** Keys from ~/.ssh/config and the ssh-agent when missing */
+    auth?: { type: "config" | "agent" | "keyboard-interactive" };
+}
+
+/**
+ * Kind of SSH port forward, after OpenSSH's -L, -R and -D
+ */
+export type ForwardKind = "local" | "remote" | "dynamic";
+
+/**
+ * An SSH port forward
+ */
+export interface ForwardSpec {
+    kind: ForwardKind;
+    /** Address to listen on (on the server for remote forwards); loopback when missing */
+    bindAddress?: string | null;
+    /** Port to listen on; 0 picks a free one */
+    bindPort: number;
+    /** Where connections are sent; unused by dynamic (SOCKS) forwards */
+    destHost?: string | null;
+    destPort?: number | null;
+}
+
+/**
+ * A running port forward with its counters (list_ssh_forwards)
+ */
+export interface ForwardInfo extends ForwardSpec {
+    id: string;
+    /** Port actually listened on */
+    boundPort: number;
+    activeConnections: number;
+    totalConnections: number;
+    /** Bytes sent into the tunnel */
+    bytesSent: number;
+    /** Bytes that came out of the tunnel */
+    bytesReceived: number;
+}
+
+/**
+ * One round of keyboard-interactive prompts from an SSH server (ssh-auth-prompt event)
+ */
+export interface AuthPrompt {
+    requestId: string;
+    host: string;
+    port: number;
+    username: string;
+    /** Title for this round, often empty */
+    name: string;
+    instructions: string;
+    /** Questions to answer in order; echo is false for secrets. Empty for a notice that needs no answer */
+    prompts: { text: string; echo: boolean }[];
+    /** 1 for the first round, counting up on multi-round logins */
+    round: number;
+}
+
+/**
+ * Effective ~/.ssh/config settings for a host alias (resolve_ssh_config)
+ */
+export interface SshHostConfig {
+    alias: string;
+    hostname: string;
+    user: string | null;
+    port: number | null;
+    identityFiles: string[];
+    identitiesOnly: boolean;
+    proxyJump: string | null;
+    serverAliveInterval: number | null;
+}
+
+/**
+ * A key held by the ssh-agent (list_agent_identities)
+ */
+export interface AgentIdentity {
+    comment: string;
+    keyType: string;
+    fingerprint: string;
+}
+
+/**
+ * Program to run in a local PTY instead of the default shell
+ * (e.g. virsh console, docker exec -it, kubectl exec)
+ */
+export interface LocalCommand {
+    /** Program to run, looked up in PATH (defaults to $SHELL) */
+    program?: string;
+    args?: string[];
+    /** Working directory (supports ~) */
+    cwd?: string;
+    /** Extra environment variables */
+    env?: Record<string, string>;
+}
+
+/**
+ * An unknown SSH host key awaiting a trust decision (ssh-host-key event)
+ */
+export interface HostKeyPrompt {
+    requestId: string;
+    host: string;
+    port: number;
+    keyType: string;
+    /** SHA256 fingerprint, as printed by ssh-keygen -l */
+    fingerprint: string;
+    /** A key of another type is already known for this host */
+    otherKeyKnown: boolean;
+}
+
+/**
+ * A trusted host key from ~/.ssh/known_hosts or Packet's own store
+ */
+export interface KnownHostEntry {
+    hosts: string[];
+    keyType: string;
+    fingerprint: string;
+    source: "openssh" | "packet";
 }
 
 /**
@@ -56,18 +297,57 @@ export interface LogFileInfo {
     startedAt: string;
 }
 
+/**
+ * Transport behind a backend session
+ */
+export type SessionKind = "pty" | "telnet" | "ssh" | "serial" | "socket" | "raw";
+
+/**
+ * A session running in the backend (list_sessions)
+ */
+export interface SessionInfo {
+    sessionId: string;
+    kind: SessionKind;
+    /** Human readable target, e.g. /bin/bash or admin@core1:22 */
+    label: string;
+    status: "connected" | "disconnected" | "error";
+}
+
+/**
+ * A session hosted by the session daemon (daemon_list_sessions)
+ */
+export interface DaemonSessionInfo extends SessionInfo {
+    /** Whether a window is attached; false means orphaned */
+    attached: boolean;
+}
+
+/**
+ * Per-session outcome of a backend broadcast write
+ */
+export interface BroadcastResult {
+    sessionId: string;
+    success: boolean;
+    error?: string | null;
+}
+
 /**
  * A saved connection profile
  */
 export interface ConnectionProfile {
     id: string;
     name: string;
-    connection_type: "ssh" | "telnet";
+    connection_type: "ssh" | "telnet" | "local" | "serial" | "unix" | "raw";
     host: string;
     port: number;
     username?: string;
-    auth_method?: "password" | "publickey";
+    auth_method?: SshAuthType;
     key_path?: string;
+    telnet_options?: TelnetOptions | null;
+    command?: LocalCommand | null;
+    serial_settings?: ComPortSettings | null;
+    raw_options?: RawOptions | null;
+    jump_hosts?: JumpHost[] | null;
+    forwards?: ForwardSpec[] | null;
     created_at: string;
     updated_at: string;
 }
@@ -159,7 +439,7 @@ export interface TerminalSession {
     /** User-editable display name for the terminal tab */
     name: string;
 
-    /** Connection type: local shell, telnet, or SSH */
+    /** Connection type: local shell, telnet, SSH, serial, Unix socket, or raw TCP */
     connectionType: ConnectionType;
 
     /** Telnet connection info (only for telnet sessions) */
@@ -168,13 +448,31 @@ export interface TerminalSession {
     /** SSH connection info (only for SSH sessions) */
     sshInfo?: SshConnection;
 
+    /** Serial port info (only for serial sessions) */
+    serialInfo?: SerialConnection;
+
+    /** Socket info (only for Unix socket sessions) */
+    unixInfo?: UnixSocketConnection;
+
+    /** Raw TCP info (only for raw sessions) */
+    rawInfo?: RawConnection;
+
+    /** Custom program (only for local sessions; omitted runs the default shell) */
+    localCommand?: LocalCommand;
+
+    /** Running backend session to take over instead of connecting (e.g. after a webview reload) */
+    restoreSessionId?: string;
+
+    /** restoreSessionId is a daemon session to attach to */
+    attachToDaemon?: boolean;
+
     /** Whether this terminal receives broadcast commands */
     broadcastEnabled: boolean;
 
     /** Reference to the xterm.js Terminal instance (null until mounted) */
     terminal: Terminal | null;
 
-    /** Backend session identifier - PTY ID, telnet, or SSH session ID (null until connected) */
+    /** Backend session identifier - PTY ID, telnet, SSH, serial, or socket session ID (null until connected) */
     sessionId: string | null;
 
     /** Group ID this session belongs to (null for ungrouped) */
@@ -211,15 +509,33 @@ export interface TerminalState {
     /** Creates a new local terminal session */
     addSession: (groupId?: string | null) => void;
 
+    /** Creates a new local session running a custom program */
+    addCommandSession: (command: LocalCommand, name?: string) => void;
+
     /** Creates a new telnet session to a GNS3 device */
-    addTelnetSession: (host: string, port: number, name?: string) => void;
+    addTelnetSession: (host: string, port: number, name?: string, options?: TelnetOptions, jumpHosts?: JumpHost[]) => void;
 
     /** Creates a new SSH session to a device/server */
     addSshSession: (connection: SshConnection, name?: string) => void;
 
+    /** Creates a new session on a local serial port */
+    addSerialSession: (connection: SerialConnection, name?: string) => void;
+
+    /** Creates a new session on a console's Unix domain socket */
+    addUnixSession: (connection: UnixSocketConnection, name?: string) => void;
+
+    /** Creates a new raw TCP session */
+    addRawSession: (connection: RawConnection, name?: string) => void;
+
     /** Removes and cleans up a terminal session by ID */
     removeSession: (id: string) => void;
 
+    /** Opens a pane attached to a session running in the session daemon */
+    attachDaemonSession: (info: SessionInfo) => void;
+
+    /** Detaches a pane from its daemon session and removes it, leaving the session running */
+    detachSession: (id: string) => Promise<void>;
+
     /** Sets the active terminal session */
     setActiveSession: (id: string) => void;
 
@@ -0,0 +1,97 @@
+/**
+ * Session Daemon
+ *
+ * With "keep sessions running" on, new sessions are opened inside the
+ * session daemon (packet --daemon) instead of this window, so they survive
+ * closing it. Panes can detach from a daemon session and attach to it again
+ * later, from this window or the next one.
+ */
+
+import { invoke, Channel } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { DaemonSessionInfo } from "../types/terminal";
+
+const STORAGE_KEY = "packet.keepSessions";
+
+/** Resolves to whether the daemon is running; reset when the link drops */
+let daemonReady: Promise<boolean> | null = null;
+
+/**
+ * A session for daemon_open_session, tagged by transport
+ */
+export type OpenSessionSpec = { type: "pty" | "telnet" | "ssh" | "serial" | "unix" | "raw" } & Record<string, unknown>;
+
+/**
+ * Whether new sessions should outlive the window
+ */
+export function keepSessionsEnabled(): boolean {
+    return localStorage.getItem(STORAGE_KEY) === "true";
+}
+
+/**
+ * Turns keeping sessions on or off for sessions opened from now on
+ */
+export async function setKeepSessions(enabled: boolean): Promise<void> {
+    localStorage.setItem(STORAGE_KEY, String(enabled));
+    if (enabled && !(await ensureDaemon())) {
+        throw new Error("Session daemon could not be started");
+    }
+}
+
+/**
+ * Starts the daemon if sessions are kept and it is not running yet
+ */
+export function ensureDaemon(): Promise<boolean> {
+    if (!keepSessionsEnabled()) return Promise.resolve(false);
+    if (!daemonReady) {
+        daemonReady = invoke("daemon_start")
+            .then(() => true)
+            .catch((err) => {
+                console.error("[Daemon] Failed to start session daemon:", err);
+                daemonReady = null;
+                return false;
+            });
+    }
+    return daemonReady;
+}
+
+/**
+ * Starts the daemon on startup and notices when the link to it drops
+ */
+export function initSessionDaemon(): void {
+    listen<{ connected: boolean }>("daemon-status", (event) => {
+        if (!event.payload.connected) {
+            console.warn("[Daemon] Lost connection to session daemon");
+            daemonReady = null;
+        }
+    });
+    ensureDaemon();
+}
+
+/**
+ * Opens a session in the daemon when sessions are kept, otherwise in this
+ * window with `command`. Both return the backend session ID.
+ */
+export async function openSession(
+    command: string,
+    args: Record<string, unknown> & { onOutput: Channel<string> },
+    spec: OpenSessionSpec
+): Promise<string> {
+    if (!(await ensureDaemon())) {
+        return invoke<string>(command, args);
+    }
+    // Missing fields take the backend defaults; null would not
+    const session = Object.fromEntries(Object.entries(spec).filter(([, value]) => value != null));
+    return invoke<string>("daemon_open_session", { session, onOutput: args.onOutput });
+}
+
+/**
+ * Lists the daemon's sessions, or none when it is not running. Sessions
+ * kept earlier are listed even after keeping was turned off.
+ */
+export function listDaemonSessions(): Promise<DaemonSessionInfo[]> {
+    return invoke<DaemonSessionInfo[]>("daemon_list_sessions").catch((err) => {
+        console.log("[Daemon] No sessions to list:", err);
+        return [];
+    });
+}
@@ -0,0 +1,105 @@
+/**
+ * SSH Port Forward Syntax
+ *
+ * Reads and writes port forwards in the form of OpenSSH's command line
+ * options, as used in profiles and the :forward command:
+ *   -L [bind:]port:host:hostport   local forward
+ *   -R [bind:]port:host:hostport   remote forward
+ *   -D [bind:]port                 dynamic (SOCKS) forward
+ * The leading dash is optional and IPv6 addresses go in brackets.
+ */
+
+import { ForwardKind, ForwardSpec } from "../types/terminal";
+
+const KINDS: Record<string, ForwardKind> = { l: "local", r: "remote", d: "dynamic" };
+const FLAGS: Record<ForwardKind, string> = { local: "L", remote: "R", dynamic: "D" };
+
+/**
+ * Splits "a:b:[2001:db8::1]:c" on colons outside brackets
+ */
+function splitAddress(input: string): string[] {
+    const parts: string[] = [];
+    let current = "";
+    let bracket = false;
+    for (const ch of input) {
+        if (ch === "[" && current === "") {
+            bracket = true;
+        } else if (ch === "]" && bracket) {
+            bracket = false;
+        } else if (ch === ":" && !bracket) {
+            parts.push(current);
+            current = "";
+        } else {
+            current += ch;
+        }
+    }
+    parts.push(current);
+    return parts;
+}
+
+function parsePort(input: string | undefined): number | null {
+    if (!input || !/^\d+$/.test(input)) return null;
+    const port = parseInt(input, 10);
+    return port <= 65535 ? port : null;
+}
+
+/**
+ * Parses a forward such as "-L 8443:10.0.0.1:443"; null when malformed
+ */
+export function parseForward(input: string): ForwardSpec | null {
+    const match = input.trim().match(/^-?([LRD])\s*(\S+)$/i);
+    if (!match) return null;
+    const kind = KINDS[match[1].toLowerCase()];
+    const parts = splitAddress(match[2]);
+
+    if (kind === "dynamic") {
+        if (parts.length > 2) return null;
+        const bindPort = parsePort(parts[parts.length - 1]);
+        if (bindPort === null) return null;
+        return { kind, bindAddress: parts.length === 2 ? parts[0] : null, bindPort };
+    }
+
+    if (parts.length !== 3 && parts.length !== 4) return null;
+    const [bindAddress, bindPort, destHost, destPort] = parts.length === 4 ? parts : [null, ...parts];
+    const port = parsePort(bindPort ?? undefined);
+    const dest = parsePort(destPort ?? undefined);
+    if (port === null || !dest || !destHost) return null;
+    return { kind, bindAddress, bindPort: port, destHost, destPort: dest };
+}
+
+/**
+ * Parses one forward per line, skipping blank lines; throws on the first bad line
+ */
+export function parseForwardList(input: string): ForwardSpec[] {
+    return input.split("\n").filter(line => line.trim()).map(line => {
+        const spec = parseForward(line);
+        if (!spec) {
+            throw new Error(`Invalid port forward: ${line.trim()}`);
+        }
+        return spec;
+    });
+}
+
+/**
+ * Formats a forward the way parseForward reads it
+ */
+export function formatForward(spec: ForwardSpec): string {
+    const bracketed = (host: string) => (host.includes(":") ? `[${host}]` : host);
+    const bind = spec.bindAddress ? `${bracketed(spec.bindAddress)}:${spec.bindPort}` : `${spec.bindPort}`;
+    const dest = spec.kind === "dynamic" ? "" : `:${bracketed(spec.destHost ?? "")}:${spec.destPort ?? ""}`;
+    return `-${FLAGS[spec.kind]} ${bind}${dest}`;
+}
+
+/**
+ * Formats a byte count for display, e.g. 1.5 MB
+ */
+export function formatBytes(bytes: number): string {
+    const units = ["B", "KB", "MB", "GB"];
+    let value = bytes;
+    let unit = 0;
+    while (value >= 1024 && unit < units.length - 1) {
+        value /= 1024;
+        unit++;
+    }
+    return unit === 0 ? `${value} ${units[unit]}` : `${value.toFixed(1)} ${units[unit]}`;
+}
@@ -0,0 +1,43 @@
+/**
+ * Scrollback Settings
+ *
+ * The backend keeps a capped copy of each session's recent output, used to
+ * repaint panes that lost their history. The cap is set with :scrollback
+ * and remembered here, since the backend starts with its 1 MiB default.
+ */
+
+import { invoke } from "@tauri-apps/api/core";
+
+const STORAGE_KEY = "packet.scrollbackBytes";
+
+const UNITS: Record<string, number> = { "": 1, b: 1, k: 1024, kb: 1024, m: 1024 * 1024, mb: 1024 * 1024 };
+
+/**
+ * Parses a size such as "65536", "512k" or "2MB"; null when malformed
+ */
+export function parseByteSize(input: string): number | null {
+    const match = input.trim().toLowerCase().match(/^(\d+(?:\.\d+)?)\s*([a-z]*)$/);
+    if (!match || !(match[2] in UNITS)) return null;
+    const bytes = Math.floor(parseFloat(match[1]) * UNITS[match[2]]);
+    return bytes > 0 ? bytes : null;
+}
+
+/**
+ * Sets the cap for new sessions and for the listed running ones, and saves it
+ */
+export async function setScrollbackLimit(maxBytes: number, sessionIds: string[]): Promise<void> {
+    localStorage.setItem(STORAGE_KEY, String(maxBytes));
+    await invoke("set_scrollback_limit", { sessionId: null, maxBytes });
+    await Promise.all(sessionIds.map((sessionId) => invoke("set_scrollback_limit", { sessionId, maxBytes })));
+}
+
+/**
+ * Applies the saved cap to the backend (called once on startup)
+ */
+export function applySavedScrollbackLimit(): void {
+    const maxBytes = Number(localStorage.getItem(STORAGE_KEY));
+    if (maxBytes > 0) {
+        invoke("set_scrollback_limit", { sessionId: null, maxBytes })
+            .catch((err) => console.error("[Scrollback] Failed to apply saved limit:", err));
+    }
+}